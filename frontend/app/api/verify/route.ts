@@ -1,15 +1,27 @@
 import { NextRequest, NextResponse } from "next/server";
-import { submitAddressesForProof, getProofStatus } from "@/lib/risc0";
+import {
+  submitAddressesForProof,
+  getProofStatus,
+  getRoundMarkets,
+} from "@/lib/risc0";
 import { verifyProofWithZkVerify } from "@/lib/zkVerify";
 import { Contract, ethers } from "ethers";
 import { abi as ContractABI } from "@/lib/VerificationAndPrize_ABI.json";
 
 export async function POST(request: NextRequest) {
   try {
-    const { addresses } = await request.json();
+    const { addresses, round_id, window_number } = await request.json();
     console.log(`Addresses: `, addresses);
-    // Submit to Risc0 service
-    const jobId = await submitAddressesForProof(addresses);
+    // The contract only settles a window on a journal committing all of its markets
+    const markets = await getRoundMarkets(round_id);
+    console.log(`Markets: `, markets);
+    // Submit to Risc0 service, which only settles on the scores frozen in the window's round
+    const jobId = await submitAddressesForProof(
+      addresses,
+      round_id,
+      window_number,
+      markets
+    );
     console.log("jobId: ", jobId);
 
     const proofResponse = await pollForProofCompletion(jobId);
@@ -40,18 +52,16 @@ export async function POST(request: NextRequest) {
 
     const attestationId = zkVerifyResult.attestationId;
     const merklePath = zkVerifyResult.proofDetails.proof;
-    const leaf = zkVerifyResult.proofDetails.leaf;
     const leafCount = zkVerifyResult.proofDetails.numberOfLeaves;
     const index = zkVerifyResult.proofDetails.leafIndex;
-    const winners = proofResponse.results.map((result) => result.is_top_half);
 
+    // The contract derives the leaf and the winners from the proven journal
     const tx = await contract.verifyWinnersAndProcess(
-      leaf,
       attestationId,
       merklePath,
       leafCount,
       index,
-      winners
+      proofResponse.journal
     );
 
     await tx.wait();
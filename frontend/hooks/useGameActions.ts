@@ -188,6 +188,10 @@ export function useGameActions() {
   const [loading, setLoading] = useState(false);
   const [gameData, setGameData] = useState<GameData[]>([]);
   const [selectedAddresses, setSelectedAddresses] = useState<string[]>([]);
+  // Round the window's scores were frozen in, the prover settles on it
+  const [roundId, setRoundId] = useState<number | null>(null);
+  // The contract's number of the window, which the proof commits to settle only this window
+  const [windowNumber, setWindowNumber] = useState<number | null>(null);
   const [bets, setBets] = useState<Record<string, "top" | "bottom">>({});
   const [windowActive, setWindowActive] = useState(false);
   const [tokenBalance, setTokenBalance] = useState<string>("0");
@@ -246,6 +250,8 @@ export function useGameActions() {
       });
       console.log("[startBettingWindow gameAction]: ", data);
       setSelectedAddresses(data.addresses);
+      setRoundId(data.round);
+      setWindowNumber(data.window_number);
       setWindowActive(true);
       return data;
     } finally {
@@ -348,6 +354,8 @@ export function useGameActions() {
         },
         body: JSON.stringify({
           addresses: selectedAddresses,
+          round_id: roundId,
+          window_number: windowNumber,
         }),
       });
 
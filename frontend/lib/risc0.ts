@@ -1,5 +1,5 @@
 import axios from "axios";
-import { RISC0_SERVICE_URL } from "./config";
+import { API_BASE, RISC0_SERVICE_URL } from "./config";
 
 export interface AddressData {
   address: string;
@@ -17,11 +17,31 @@ export interface ProofResponse {
   }>;
 }
 
+// A market of the window as the prover settles it, e.g. { rank_bucket: { address, buckets } }
+export type SettlementMarket = Record<string, Record<string, string | number>>;
+
+// Markets of the round's window in the contract's order. The journal must commit all of them
+// for the window to settle
+export async function getRoundMarkets(
+  roundId: number
+): Promise<SettlementMarket[]> {
+  const response = await axios.get(
+    `${API_BASE}/addresses/window/rounds/${roundId}/markets`
+  );
+  return response.data;
+}
+
 export async function submitAddressesForProof(
-  addresses: string[]
+  addresses: string[],
+  roundId: number,
+  windowNumber: number,
+  markets: SettlementMarket[]
 ): Promise<string> {
   const response = await axios.post(`${RISC0_SERVICE_URL}/check_position/`, {
     addresses,
+    round_id: roundId,
+    window_number: windowNumber,
+    markets,
   });
   return response.data; // job_id
 }
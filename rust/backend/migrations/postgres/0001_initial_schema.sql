@@ -0,0 +1,84 @@
+-- Shared leaderboard data for deployments with several replicas. Chain state (indexed
+-- events and tracked transactions) stays in each replica's SQLite state database.
+
+-- One leaderboard per game, the default one holds seeded scores
+CREATE TABLE leaderboards (
+    id BIGSERIAL PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    contract_address TEXT UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO leaderboards (slug, name) VALUES ('default', 'Default');
+
+CREATE TABLE addresses (
+    id BIGSERIAL PRIMARY KEY,
+    address TEXT NOT NULL,
+    score BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    leaderboard_id BIGINT NOT NULL DEFAULT 1 REFERENCES leaderboards (id),
+    submitted_at BIGINT,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX idx_addresses_leaderboard_player ON addresses (leaderboard_id, address);
+CREATE INDEX idx_addresses_leaderboard_score ON addresses (leaderboard_id, score);
+CREATE INDEX idx_addresses_leaderboard_created ON addresses (leaderboard_id, created_at);
+
+-- Closed epochs of each leaderboard with the frozen scores they were hashed from.
+-- tx_id is the tracked transaction in the state database of the replica that anchored it.
+CREATE TABLE epochs (
+    id BIGSERIAL PRIMARY KEY,
+    leaderboard_id BIGINT NOT NULL REFERENCES leaderboards (id),
+    number BIGINT NOT NULL,
+    started_at TIMESTAMPTZ,
+    closed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    snapshot_hash TEXT NOT NULL,
+    hashed_at BIGINT NOT NULL,
+    record_count BIGINT NOT NULL,
+    tx_id BIGINT,
+    UNIQUE (leaderboard_id, number)
+);
+
+CREATE TABLE epoch_scores (
+    epoch_id BIGINT NOT NULL REFERENCES epochs (id),
+    address TEXT NOT NULL,
+    score BIGINT NOT NULL,
+    created_at TIMESTAMPTZ,
+    submitted_at BIGINT,
+    PRIMARY KEY (epoch_id, address)
+);
+
+-- Game servers allowed to submit scores, and submissions that failed verification
+CREATE TABLE game_servers (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    public_key TEXT NOT NULL,
+    address TEXT NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_nonce BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE score_audit (
+    id BIGSERIAL PRIMARY KEY,
+    server_address TEXT,
+    address TEXT NOT NULL,
+    score BIGINT NOT NULL,
+    game_id TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    nonce TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    status TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Leaderboard and snapshot of each window started by any replica, looked up by the indexers
+CREATE TABLE window_requests (
+    tx_hash TEXT PRIMARY KEY,
+    leaderboard_id BIGINT NOT NULL REFERENCES leaderboards (id),
+    epoch_id BIGINT REFERENCES epochs (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- Order player addresses by bytes like SQLite, so exports sorted by address match the
+-- canonical snapshot hash whatever the database locale
+ALTER TABLE addresses ALTER COLUMN address TYPE TEXT COLLATE "C";
+ALTER TABLE epoch_scores ALTER COLUMN address TYPE TEXT COLLATE "C";
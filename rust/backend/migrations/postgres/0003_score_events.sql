@@ -0,0 +1,32 @@
+-- Every change of a player's score, newest last. A score is NULL when the epoch closed and
+-- the score moved into its snapshot. recorded_at is in unix milliseconds.
+CREATE TABLE score_events (
+    id BIGSERIAL PRIMARY KEY,
+    leaderboard_id BIGINT NOT NULL REFERENCES leaderboards (id),
+    address TEXT COLLATE "C" NOT NULL,
+    score BIGINT,
+    previous_score BIGINT,
+    source TEXT NOT NULL,
+    source_id TEXT,
+    reason TEXT,
+    submitted_at BIGINT,
+    recorded_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_score_events_player ON score_events (leaderboard_id, address, recorded_at);
+
+CREATE FUNCTION score_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'score_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER score_events_append_only BEFORE UPDATE OR DELETE ON score_events
+    FOR EACH ROW EXECUTE FUNCTION score_events_append_only();
+
+-- Current scores become the first events, as of their last change
+INSERT INTO score_events (leaderboard_id, address, score, source, submitted_at, recorded_at)
+SELECT leaderboard_id, address, score, 'migration', submitted_at,
+       (EXTRACT(EPOCH FROM COALESCE(updated_at, created_at)) * 1000)::BIGINT
+FROM addresses
+ORDER BY id;
@@ -0,0 +1,19 @@
+-- Window selections committed to the hash of a future block before the window starts. The
+-- block hash, drawn addresses and window transaction are filled in once, when the window
+-- starts. committed_at is in unix milliseconds.
+CREATE TABLE window_draws (
+    id BIGSERIAL PRIMARY KEY,
+    leaderboard_id BIGINT NOT NULL REFERENCES leaderboards (id),
+    epoch_id BIGINT NOT NULL REFERENCES epochs (id),
+    snapshot_hash TEXT NOT NULL,
+    selection TEXT NOT NULL,
+    target_block BIGINT NOT NULL,
+    committed_at BIGINT NOT NULL,
+    block_hash TEXT,
+    addresses TEXT,
+    tx_hash TEXT
+);
+
+-- A leaderboard has one pending draw at a time, so draws cannot be retried until one suits
+CREATE UNIQUE INDEX idx_window_draws_pending ON window_draws (leaderboard_id)
+    WHERE tx_hash IS NULL;
@@ -0,0 +1,31 @@
+-- Scores of a window's players, frozen when the window starts. round_hash commits the
+-- canonical hash of the scores to the round, keccak256(scores_hash ‖ uint256(id)), and is
+-- anchored on-chain before the window opens. hashed_at is in unix seconds.
+CREATE TABLE window_rounds (
+    id BIGSERIAL PRIMARY KEY,
+    leaderboard_id BIGINT NOT NULL REFERENCES leaderboards (id),
+    epoch_id BIGINT REFERENCES epochs (id),
+    scores_hash TEXT NOT NULL,
+    round_hash TEXT NOT NULL,
+    record_count BIGINT NOT NULL,
+    hashed_at BIGINT NOT NULL,
+    anchor_tx TEXT
+);
+
+CREATE TABLE window_round_scores (
+    round_id BIGINT NOT NULL REFERENCES window_rounds (id),
+    address TEXT COLLATE "C" NOT NULL,
+    score BIGINT NOT NULL,
+    PRIMARY KEY (round_id, address)
+);
+
+CREATE FUNCTION window_round_scores_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'window_round_scores is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER window_round_scores_append_only BEFORE UPDATE OR DELETE ON window_round_scores
+    FOR EACH ROW EXECUTE FUNCTION window_round_scores_append_only();
+
+ALTER TABLE window_requests ADD COLUMN round_id BIGINT REFERENCES window_rounds (id);
@@ -0,0 +1,126 @@
+-- Chain state shared by all replicas: tracked transactions and the mirror of the
+-- AddressLogger contract events. Only the replica holding the chain writer lock indexes
+-- events and follows transactions, every replica reads them.
+
+-- Transactions submitted by the contract services, followed by the tx tracker
+CREATE TABLE transactions (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    tx_hash TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL DEFAULT 'pending',
+    block_number BIGINT,
+    block_hash TEXT,
+    confirmations BIGINT NOT NULL DEFAULT 0,
+    gas_used TEXT,
+    revert_reason TEXT,
+    reorg_count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Anchors used to point at transactions in the state database of the replica that sent them
+UPDATE epochs SET tx_id = NULL;
+ALTER TABLE epochs ADD FOREIGN KEY (tx_id) REFERENCES transactions (id);
+
+CREATE TABLE indexer_state (
+    id BIGINT PRIMARY KEY CHECK (id = 1),
+    last_block BIGINT NOT NULL
+);
+
+CREATE TABLE indexed_blocks (
+    block_number BIGINT PRIMARY KEY,
+    block_hash TEXT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+-- Windows are numbered by the indexer in the order they started
+CREATE TABLE windows (
+    id BIGINT PRIMARY KEY,
+    operator TEXT NOT NULL,
+    valid_addresses JSONB NOT NULL,
+    started_at BIGINT NOT NULL,
+    start_block BIGINT NOT NULL,
+    start_tx TEXT NOT NULL,
+    closed_at BIGINT,
+    close_block BIGINT,
+    close_tx TEXT,
+    settled_block BIGINT,
+    settle_tx TEXT,
+    leaderboard_id BIGINT NOT NULL DEFAULT 1 REFERENCES leaderboards (id),
+    contract TEXT,
+    epoch_id BIGINT REFERENCES epochs (id),
+    selection JSONB,
+    round_id BIGINT REFERENCES window_rounds (id)
+);
+
+CREATE TABLE bets (
+    id BIGSERIAL PRIMARY KEY,
+    window_id BIGINT NOT NULL REFERENCES windows (id),
+    bet_index BIGINT NOT NULL,
+    bettor TEXT NOT NULL,
+    selected_address TEXT NOT NULL,
+    position BOOLEAN NOT NULL,
+    amount TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'pending',
+    payout TEXT,
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    UNIQUE (tx_hash, log_index)
+);
+
+-- market is set on the payouts of a market settlement
+CREATE TABLE payouts (
+    id BIGSERIAL PRIMARY KEY,
+    window_id BIGINT REFERENCES windows (id),
+    bettor TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    is_winner BOOLEAN NOT NULL,
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    market BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (tx_hash, log_index)
+);
+
+-- Markets of a window besides up/down, numbered like the contract in the order of their
+-- first bet. kind and param are the contract's encoding, market the API's.
+CREATE TABLE markets (
+    window_id BIGINT NOT NULL REFERENCES windows (id),
+    market_index BIGINT NOT NULL,
+    kind BIGINT NOT NULL,
+    address TEXT NOT NULL,
+    param TEXT NOT NULL,
+    market JSONB NOT NULL,
+    outcomes BIGINT NOT NULL,
+    winning_outcome BIGINT,
+    settled_block BIGINT,
+    settle_tx TEXT,
+    block_number BIGINT NOT NULL,
+    PRIMARY KEY (window_id, market_index),
+    UNIQUE (window_id, kind, address, param)
+);
+
+-- position is the outcome bet on, outcome is pending, won or lost as for bets
+CREATE TABLE market_bets (
+    id BIGSERIAL PRIMARY KEY,
+    window_id BIGINT NOT NULL REFERENCES windows (id),
+    market_index BIGINT NOT NULL,
+    bet_index BIGINT NOT NULL,
+    bettor TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    amount TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'pending',
+    payout TEXT,
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    UNIQUE (tx_hash, log_index)
+);
+
+CREATE INDEX idx_bets_window ON bets (window_id, bet_index);
+CREATE INDEX idx_bets_bettor ON bets (bettor, window_id);
+CREATE INDEX idx_payouts_window ON payouts (window_id);
+CREATE INDEX idx_windows_contract ON windows (contract, id);
+CREATE INDEX idx_windows_leaderboard ON windows (leaderboard_id, id);
+CREATE INDEX idx_market_bets_market ON market_bets (window_id, market_index, bet_index);
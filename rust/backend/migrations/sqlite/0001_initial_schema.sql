@@ -0,0 +1,158 @@
+-- Schema as created by init_db before versioned migrations. Everything is
+-- IF NOT EXISTS so databases from that time can be adopted, see db::adopt_legacy_schema.
+
+-- One leaderboard per game, the default one holds seeded and legacy scores
+CREATE TABLE IF NOT EXISTS leaderboards (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    contract_address TEXT UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO leaderboards (id, slug, name) VALUES (1, 'default', 'Default');
+
+CREATE TABLE IF NOT EXISTS addresses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    address TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    leaderboard_id INTEGER NOT NULL DEFAULT 1 REFERENCES leaderboards (id),
+    submitted_at INTEGER,
+    updated_at DATETIME
+);
+
+-- Closed epochs of each leaderboard with the frozen scores they were hashed from
+CREATE TABLE IF NOT EXISTS epochs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards (id),
+    number INTEGER NOT NULL,
+    started_at DATETIME,
+    closed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    snapshot_hash TEXT NOT NULL,
+    hashed_at INTEGER NOT NULL,
+    record_count INTEGER NOT NULL,
+    tx_id INTEGER REFERENCES transactions (id),
+    UNIQUE (leaderboard_id, number)
+);
+
+CREATE TABLE IF NOT EXISTS epoch_scores (
+    epoch_id INTEGER NOT NULL REFERENCES epochs (id),
+    address TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at DATETIME,
+    submitted_at INTEGER,
+    PRIMARY KEY (epoch_id, address)
+);
+
+-- Game servers allowed to submit scores, and submissions that failed verification
+CREATE TABLE IF NOT EXISTS game_servers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    public_key TEXT NOT NULL,
+    address TEXT NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    last_nonce INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS score_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_address TEXT,
+    address TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    game_id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    nonce TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    status TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Transactions submitted by the contract services, followed by the tx tracker
+CREATE TABLE IF NOT EXISTS transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    tx_hash TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL DEFAULT 'pending',
+    block_number INTEGER,
+    block_hash TEXT,
+    confirmations INTEGER NOT NULL DEFAULT 0,
+    gas_used TEXT,
+    revert_reason TEXT,
+    reorg_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Mirror of the AddressLogger contract events, written by the event indexer
+CREATE TABLE IF NOT EXISTS indexer_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_block INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS indexed_blocks (
+    block_number INTEGER PRIMARY KEY,
+    block_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS windows (
+    id INTEGER PRIMARY KEY,
+    operator TEXT NOT NULL,
+    valid_addresses TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    start_block INTEGER NOT NULL,
+    start_tx TEXT NOT NULL,
+    closed_at INTEGER,
+    close_block INTEGER,
+    close_tx TEXT,
+    settled_block INTEGER,
+    settle_tx TEXT,
+    leaderboard_id INTEGER NOT NULL DEFAULT 1 REFERENCES leaderboards (id),
+    contract TEXT,
+    epoch_id INTEGER REFERENCES epochs (id)
+);
+
+-- Leaderboard and snapshot of each window started by this backend, looked up by the indexer
+CREATE TABLE IF NOT EXISTS window_requests (
+    tx_hash TEXT PRIMARY KEY,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards (id),
+    epoch_id INTEGER REFERENCES epochs (id),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS bets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    window_id INTEGER NOT NULL REFERENCES windows(id),
+    bet_index INTEGER NOT NULL,
+    bettor TEXT NOT NULL,
+    selected_address TEXT NOT NULL,
+    position BOOLEAN NOT NULL,
+    amount TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'pending',
+    payout TEXT,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    UNIQUE (tx_hash, log_index)
+);
+
+CREATE TABLE IF NOT EXISTS payouts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    window_id INTEGER REFERENCES windows(id),
+    bettor TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    is_winner BOOLEAN NOT NULL,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    UNIQUE (tx_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bets_window ON bets (window_id, bet_index);
+CREATE INDEX IF NOT EXISTS idx_bets_bettor ON bets (bettor, window_id);
+CREATE INDEX IF NOT EXISTS idx_payouts_window ON payouts (window_id);
+CREATE INDEX IF NOT EXISTS idx_windows_contract ON windows (contract, id);
+CREATE INDEX IF NOT EXISTS idx_windows_leaderboard ON windows (leaderboard_id, id);
@@ -0,0 +1,28 @@
+-- Seeded rows were never deduplicated, keep the newest row of each player. The older rows
+-- are moved to addresses_duplicates so they can be reviewed or restored by hand.
+CREATE TABLE IF NOT EXISTS addresses_duplicates (
+    id INTEGER PRIMARY KEY,
+    address TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at DATETIME,
+    leaderboard_id INTEGER NOT NULL,
+    submitted_at INTEGER,
+    updated_at DATETIME,
+    removed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO addresses_duplicates
+    (id, address, score, created_at, leaderboard_id, submitted_at, updated_at)
+SELECT id, address, score, created_at, leaderboard_id, submitted_at, updated_at
+FROM addresses
+WHERE id NOT IN (SELECT MAX(id) FROM addresses GROUP BY leaderboard_id, address);
+
+DELETE FROM addresses
+WHERE id IN (SELECT id FROM addresses_duplicates);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_addresses_leaderboard_player
+    ON addresses (leaderboard_id, address);
+CREATE INDEX IF NOT EXISTS idx_addresses_leaderboard_score
+    ON addresses (leaderboard_id, score);
+CREATE INDEX IF NOT EXISTS idx_addresses_leaderboard_created
+    ON addresses (leaderboard_id, created_at);
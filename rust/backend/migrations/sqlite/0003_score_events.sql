@@ -0,0 +1,33 @@
+-- Every change of a player's score, newest last. A score is NULL when the epoch closed and
+-- the score moved into its snapshot. recorded_at is in unix milliseconds.
+CREATE TABLE score_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards (id),
+    address TEXT NOT NULL,
+    score INTEGER,
+    previous_score INTEGER,
+    source TEXT NOT NULL,
+    source_id TEXT,
+    reason TEXT,
+    submitted_at INTEGER,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_score_events_player ON score_events (leaderboard_id, address, recorded_at);
+
+CREATE TRIGGER score_events_no_update BEFORE UPDATE ON score_events
+BEGIN
+    SELECT RAISE(ABORT, 'score_events is append-only');
+END;
+
+CREATE TRIGGER score_events_no_delete BEFORE DELETE ON score_events
+BEGIN
+    SELECT RAISE(ABORT, 'score_events is append-only');
+END;
+
+-- Current scores become the first events, as of their last change
+INSERT INTO score_events (leaderboard_id, address, score, source, submitted_at, recorded_at)
+SELECT leaderboard_id, address, score, 'migration', submitted_at,
+       CAST(strftime('%s', COALESCE(updated_at, created_at, CURRENT_TIMESTAMP)) AS INTEGER) * 1000
+FROM addresses
+ORDER BY id;
@@ -0,0 +1,3 @@
+-- Strategy and parameters the players of a window were picked with, as JSON
+ALTER TABLE window_requests ADD COLUMN selection TEXT;
+ALTER TABLE windows ADD COLUMN selection TEXT;
@@ -0,0 +1,33 @@
+-- Scores of a window's players, frozen when the window starts. round_hash commits the
+-- canonical hash of the scores to the round, keccak256(scores_hash ‖ uint256(id)), and is
+-- anchored on-chain before the window opens. hashed_at is in unix seconds.
+CREATE TABLE window_rounds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    leaderboard_id INTEGER NOT NULL REFERENCES leaderboards (id),
+    epoch_id INTEGER REFERENCES epochs (id),
+    scores_hash TEXT NOT NULL,
+    round_hash TEXT NOT NULL,
+    record_count INTEGER NOT NULL,
+    hashed_at INTEGER NOT NULL,
+    anchor_tx TEXT
+);
+
+CREATE TABLE window_round_scores (
+    round_id INTEGER NOT NULL REFERENCES window_rounds (id),
+    address TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    PRIMARY KEY (round_id, address)
+);
+
+CREATE TRIGGER window_round_scores_no_update BEFORE UPDATE ON window_round_scores
+BEGIN
+    SELECT RAISE(ABORT, 'window_round_scores is append-only');
+END;
+
+CREATE TRIGGER window_round_scores_no_delete BEFORE DELETE ON window_round_scores
+BEGIN
+    SELECT RAISE(ABORT, 'window_round_scores is append-only');
+END;
+
+ALTER TABLE window_requests ADD COLUMN round_id INTEGER REFERENCES window_rounds (id);
+ALTER TABLE windows ADD COLUMN round_id INTEGER;
@@ -0,0 +1,39 @@
+-- Markets of a window besides up/down, numbered like the contract in the order of their
+-- first bet. kind and param are the contract's encoding, market the API's.
+CREATE TABLE markets (
+    window_id INTEGER NOT NULL REFERENCES windows (id),
+    market_index INTEGER NOT NULL,
+    kind INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    param TEXT NOT NULL,
+    market TEXT NOT NULL,
+    outcomes INTEGER NOT NULL,
+    winning_outcome INTEGER,
+    settled_block INTEGER,
+    settle_tx TEXT,
+    block_number INTEGER NOT NULL,
+    PRIMARY KEY (window_id, market_index),
+    UNIQUE (window_id, kind, address, param)
+);
+
+-- position is the outcome bet on, outcome is pending, won or lost as for bets
+CREATE TABLE market_bets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    window_id INTEGER NOT NULL REFERENCES windows (id),
+    market_index INTEGER NOT NULL,
+    bet_index INTEGER NOT NULL,
+    bettor TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    amount TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'pending',
+    payout TEXT,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index INTEGER NOT NULL,
+    UNIQUE (tx_hash, log_index)
+);
+
+CREATE INDEX idx_market_bets_market ON market_bets (window_id, market_index, bet_index);
+
+-- Payouts of a market settlement, left out when inferring the up/down winners
+ALTER TABLE payouts ADD COLUMN market BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+use std::{env, str::FromStr};
+
+/// Read an optional numeric setting, falling back to `default` when unset or malformed.
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
@@ -1,27 +1,87 @@
 use anyhow::Result;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::str::FromStr;
 
 // pub const DATABASE_URL: &str = "sqlite:data/addresses.db";
 // use crate::DATABASE_URL;
 
+/// Versioned migrations in `migrations/sqlite/`, applied in order at startup
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+const INITIAL_SCHEMA: &str = include_str!("../migrations/sqlite/0001_initial_schema.sql");
+
 pub async fn init_db(database_url: &str) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::from_str(database_url)?
+        .create_if_missing(true)
+        .foreign_keys(true);
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&format!("{}?mode=rwc", database_url)) // Use `mode=rwc` to create the file if it doesn't exist
+        .connect_with(options)
         .await?;
+    adopt_legacy_schema(&pool).await?;
+    MIGRATOR.run(&pool).await?;
+
+    Ok(pool)
+}
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS addresses (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            address TEXT NOT NULL,
-            score INTEGER NOT NULL,
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-        );
-        "#,
+/// Databases created before versioned migrations only have the `addresses` table and no
+/// `_sqlx_migrations`. Bring them to the shape of the initial migration, which is then
+/// recorded as applied.
+async fn adopt_legacy_schema(pool: &SqlitePool) -> Result<()> {
+    if table_exists(pool, "_sqlx_migrations").await? || !table_exists(pool, "addresses").await? {
+        return Ok(());
+    }
+
+    // SQLite can't add a REFERENCES column with a default, so upgraded tables go without
+    // the foreign key
+    add_column_if_missing(
+        pool,
+        "addresses",
+        "leaderboard_id",
+        "INTEGER NOT NULL DEFAULT 1",
     )
-    .execute(&pool)
     .await?;
+    add_column_if_missing(pool, "addresses", "submitted_at", "INTEGER").await?;
+    add_column_if_missing(pool, "addresses", "updated_at", "DATETIME").await?;
+    // Creates the missing tables
+    sqlx::raw_sql(INITIAL_SCHEMA).execute(pool).await?;
 
-    Ok(pool)
+    Ok(())
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+    )
+    .bind(table)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count > 0)
+}
+
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(pool)
+            .await?;
+
+    if exists == 0 {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
 }
@@ -1,21 +1,43 @@
 use std::env;
 
 use crate::models::{
-    AddressQueryParams, BetCountResponse, BetResponse, BettingAmountsResponse, BurnTokenRequest,
-    InitRequest, MintToRequest, MintTokenRequest, PaginationParams, PlaceBetRequest,
-    TokenBalanceResponse, WindowStatusResponse,
+    AddressQueryParams, BetCountResponse, BetFilterParams, BetMarket, BetQuoteParams,
+    BetQuoteResponse, BetResponse, BettingAmountsResponse, BurnTokenRequest,
+    CreateLeaderboardRequest, DrawVerification, Epoch, ExportParams, ExportScope, IndexedWindow,
+    InitRequest, Leaderboard, LeaderboardParams, MintToRequest, MintTokenRequest, PaginationParams,
+    PlaceBetRequest, PlayerScore, RankingParams, RankingScope, RegisterGameServerRequest,
+    ScoreAtParams, ScoreCorrectionRequest, ScoreSubmission, ScoreSubmissionResponse,
+    TokenBalanceResponse, TrackedTransaction, WindowDraw, WindowRound, WindowSelection,
+    WindowSelectionParams, WindowStatusResponse,
 };
-use crate::services::addr_logger_contract_service::AddrLoggerContractService;
+use crate::repository::Repository;
+use crate::services::addr_logger_contract_service::{
+    AddrLoggerContractService, AddrLoggerContracts,
+};
+use crate::services::bet_history_service::{self, BetScope};
 use crate::services::betting_token_service::BettingTokenService;
+use crate::services::draw_service::{self, DrawService};
+use crate::services::export_service::{self, ExportFormat};
 use crate::services::hash_contract_service::HashContractService;
-use crate::services::{address_service, hash_service};
-use actix_web::error::{ErrorBadRequest, ErrorForbidden, ErrorInternalServerError};
-use actix_web::{web, HttpResponse, Result};
-use ethers::types::{Address, U256};
-use ethers::utils::parse_ether;
+use crate::services::import_service::{ImportFormat, ScoreImporter};
+use crate::services::indexer_service::EventIndexerService;
+use crate::services::live_service::{LiveEvent, LiveFeed, PoolUpdate};
+use crate::services::market_service;
+use crate::services::ranking_service::{self, MAX_RANKING_LIMIT};
+use crate::services::score_service::{self, ScoreConfig};
+use crate::services::tx_tracker_service::TxTrackerService;
+use crate::services::{
+    address_service, hash_service, history_service, leaderboard_service, payout_service,
+    selection_service,
+};
+use actix_web::error::{
+    ErrorBadRequest, ErrorConflict, ErrorForbidden, ErrorInternalServerError, ErrorNotFound,
+};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use ethers::types::{Address, H256, U256};
+use futures_util::StreamExt;
 use log::{debug, error, info};
 use serde_json::json;
-use sqlx::SqlitePool;
 
 pub async fn get_addresses(query: web::Query<AddressQueryParams>) -> Result<HttpResponse> {
     let count = query.count.unwrap_or(1000);
@@ -33,11 +55,13 @@ pub async fn get_addresses(query: web::Query<AddressQueryParams>) -> Result<Http
     Ok(HttpResponse::Ok().json(addresses))
 }
 pub async fn get_all_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("get_all_addresses: Retrieving all addresses");
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_all_addresses").await?;
 
-    let addresses = match address_service::get_all_addresses(&pool).await {
+    let addresses = match repository.get_all_addresses(leaderboard.id).await {
         Ok(addrs) => {
             debug!(
                 "get_all_addresses: Successfully retrieved {} addresses",
@@ -54,16 +78,37 @@ pub async fn get_all_addresses(
     Ok(HttpResponse::Ok().json(addresses))
 }
 pub async fn generate_and_store_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
     query: web::Query<AddressQueryParams>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse> {
+    // Random addresses are only for seeding development databases
+    if !score_config.enable_seeding {
+        return Err(ErrorForbidden(
+            "Seeding is disabled, set ENABLE_SEEDING=true",
+        ));
+    }
     let count = query.count.unwrap_or(1000);
     debug!(
         "generate_and_store_addresses: Starting generation with count={}",
         count
     );
 
-    let addresses = match address_service::generate_and_store_addresses(&pool, count).await {
+    let leaderboard = find_leaderboard(
+        repository.get_ref(),
+        &params,
+        "generate_and_store_addresses",
+    )
+    .await?;
+
+    let addresses = match address_service::generate_and_store_addresses(
+        repository.get_ref(),
+        leaderboard.id,
+        count,
+    )
+    .await
+    {
         Ok(addrs) => {
             debug!(
                 "generate_and_store_addresses: Successfully stored {} addresses",
@@ -83,9 +128,287 @@ pub async fn generate_and_store_addresses(
     Ok(HttpResponse::Ok().json(addresses))
 }
 
+/// Endpoint: POST /api/v0/addresses/import, requires X-Admin-Token
+///
+/// Streams `text/csv` (`address,score`) or `application/x-ndjson` bodies into a leaderboard.
+pub async fn import_addresses(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    params: web::Query<LeaderboardParams>,
+    mut body: web::Payload,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let format = ImportFormat::from_content_type(content_type)
+        .ok_or_else(|| ErrorBadRequest("Content-Type must be text/csv or application/x-ndjson"))?;
+
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "import_addresses").await?;
+    let mut importer = ScoreImporter::new(
+        repository.get_ref(),
+        leaderboard.id,
+        format,
+        score_config.max_score,
+    );
+    let store_failed = |e: anyhow::Error| {
+        error!("import_addresses: Failed to store scores: {}", e);
+        ErrorInternalServerError("Failed to store scores")
+    };
+
+    while let Some(chunk) = body.next().await {
+        importer.push(&chunk?).await.map_err(store_failed)?;
+    }
+    let report = importer.finish().await.map_err(store_failed)?;
+    info!(
+        "import_addresses: Imported {} scores into {} ({} duplicates, {} rejected)",
+        report.inserted, leaderboard.slug, report.duplicates, report.rejected
+    );
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Endpoint: GET /api/v0/addresses/export
+///
+/// Streams the current scores, optionally created within `from` and `to`, or the snapshot of
+/// an `epoch`, ending with the canonical hash of what was exported.
+pub async fn export_addresses(
+    repository: web::Data<dyn Repository>,
+    query: web::Query<ExportParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let format = query
+        .format
+        .as_deref()
+        .unwrap_or("csv")
+        .parse::<ExportFormat>()
+        .map_err(|e| ErrorBadRequest(e.to_string()))?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "export_addresses").await?;
+
+    let (scope, name, snapshot_hash) = match query.epoch {
+        Some(_) if query.from.is_some() || query.to.is_some() => {
+            return Err(ErrorBadRequest(
+                "An epoch export can't be limited to a time range",
+            ));
+        }
+        Some(number) => {
+            let epoch = find_epoch(
+                repository.get_ref(),
+                &leaderboard,
+                number,
+                "export_addresses",
+            )
+            .await?;
+            (
+                ExportScope::Snapshot { epoch_id: epoch.id },
+                format!("{}-epoch-{}", leaderboard.slug, epoch.number),
+                Some(epoch.snapshot_hash),
+            )
+        }
+        None => {
+            if let (Some(from), Some(to)) = (query.from, query.to) {
+                if from >= to {
+                    return Err(ErrorBadRequest("from must be before to"));
+                }
+            }
+            (
+                ExportScope::Live {
+                    leaderboard_id: leaderboard.id,
+                    from: query.from,
+                    to: query.to,
+                },
+                leaderboard.slug.clone(),
+                None,
+            )
+        }
+    };
+    debug!("export_addresses: Exporting {} as {:?}", name, format);
+
+    let body = export_service::export_scores(repository.into_inner(), scope, format)
+        .map_err(|e| {
+            error!("export_addresses: Failed to start export: {}", e);
+            ErrorInternalServerError("Failed to start export")
+        })?
+        .inspect(|chunk| {
+            if let Err(e) = chunk {
+                error!("export_addresses: Export failed: {}", e);
+            }
+        });
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type()).insert_header((
+        "Content-Disposition",
+        format!("attachment; filename=\"{}.{}\"", name, format.extension()),
+    ));
+    if let Some(hash) = snapshot_hash {
+        response.insert_header(("X-Snapshot-Hash", hash));
+    }
+
+    Ok(response.streaming(body))
+}
+
+/// Endpoint: POST /api/v0/scores
+pub async fn submit_scores(
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    submissions: web::Json<Vec<ScoreSubmission>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    debug!("submit_scores: Received {} scores", submissions.len());
+
+    if submissions.is_empty() {
+        return Err(ErrorBadRequest("No scores submitted"));
+    }
+    if submissions.len() > score_config.max_batch_size {
+        return Err(ErrorBadRequest(format!(
+            "At most {} scores per request",
+            score_config.max_batch_size
+        )));
+    }
+
+    let results = score_service::submit_scores(repository.get_ref(), &submissions, &score_config)
+        .await
+        .map_err(|e| {
+            error!("submit_scores: Failed to store scores: {}", e);
+            ErrorInternalServerError("Failed to store scores")
+        })?;
+
+    let rejected = results
+        .iter()
+        .filter(|r| r.status == score_service::SUBMISSION_REJECTED)
+        .count();
+    info!(
+        "submit_scores: Accepted {} of {} scores",
+        results.len() - rejected,
+        results.len()
+    );
+
+    Ok(HttpResponse::Ok().json(ScoreSubmissionResponse {
+        accepted: results.len() - rejected,
+        rejected,
+        results,
+    }))
+}
+
+fn require_admin(req: &HttpRequest, score_config: &ScoreConfig) -> Result<(), actix_web::Error> {
+    let Some(expected) = &score_config.admin_token else {
+        return Err(ErrorForbidden(
+            "Admin endpoints are disabled, set ADMIN_TOKEN",
+        ));
+    };
+    match req.headers().get("X-Admin-Token") {
+        Some(token) if token.as_bytes() == expected.as_bytes() => Ok(()),
+        _ => Err(ErrorForbidden("Invalid admin token")),
+    }
+}
+
+/// Endpoint: POST /api/v0/game-servers
+pub async fn register_game_server(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    request: web::Json<RegisterGameServerRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    debug!("register_game_server: Registering {}", request.name);
+
+    let server = score_service::register_game_server(
+        repository.get_ref(),
+        &request.name,
+        &request.public_key,
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            "register_game_server: Failed to register game server: {}",
+            e
+        );
+        ErrorBadRequest(e.to_string())
+    })?;
+    info!(
+        "register_game_server: Registered {} as {}",
+        server.name, server.address
+    );
+
+    Ok(HttpResponse::Ok().json(server))
+}
+
+/// Endpoint: GET /api/v0/game-servers
+pub async fn get_game_servers(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+
+    let servers = repository.get_game_servers().await.map_err(|e| {
+        error!("get_game_servers: Failed to retrieve game servers: {}", e);
+        ErrorInternalServerError("Failed to retrieve game servers")
+    })?;
+
+    Ok(HttpResponse::Ok().json(servers))
+}
+
+/// Endpoint: POST /api/v0/game-servers/{id}/{action}, action is `activate` or `deactivate`
+pub async fn set_game_server_active(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    path: web::Path<(i64, String)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let (id, action) = path.into_inner();
+    let active = match action.as_str() {
+        "activate" => true,
+        "deactivate" => false,
+        _ => return Err(ErrorNotFound("Unknown action")),
+    };
+
+    let found = repository
+        .set_game_server_active(id, active)
+        .await
+        .map_err(|e| {
+            error!(
+                "set_game_server_active: Failed to update game server: {}",
+                e
+            );
+            ErrorInternalServerError("Failed to update game server")
+        })?;
+    if !found {
+        return Err(ErrorNotFound("Game server not found"));
+    }
+
+    Ok(HttpResponse::Ok().json(json!({ "id": id, "active": active })))
+}
+
+/// Endpoint: GET /api/v0/scores/audit
+pub async fn get_score_audit(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    query: web::Query<PaginationParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(100);
+
+    let entries = repository
+        .get_audit_entries(page, per_page)
+        .await
+        .map_err(|e| {
+            error!("get_score_audit: Failed to retrieve audit entries: {}", e);
+            ErrorInternalServerError("Failed to retrieve audit entries")
+        })?;
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
 pub async fn get_stored_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
     query: web::Query<PaginationParams>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse> {
     let page = query.page.unwrap_or(1);
     let per_page = query.per_page.unwrap_or(100);
@@ -94,7 +417,13 @@ pub async fn get_stored_addresses(
         page, per_page
     );
 
-    let addresses = match address_service::get_stored_addresses(&pool, page, per_page).await {
+    let leaderboard =
+        find_leaderboard(repository.get_ref(), &params, "get_stored_addresses").await?;
+
+    let addresses = match repository
+        .get_stored_addresses(leaderboard.id, page, per_page)
+        .await
+    {
         Ok(addrs) => {
             debug!("get_stored_addresses: Retrieved {} addresses", addrs.len());
             addrs
@@ -109,8 +438,9 @@ pub async fn get_stored_addresses(
 }
 
 pub async fn hash_stored_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
     query: web::Query<PaginationParams>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let page = query.page.unwrap_or(1);
     let per_page = query.per_page.unwrap_or(100);
@@ -119,7 +449,13 @@ pub async fn hash_stored_addresses(
         page, per_page
     );
 
-    let addresses = match address_service::get_stored_addresses(&pool, page, per_page).await {
+    let leaderboard =
+        find_leaderboard(repository.get_ref(), &params, "hash_stored_addresses").await?;
+
+    let addresses = match repository
+        .get_stored_addresses(leaderboard.id, page, per_page)
+        .await
+    {
         Ok(addrs) => {
             debug!(
                 "hash_stored_addresses: Retrieved {} addresses for hashing",
@@ -151,11 +487,13 @@ pub async fn hash_stored_addresses(
 }
 
 pub async fn hash_all_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("hash_all_addresses: Starting to retrieve all addresses");
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "hash_all_addresses").await?;
 
-    let addresses = match address_service::get_all_addresses(&pool).await {
+    let addresses = match repository.get_all_addresses(leaderboard.id).await {
         Ok(addrs) => {
             debug!(
                 "hash_all_addresses: Retrieved {} addresses for hashing",
@@ -187,12 +525,20 @@ pub async fn hash_all_addresses(
 }
 
 pub async fn hash_and_store_all_addresses(
-    pool: web::Data<SqlitePool>,
+    repository: web::Data<dyn Repository>,
     contract_service: web::Data<HashContractService>,
+    tx_tracker: web::Data<TxTrackerService>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("hash_and_store_all_addresses: Starting process");
+    let leaderboard = find_leaderboard(
+        repository.get_ref(),
+        &params,
+        "hash_and_store_all_addresses",
+    )
+    .await?;
 
-    let addresses = match address_service::get_all_addresses(&pool).await {
+    let addresses = match repository.get_all_addresses(leaderboard.id).await {
         Ok(addrs) => {
             debug!(
                 "hash_and_store_all_addresses: Retrieved {} addresses",
@@ -240,16 +586,13 @@ pub async fn hash_and_store_all_addresses(
     let mut hash_array = [0u8; 32];
     hash_array.copy_from_slice(&hash_bytes);
 
-    let transaction_hash = match contract_service
+    let tx_hash = match contract_service
         .store_hash(hash_array, hash_result.timestamp, hash_result.record_count)
         .await
     {
         Ok(res) => {
-            info!("hash_and_store_all_addresses: Successfully stored hash in contract");
-            debug!(
-                "hash_and_store_all_addresses: Transaction result: {}",
-                hex::encode(&res)
-            );
+            info!("hash_and_store_all_addresses: Submitted hash to contract");
+            debug!("hash_and_store_all_addresses: Transaction hash: {:#x}", res);
             res
         }
         Err(e) => {
@@ -261,20 +604,29 @@ pub async fn hash_and_store_all_addresses(
         }
     };
 
+    let tracked = track_transaction(&tx_tracker, "store_hash", tx_hash).await?;
+
     Ok(HttpResponse::Ok().json(json!({
         "hash": hash_result.hash,
         "timestamp": hash_result.timestamp,
         "record_count": hash_result.record_count,
-        "transaction_hash":transaction_hash
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
 /// Initialize the contract with operator, treasury and token addresses
 pub async fn init_contract(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    tx_tracker: web::Data<TxTrackerService>,
+    params: web::Query<LeaderboardParams>,
     init_request: web::Json<InitRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("init_contract: Starting contract initialization");
+    let (_, contract_service) =
+        leaderboard_contract(repository.get_ref(), &contracts, &params, "init_contract").await?;
 
     let operator = init_request.operator.parse::<Address>().map_err(|e| {
         error!("init_contract: Invalid operator address: {}", e);
@@ -305,7 +657,7 @@ pub async fn init_contract(
             ErrorInternalServerError("Invalid token address configuration")
         })?;
 
-    let transaction_hash = contract_service
+    let tx_hash = contract_service
         .init(operator, authorized_contract, treasury, token)
         .await
         .map_err(|e| {
@@ -313,18 +665,33 @@ pub async fn init_contract(
             ErrorInternalServerError("Failed to initialize contract")
         })?;
 
+    let tracked = track_transaction(&tx_tracker, "init", tx_hash).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_hash": transaction_hash
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
+/// Starts a betting window after freezing its players' scores in a round and anchoring the
+/// round's hash, so the prover can refuse data that changed after bets were placed
 pub async fn start_betting_window(
-    pool: web::Data<SqlitePool>,
-    contract_service: web::Data<AddrLoggerContractService>,
-    query: web::Query<AddressQueryParams>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    hash_contract: web::Data<HashContractService>,
+    tx_tracker: web::Data<TxTrackerService>,
+    draw_service: web::Data<DrawService>,
+    params: web::Query<LeaderboardParams>,
+    selection_params: web::Query<WindowSelectionParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let count = query.count.unwrap_or(3);
-    debug!("start_betting_window: Starting with count={}", count);
+    let (leaderboard, contract_service) = leaderboard_contract(
+        repository.get_ref(),
+        &contracts,
+        &params,
+        "start_betting_window",
+    )
+    .await?;
 
     // Check if a window is already active
     if contract_service.get_window_active().await.map_err(|e| {
@@ -334,24 +701,50 @@ pub async fn start_betting_window(
         return Err(ErrorForbidden("A betting window is already active"));
     }
 
-    let addresses = sqlx::query!(
-        "SELECT address FROM addresses ORDER BY RANDOM() LIMIT ?",
-        count
-    )
-    .fetch_all(pool.get_ref())
-    .await
-    .map_err(ErrorInternalServerError)?;
+    let (epoch, selection, raw_addresses, drawn) = match selection_params.draw {
+        Some(id) => {
+            if selection_params.count.is_some()
+                || selection_params.epoch.is_some()
+                || selection_params.strategy.is_some()
+            {
+                return Err(ErrorBadRequest(
+                    "A draw fixes the epoch and selection of its window",
+                ));
+            }
+            let (draw, seed, addresses) =
+                drawn_players(repository.get_ref(), &draw_service, &leaderboard, id).await?;
+            let epoch = repository
+                .get_epoch(draw.epoch_id)
+                .await
+                .map_err(ErrorInternalServerError)?;
+            (epoch, draw.selection, addresses, Some((draw.id, seed)))
+        }
+        None => {
+            let selection = selection_service::parse_selection(&selection_params)
+                .map_err(|e| ErrorBadRequest(e.to_string()))?;
+            let epoch =
+                window_epoch(repository.get_ref(), &leaderboard, selection_params.epoch).await?;
+            let addresses = selected_players(
+                repository.get_ref(),
+                &leaderboard,
+                epoch.as_ref(),
+                &selection,
+            )
+            .await?;
+            (epoch, selection, addresses, None)
+        }
+    };
+    debug!("start_betting_window: Starting with {:?}", selection);
 
     // Log raw addresses from database
     debug!("Selected addresses from database:");
-    let raw_addresses: Vec<String> = addresses.iter().map(|row| row.address.clone()).collect();
     for (i, addr) in raw_addresses.iter().enumerate() {
         debug!("Address {}: {}", i + 1, addr);
     }
 
-    let eth_addresses: Vec<Address> = addresses
-        .into_iter()
-        .filter_map(|row| row.address.parse().ok())
+    let eth_addresses: Vec<Address> = raw_addresses
+        .iter()
+        .filter_map(|addr| addr.parse().ok())
         .collect();
 
     // Log converted Ethereum addresses
@@ -360,22 +753,432 @@ pub async fn start_betting_window(
         debug!("ETH Address {}: {:?}", i + 1, addr);
     }
 
-    let transaction_hash = contract_service
-        .start_betting_window(eth_addresses.clone())
+    let round = repository
+        .create_round(
+            leaderboard.id,
+            epoch.as_ref().map(|epoch| epoch.id),
+            window_scope(&leaderboard, epoch.as_ref()),
+            &raw_addresses,
+        )
+        .await
+        .map_err(|e| {
+            error!("start_betting_window: Failed to freeze scores: {}", e);
+            ErrorInternalServerError("Failed to freeze the window's scores")
+        })?;
+    let anchor = anchor_round(repository.get_ref(), &hash_contract, &tx_tracker, &round).await?;
+
+    // Proofs settling the window commit its number, the one after the contract's latest
+    let latest_window = contract_service.get_window_number().await.map_err(|e| {
+        error!("start_betting_window: Failed to read window number: {}", e);
+        ErrorInternalServerError("Failed to read the window number")
+    })?;
+    let window_number = latest_window.as_u64() + 1;
+    let tx_hash = contract_service
+        .start_betting_window(eth_addresses.clone(), round_hash_bytes(&round)?)
         .await
         .map_err(ErrorInternalServerError)?;
 
+    // Lets the indexer attribute the window when leaderboards share a contract
+    repository
+        .record_window_request(
+            &format!("{:#x}", tx_hash),
+            leaderboard.id,
+            epoch.as_ref().map(|epoch| epoch.id),
+            &selection,
+            Some(round.id),
+        )
+        .await
+        .map_err(|e| {
+            error!(
+                "start_betting_window: Failed to record window request: {}",
+                e
+            );
+            ErrorInternalServerError("Failed to record betting window")
+        })?;
+    if let Some((id, seed)) = drawn {
+        repository
+            .complete_draw(
+                id,
+                &format!("{:#x}", seed),
+                &raw_addresses,
+                &format!("{:#x}", tx_hash),
+            )
+            .await
+            .map_err(|e| {
+                error!("start_betting_window: Failed to record draw {}: {}", id, e);
+                ErrorInternalServerError("Failed to record betting window")
+            })?;
+    }
+
+    let tracked = track_transaction(&tx_tracker, "start_betting_window", tx_hash).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "count": count,
+        "leaderboard": leaderboard.slug,
+        "epoch": epoch.as_ref().map(|epoch| epoch.number),
+        "epoch_id": epoch.as_ref().map(|epoch| epoch.id),
+        "count": raw_addresses.len(),
+        "selection": selection,
+        "draw": drawn.map(|(id, _)| id),
+        "round": round.id,
+        "round_hash": round.round_hash,
+        "window_number": window_number,
+        "anchor_tx": anchor.tx_hash,
         "addresses": raw_addresses,
         "eth_addresses": eth_addresses.iter().map(|addr| format!("{:?}", addr)).collect::<Vec<String>>(),
-        "transaction_hash": transaction_hash
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
+    })))
+}
+
+/// The snapshot a window is drawn from: `number`, or the latest closed epoch. The live scores
+/// are only used until the first epoch is closed.
+async fn window_epoch(
+    repository: &dyn Repository,
+    leaderboard: &Leaderboard,
+    number: Option<i64>,
+) -> Result<Option<Epoch>, actix_web::Error> {
+    match number {
+        Some(number) => Ok(Some(
+            find_epoch(repository, leaderboard, number, "start_betting_window").await?,
+        )),
+        None => repository
+            .latest_epoch(leaderboard.id)
+            .await
+            .map_err(ErrorInternalServerError),
+    }
+}
+
+fn window_scope(leaderboard: &Leaderboard, epoch: Option<&Epoch>) -> RankingScope {
+    match epoch {
+        Some(epoch) => RankingScope::Snapshot { epoch_id: epoch.id },
+        None => RankingScope::Live {
+            leaderboard_id: leaderboard.id,
+        },
+    }
+}
+
+/// Store the hash of a round on-chain before its window opens
+async fn anchor_round(
+    repository: &dyn Repository,
+    contract_service: &HashContractService,
+    tx_tracker: &TxTrackerService,
+    round: &WindowRound,
+) -> Result<TrackedTransaction, actix_web::Error> {
+    let tx_hash = contract_service
+        .store_hash(
+            round_hash_bytes(round)?,
+            round.hashed_at,
+            round.record_count as usize,
+        )
+        .await
+        .map_err(|e| {
+            error!("start_betting_window: Failed to store round hash: {}", e);
+            ErrorInternalServerError("Failed to anchor the window's scores")
+        })?;
+    let tracked = track_transaction(tx_tracker, "store_hash", tx_hash).await?;
+
+    repository
+        .set_round_anchor(round.id, &tracked.tx_hash)
+        .await
+        .map_err(|e| {
+            error!("start_betting_window: Failed to record round anchor: {}", e);
+            ErrorInternalServerError("Round anchored but could not be recorded")
+        })?;
+
+    Ok(tracked)
+}
+
+fn round_hash_bytes(round: &WindowRound) -> Result<[u8; 32], actix_web::Error> {
+    let hash_bytes = hex::decode(&round.round_hash).map_err(|e| {
+        error!("start_betting_window: Failed to decode round hash: {}", e);
+        ErrorInternalServerError(e)
+    })?;
+    let mut hash_array = [0u8; 32];
+    hash_array.copy_from_slice(&hash_bytes);
+    Ok(hash_array)
+}
+
+async fn selected_players(
+    repository: &dyn Repository,
+    leaderboard: &Leaderboard,
+    epoch: Option<&Epoch>,
+    selection: &WindowSelection,
+) -> Result<Vec<String>, actix_web::Error> {
+    let scope = window_scope(leaderboard, epoch);
+    if let WindowSelection::Explicit { addresses } = selection {
+        let unranked = selection_service::unranked_players(repository, scope, addresses)
+            .await
+            .map_err(ErrorInternalServerError)?;
+        if !unranked.is_empty() {
+            return Err(ErrorBadRequest(format!(
+                "No score for {}",
+                unranked.join(", ")
+            )));
+        }
+    }
+
+    selection_service::select(repository, scope, selection)
+        .await
+        .map_err(ErrorInternalServerError)
+}
+
+/// The pending draw `id` of a leaderboard with its seed and players, once its block is final
+async fn drawn_players(
+    repository: &dyn Repository,
+    draw_service: &DrawService,
+    leaderboard: &Leaderboard,
+    id: i64,
+) -> Result<(WindowDraw, H256, Vec<String>), actix_web::Error> {
+    let draw = find_draw(repository, leaderboard, id, "start_betting_window").await?;
+    if draw.tx_hash.is_some() {
+        return Err(ErrorConflict(format!(
+            "Draw {} already started a window",
+            id
+        )));
+    }
+    let seed = draw_service
+        .seed(&draw)
+        .await
+        .map_err(|e| {
+            error!("start_betting_window: Failed to read the seed block: {}", e);
+            ErrorInternalServerError("Failed to read the seed block")
+        })?
+        .ok_or_else(|| {
+            ErrorConflict(format!(
+                "Block {} of draw {} is not final yet",
+                draw.target_block, id
+            ))
+        })?;
+
+    let drawn = draw_service::draw_players(repository, &draw, seed)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    if drawn.snapshot_hash != draw.snapshot_hash {
+        error!(
+            "start_betting_window: Snapshot of epoch {} no longer matches {}",
+            draw.epoch_id, draw.snapshot_hash
+        );
+        return Err(ErrorInternalServerError(
+            "The snapshot no longer matches its committed hash",
+        ));
+    }
+
+    Ok((draw, seed, drawn.addresses))
+}
+
+async fn find_draw(
+    repository: &dyn Repository,
+    leaderboard: &Leaderboard,
+    id: i64,
+    caller: &str,
+) -> Result<WindowDraw, actix_web::Error> {
+    repository
+        .window_draw(id)
+        .await
+        .map_err(|e| {
+            error!("{}: Failed to retrieve draw: {}", caller, e);
+            ErrorInternalServerError("Failed to retrieve draw")
+        })?
+        .filter(|draw| draw.leaderboard_id == leaderboard.id)
+        .ok_or_else(|| ErrorNotFound(format!("Draw {} not found", id)))
+}
+
+/// Endpoint: POST /api/v0/addresses/window/draws
+pub async fn commit_window_draw(
+    repository: web::Data<dyn Repository>,
+    draw_service: web::Data<DrawService>,
+    params: web::Query<LeaderboardParams>,
+    selection_params: web::Query<WindowSelectionParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let selection = selection_service::parse_selection(&selection_params)
+        .map_err(|e| ErrorBadRequest(e.to_string()))?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "commit_window_draw").await?;
+    let epoch = window_epoch(repository.get_ref(), &leaderboard, selection_params.epoch)
+        .await?
+        .ok_or_else(|| ErrorBadRequest("Windows are drawn from a closed epoch"))?;
+    if let Some(reason) = draw_service::check_draw(&epoch, &selection) {
+        return Err(ErrorBadRequest(reason));
+    }
+
+    let pending = repository
+        .pending_draw(leaderboard.id)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    if let Some(pending) = pending {
+        return Err(ErrorConflict(format!(
+            "Draw {} has to start a window first",
+            pending.id
+        )));
+    }
+
+    let draw = draw_service
+        .commit(repository.get_ref(), &epoch, &selection)
+        .await
+        .map_err(|e| {
+            error!("commit_window_draw: Failed to commit draw: {}", e);
+            ErrorInternalServerError("Failed to commit draw")
+        })?;
+    info!(
+        "commit_window_draw: Draw {} of {} seeded by block {}",
+        draw.id, leaderboard.slug, draw.target_block
+    );
+
+    Ok(HttpResponse::Ok().json(draw))
+}
+
+/// Endpoint: GET /api/v0/addresses/window/draws/{id}
+pub async fn get_window_draw(
+    repository: web::Data<dyn Repository>,
+    id: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_window_draw").await?;
+    let draw = find_draw(
+        repository.get_ref(),
+        &leaderboard,
+        id.into_inner(),
+        "get_window_draw",
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(draw))
+}
+
+/// Endpoint: GET /api/v0/addresses/window/draws/{id}/verify
+///
+/// Recomputes the players of a draw from the chain's hash of its block and the stored snapshot
+pub async fn verify_window_draw(
+    repository: web::Data<dyn Repository>,
+    draw_service: web::Data<DrawService>,
+    id: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "verify_window_draw").await?;
+    let draw = find_draw(repository.get_ref(), &leaderboard, id, "verify_window_draw").await?;
+
+    let seed = draw_service
+        .seed(&draw)
+        .await
+        .map_err(|e| {
+            error!("verify_window_draw: Failed to read the seed block: {}", e);
+            ErrorInternalServerError("Failed to read the seed block")
+        })?
+        .ok_or_else(|| {
+            ErrorConflict(format!(
+                "Block {} of draw {} is not final yet",
+                draw.target_block, id
+            ))
+        })?;
+    let drawn = draw_service::draw_players(repository.get_ref(), &draw, seed)
+        .await
+        .map_err(|e| {
+            error!("verify_window_draw: Failed to recompute draw {}: {}", id, e);
+            ErrorInternalServerError("Failed to recompute draw")
+        })?;
+
+    let block_hash = format!("{:#x}", seed);
+    Ok(HttpResponse::Ok().json(DrawVerification {
+        block_hash_matches: draw.block_hash.as_ref() == Some(&block_hash),
+        block_hash,
+        snapshot_hash_matches: drawn.snapshot_hash == draw.snapshot_hash,
+        snapshot_hash: drawn.snapshot_hash,
+        addresses_match: draw.addresses.as_ref() == Some(&drawn.addresses),
+        addresses: drawn.addresses,
+        draw,
+    }))
+}
+
+/// Endpoint: GET /api/v0/addresses/window/rounds/{id}
+///
+/// The frozen scores stay hidden while bets can still be placed on them
+pub async fn get_window_round(
+    repository: web::Data<dyn Repository>,
+    indexer: web::Data<EventIndexerService>,
+    id: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_window_round").await?;
+    let round = repository
+        .window_round(id)
+        .await
+        .map_err(|e| {
+            error!("get_window_round: Failed to retrieve round: {}", e);
+            ErrorInternalServerError("Failed to retrieve round")
+        })?
+        .filter(|round| round.leaderboard_id == leaderboard.id)
+        .ok_or_else(|| ErrorNotFound(format!("Round {} not found", id)))?;
+
+    let window = indexer.round_window(id).await.map_err(|e| {
+        error!("get_window_round: Failed to read indexed window: {}", e);
+        ErrorInternalServerError("Failed to retrieve round")
+    })?;
+    let revealed = window
+        .as_ref()
+        .is_some_and(|window| window.close_block.is_some());
+    let scores = if revealed {
+        Some(repository.round_scores(id).await.map_err(|e| {
+            error!("get_window_round: Failed to retrieve scores: {}", e);
+            ErrorInternalServerError("Failed to retrieve round")
+        })?)
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(json!({
+        "round": round,
+        "window_id": window.map(|window| window.id),
+        "revealed": revealed,
+        "scores": scores,
     })))
 }
+
+/// Endpoint: GET /api/v0/addresses/window/rounds/{id}/markets
+///
+/// Markets of the round's window in the contract's order, in the shape the prover settles
+/// them. A proof without them can't settle the window.
+pub async fn get_round_markets(
+    repository: web::Data<dyn Repository>,
+    indexer: web::Data<EventIndexerService>,
+    id: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_round_markets").await?;
+    let window = indexer
+        .round_window(id)
+        .await
+        .map_err(|e| {
+            error!("get_round_markets: Failed to read indexed window: {}", e);
+            ErrorInternalServerError("Failed to retrieve markets")
+        })?
+        .filter(|window| window.leaderboard_id == leaderboard.id)
+        .ok_or_else(|| ErrorNotFound(format!("Round {} has no indexed window", id)))?;
+
+    let markets = indexer.settlement_markets(window.id).await.map_err(|e| {
+        error!("get_round_markets: Failed to read indexed markets: {}", e);
+        ErrorInternalServerError("Failed to retrieve markets")
+    })?;
+
+    Ok(HttpResponse::Ok().json(markets))
+}
+
 pub async fn close_betting_window(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    tx_tracker: web::Data<TxTrackerService>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("close_betting_window: Closing current betting window");
+    let (_, contract_service) = leaderboard_contract(
+        repository.get_ref(),
+        &contracts,
+        &params,
+        "close_betting_window",
+    )
+    .await?;
 
     // Check if a window is active
     if !contract_service.get_window_active().await.map_err(|e| {
@@ -385,13 +1188,17 @@ pub async fn close_betting_window(
         return Err(ErrorForbidden("No active betting window found"));
     }
 
-    let transaction_hash = contract_service.close_betting_window().await.map_err(|e| {
+    let tx_hash = contract_service.close_betting_window().await.map_err(|e| {
         error!("close_betting_window: Transaction failed: {}", e);
         ErrorInternalServerError("Failed to close betting window")
     })?;
 
+    let tracked = track_transaction(&tx_tracker, "close_betting_window", tx_hash).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_hash": transaction_hash
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
@@ -399,14 +1206,24 @@ pub async fn close_betting_window(
 /// Endpoint: POST /api/v0/addresses/bets
 /// Body: {
 ///     "selected_address": "0x...",
+///     "market": { "type": "top_k", "k": 3 },
 ///     "position": true,
 ///     "amount": "0.1"
 /// }
 pub async fn place_bet(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    tx_tracker: web::Data<TxTrackerService>,
+    params: web::Query<LeaderboardParams>,
     bet_request: web::Json<PlaceBetRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("place_bet: Processing bet request");
+    let market = &bet_request.market;
+    let outcome = market_service::bet_outcome(market, bet_request.position, bet_request.bucket)
+        .map_err(|e| ErrorBadRequest(e.to_string()))?;
+    let encoded = market_service::encode(market).map_err(|e| ErrorBadRequest(e.to_string()))?;
+    let (_, contract_service) =
+        leaderboard_contract(repository.get_ref(), &contracts, &params, "place_bet").await?;
 
     // Check if betting window is active
     if !contract_service.get_window_active().await.map_err(|e| {
@@ -429,18 +1246,31 @@ pub async fn place_bet(
             ErrorBadRequest("Invalid address format")
         })?;
 
-    // Verify if address is valid for current window
-    if !contract_service
-        .is_valid_address(selected_address)
-        .await
-        .map_err(|e| {
-            error!("place_bet: Failed to validate address: {}", e);
-            ErrorInternalServerError("Failed to validate address")
-        })?
-    {
-        return Err(ErrorBadRequest(
-            "Invalid address for current betting window",
-        ));
+    // Verify if address is valid for current window, and the opponent of a head-to-head
+    let mut window_addresses = vec![selected_address];
+    if let BetMarket::HeadToHead { opponent } = market {
+        let opponent = opponent.parse::<Address>().map_err(|e| {
+            error!("place_bet: Invalid opponent address format: {}", e);
+            ErrorBadRequest("Invalid opponent address format")
+        })?;
+        if opponent == selected_address {
+            return Err(ErrorBadRequest("An address can't be its own opponent"));
+        }
+        window_addresses.push(opponent);
+    }
+    for address in window_addresses {
+        if !contract_service
+            .is_valid_address(address)
+            .await
+            .map_err(|e| {
+                error!("place_bet: Failed to validate address: {}", e);
+                ErrorInternalServerError("Failed to validate address")
+            })?
+        {
+            return Err(ErrorBadRequest(
+                "Invalid address for current betting window",
+            ));
+        }
     }
 
     let amount = U256::from_dec_str(&bet_request.amount).map_err(|e| {
@@ -448,66 +1278,221 @@ pub async fn place_bet(
         ErrorBadRequest("Invalid amount format")
     })?;
 
-    let transaction_hash = contract_service
-        .place_bet(bettor, selected_address, bet_request.position, amount)
-        .await
-        .map_err(|e| {
-            error!("place_bet: Transaction failed: {}", e);
-            ErrorInternalServerError("Failed to place bet")
-        })?;
+    let (kind, tx_hash) = match encoded {
+        None => (
+            "place_bet",
+            contract_service
+                .place_bet(bettor, selected_address, outcome == 1, amount)
+                .await,
+        ),
+        Some((kind, param)) => (
+            "place_market_bet",
+            contract_service
+                .place_market_bet(bettor, kind, selected_address, param, outcome, amount)
+                .await,
+        ),
+    };
+    let tx_hash = tx_hash.map_err(|e| {
+        error!("place_bet: Transaction failed: {}", e);
+        ErrorInternalServerError("Failed to place bet")
+    })?;
+
+    let tracked = track_transaction(&tx_tracker, kind, tx_hash).await?;
 
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_hash": transaction_hash
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
+/// Served from the event index, falling back to the contract until a window has been indexed
 pub async fn get_window_status(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    indexer: web::Data<EventIndexerService>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("get_window_status: Checking betting window status");
-
-    let is_active = contract_service.get_window_active().await.map_err(|e| {
-        error!("get_window_status: Failed to get window status: {}", e);
-        ErrorInternalServerError("Failed to get window status")
-    })?;
+    let (leaderboard, contract_service) = leaderboard_contract(
+        repository.get_ref(),
+        &contracts,
+        &params,
+        "get_window_status",
+    )
+    .await?;
+
+    let window = indexed_window(&indexer, &leaderboard, "get_window_status").await?;
+    let is_active = match window {
+        Some(window) => window.close_block.is_none(),
+        None => contract_service.get_window_active().await.map_err(|e| {
+            error!("get_window_status: Failed to get window status: {}", e);
+            ErrorInternalServerError("Failed to get window status")
+        })?,
+    };
 
     Ok(HttpResponse::Ok().json(WindowStatusResponse { active: is_active }))
 }
 
 pub async fn get_betting_amounts(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    indexer: web::Data<EventIndexerService>,
     index: web::Path<u64>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let addr_index = U256::from(index.into_inner());
+    let index = index.into_inner();
     debug!(
         "get_betting_amounts: Retrieving amounts for index {}",
-        addr_index
+        index
     );
+    let (leaderboard, contract_service) = leaderboard_contract(
+        repository.get_ref(),
+        &contracts,
+        &params,
+        "get_betting_amounts",
+    )
+    .await?;
+
+    let (up_amount, down_amount) =
+        match indexed_window(&indexer, &leaderboard, "get_betting_amounts").await? {
+            Some(window) => {
+                let address = window.valid_addresses.get(index as usize).ok_or_else(|| {
+                    ErrorNotFound(format!(
+                        "No address at index {} in window {}",
+                        index, window.id
+                    ))
+                })?;
+                indexer
+                    .pool_amounts(window.id, address)
+                    .await
+                    .map_err(|e| {
+                        error!("get_betting_amounts: Failed to read indexed pools: {}", e);
+                        ErrorInternalServerError("Failed to get betting amounts")
+                    })?
+            }
+            None => {
+                let addr_index = U256::from(index);
+                let up_amount = contract_service
+                    .get_up_amount(addr_index)
+                    .await
+                    .map_err(|e| {
+                        error!("get_betting_amounts: Failed to get up amount: {}", e);
+                        ErrorInternalServerError("Failed to get up amount")
+                    })?;
+
+                let down_amount =
+                    contract_service
+                        .get_down_amount(addr_index)
+                        .await
+                        .map_err(|e| {
+                            error!("get_betting_amounts: Failed to get down amount: {}", e);
+                            ErrorInternalServerError("Failed to get down amount")
+                        })?;
+
+                (up_amount, down_amount)
+            }
+        };
+
+    Ok(HttpResponse::Ok().json(BettingAmountsResponse {
+        up_amount: up_amount.to_string(),
+        down_amount: down_amount.to_string(),
+    }))
+}
+
+/// Endpoint: GET /api/v0/addresses/bets/quote
+pub async fn get_bet_quote(
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    indexer: web::Data<EventIndexerService>,
+    params: web::Query<BetQuoteParams>,
+    leaderboard_params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    debug!("get_bet_quote: Quoting bet on {}", params.address);
+    let (leaderboard, contract_service) = leaderboard_contract(
+        repository.get_ref(),
+        &contracts,
+        &leaderboard_params,
+        "get_bet_quote",
+    )
+    .await?;
+
+    let selected_address = params.address.parse::<Address>().map_err(|e| {
+        error!("get_bet_quote: Invalid address format: {}", e);
+        ErrorBadRequest("Invalid address format")
+    })?;
+    let amount = U256::from_dec_str(&params.amount).map_err(|e| {
+        error!("get_bet_quote: Invalid amount format: {}", e);
+        ErrorBadRequest("Invalid amount format")
+    })?;
+
+    if !contract_service
+        .is_valid_address(selected_address)
+        .await
+        .map_err(|e| {
+            error!("get_bet_quote: Failed to validate address: {}", e);
+            ErrorInternalServerError("Failed to validate address")
+        })?
+    {
+        return Err(ErrorBadRequest(
+            "Invalid address for current betting window",
+        ));
+    }
+
+    // The contract only exposes pools by index, the indexed window maps addresses to indices
+    let address = format!("{:?}", selected_address);
+    let window = indexed_window(&indexer, &leaderboard, "get_bet_quote")
+        .await?
+        .ok_or_else(|| ErrorNotFound("Betting window is not indexed yet"))?;
+    let address_index = window
+        .valid_addresses
+        .iter()
+        .position(|addr| *addr == address)
+        .ok_or_else(|| ErrorNotFound("Betting window is not indexed yet"))?;
 
     let up_amount = contract_service
-        .get_up_amount(addr_index)
+        .get_up_amount(U256::from(address_index))
         .await
         .map_err(|e| {
-            error!("get_betting_amounts: Failed to get up amount: {}", e);
+            error!("get_bet_quote: Failed to get up amount: {}", e);
             ErrorInternalServerError("Failed to get up amount")
         })?;
-
     let down_amount = contract_service
-        .get_down_amount(addr_index)
+        .get_down_amount(U256::from(address_index))
         .await
         .map_err(|e| {
-            error!("get_betting_amounts: Failed to get down amount: {}", e);
+            error!("get_bet_quote: Failed to get down amount: {}", e);
             ErrorInternalServerError("Failed to get down amount")
         })?;
 
-    Ok(HttpResponse::Ok().json(BettingAmountsResponse {
+    let quote = payout_service::quote(amount, params.position, up_amount, down_amount);
+    let pool_share =
+        payout_service::to_f64(quote.proportion) / payout_service::PROPORTION_SCALE as f64;
+    let (up_odds, down_odds) = payout_service::implied_odds(quote.up_after, quote.down_after);
+    let potential_profit = quote.potential_payout.saturating_sub(amount);
+
+    Ok(HttpResponse::Ok().json(BetQuoteResponse {
+        address,
+        address_index,
+        position: params.position,
+        amount: amount.to_string(),
+        fee: quote.fee.to_string(),
+        net_stake: quote.net_stake.to_string(),
         up_amount: up_amount.to_string(),
         down_amount: down_amount.to_string(),
+        up_amount_after: quote.up_after.to_string(),
+        down_amount_after: quote.down_after.to_string(),
+        pool_share,
+        potential_payout: quote.potential_payout.to_string(),
+        potential_profit: potential_profit.to_string(),
+        odds: if params.position { up_odds } else { down_odds },
     }))
 }
 
+#[allow(dead_code)] // Payouts are processed by the Solidity VerificationAndPrize contract
 pub async fn process_payouts(
     contract_service: web::Data<AddrLoggerContractService>,
+    tx_tracker: web::Data<TxTrackerService>,
     winners: web::Json<Vec<bool>>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!(
@@ -525,7 +1510,7 @@ pub async fn process_payouts(
         ));
     }
 
-    let transaction_hash = contract_service
+    let tx_hash = contract_service
         .process_payouts(winners.into_inner())
         .await
         .map_err(|e| {
@@ -533,62 +1518,788 @@ pub async fn process_payouts(
             ErrorInternalServerError("Failed to process payouts")
         })?;
 
+    let tracked = track_transaction(&tx_tracker, "process_payouts", tx_hash).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-       "status": "success",
-       "message": "Payouts processed successfully",
-       "transaction_hash": transaction_hash
+       "status": tracked.status,
+       "message": "Payouts submitted",
+       "tx_id": tracked.id,
+       "transaction_hash": tracked.tx_hash
     })))
 }
 
-/// Get details of a specific bet by index
+/// Get details of a specific bet by index within the current window
 /// Endpoint: GET /api/v0/addresses/bets/{index}
 /// Example: GET /api/v0/addresses/bets/0 for first bet
 pub async fn get_bet(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    indexer: web::Data<EventIndexerService>,
     index: web::Path<u64>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    let index = index.into_inner();
     debug!("get_bet: Retrieving bet at index {}", index);
-
-    let bet = contract_service
-        .get_bet(U256::from(index.into_inner()))
-        .await
-        .map_err(|e| {
-            error!("get_bet: Failed to retrieve bet: {}", e);
-            ErrorInternalServerError("Failed to retrieve bet")
-        })?;
-
-    let response = BetResponse {
-        bettor: format!("{:?}", bet.0),
-        selected_address: format!("{:?}", bet.1),
-        position: bet.2,
-        amount: bet.3.to_string(),
+    let (leaderboard, contract_service) =
+        leaderboard_contract(repository.get_ref(), &contracts, &params, "get_bet").await?;
+
+    let response = match indexed_window(&indexer, &leaderboard, "get_bet").await? {
+        Some(window) => {
+            let bet = indexer
+                .bet(window.id, index as i64)
+                .await
+                .map_err(|e| {
+                    error!("get_bet: Failed to read indexed bet: {}", e);
+                    ErrorInternalServerError("Failed to retrieve bet")
+                })?
+                .ok_or_else(|| ErrorNotFound("Bet not found"))?;
+
+            BetResponse {
+                bettor: bet.bettor,
+                selected_address: bet.selected_address,
+                // Always set, indexed_bet only returns up/down bets
+                position: bet.position.unwrap_or_default(),
+                amount: bet.amount,
+            }
+        }
+        None => {
+            let bet = contract_service
+                .get_bet(U256::from(index))
+                .await
+                .map_err(|e| {
+                    error!("get_bet: Failed to retrieve bet: {}", e);
+                    ErrorInternalServerError("Failed to retrieve bet")
+                })?;
+
+            BetResponse {
+                bettor: format!("{:?}", bet.0),
+                selected_address: format!("{:?}", bet.1),
+                position: bet.2,
+                amount: bet.3.to_string(),
+            }
+        }
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
-/// Get total number of bets placed
+/// Get number of bets placed in the current window
 /// Endpoint: GET /api/v0/addresses/bets/count
-
 pub async fn get_bet_count(
-    contract_service: web::Data<AddrLoggerContractService>,
+    repository: web::Data<dyn Repository>,
+    contracts: web::Data<AddrLoggerContracts>,
+    indexer: web::Data<EventIndexerService>,
+    params: web::Query<LeaderboardParams>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!("get_bet_count: Retrieving total bet count");
-
-    let count = contract_service.get_bet_count().await.map_err(|e| {
-        error!("get_bet_count: Failed to retrieve count: {}", e);
-        ErrorInternalServerError("Failed to retrieve bet count")
-    })?;
+    let (leaderboard, contract_service) =
+        leaderboard_contract(repository.get_ref(), &contracts, &params, "get_bet_count").await?;
+
+    let count = match indexed_window(&indexer, &leaderboard, "get_bet_count").await? {
+        Some(window) => U256::from(indexer.bet_count(window.id).await.map_err(|e| {
+            error!("get_bet_count: Failed to count indexed bets: {}", e);
+            ErrorInternalServerError("Failed to retrieve bet count")
+        })?),
+        None => contract_service.get_bet_count().await.map_err(|e| {
+            error!("get_bet_count: Failed to retrieve count: {}", e);
+            ErrorInternalServerError("Failed to retrieve bet count")
+        })?,
+    };
 
     Ok(HttpResponse::Ok().json(BetCountResponse {
         count: count.to_string(),
     }))
 }
 
+/// Markets of the current window besides up/down, with the amount bet on each outcome
+/// Endpoint: GET /api/v0/addresses/markets
+pub async fn get_markets(
+    repository: web::Data<dyn Repository>,
+    indexer: web::Data<EventIndexerService>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_markets").await?;
+    let window = indexed_window(&indexer, &leaderboard, "get_markets")
+        .await?
+        .ok_or_else(|| ErrorNotFound("Betting window is not indexed yet"))?;
+
+    let markets = indexer.markets(window.id).await.map_err(|e| {
+        error!("get_markets: Failed to read indexed markets: {}", e);
+        ErrorInternalServerError("Failed to retrieve markets")
+    })?;
+
+    Ok(HttpResponse::Ok().json(markets))
+}
+
+async fn indexed_window(
+    indexer: &EventIndexerService,
+    leaderboard: &Leaderboard,
+    caller: &str,
+) -> Result<Option<IndexedWindow>, actix_web::Error> {
+    indexer.latest_window(leaderboard.id).await.map_err(|e| {
+        error!("{}: Failed to read indexed window: {}", caller, e);
+        ErrorInternalServerError("Failed to read betting window")
+    })
+}
+
+/// Endpoint: GET /api/v0/indexer/status
+pub async fn get_indexer_status(
+    indexer: web::Data<EventIndexerService>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let status = indexer.status().await.map_err(|e| {
+        error!("get_indexer_status: Failed to read indexer status: {}", e);
+        ErrorInternalServerError("Failed to read indexer status")
+    })?;
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+// Leaderboards
+
+/// Resolve the `leaderboard` query parameter, the default leaderboard when omitted
+async fn find_leaderboard(
+    repository: &dyn Repository,
+    params: &LeaderboardParams,
+    caller: &str,
+) -> Result<Leaderboard, actix_web::Error> {
+    let slug = params
+        .leaderboard
+        .as_deref()
+        .unwrap_or(leaderboard_service::DEFAULT_LEADERBOARD);
+
+    repository
+        .find_leaderboard(slug)
+        .await
+        .map_err(|e| {
+            error!("{}: Failed to read leaderboard: {}", caller, e);
+            ErrorInternalServerError("Failed to read leaderboard")
+        })?
+        .ok_or_else(|| ErrorNotFound(format!("Leaderboard '{}' not found", slug)))
+}
+
+async fn leaderboard_contract(
+    repository: &dyn Repository,
+    contracts: &AddrLoggerContracts,
+    params: &LeaderboardParams,
+    caller: &str,
+) -> Result<(Leaderboard, AddrLoggerContractService), actix_web::Error> {
+    let leaderboard = find_leaderboard(repository, params, caller).await?;
+    let contract_service = contracts.for_leaderboard(&leaderboard).map_err(|e| {
+        error!("{}: Invalid leaderboard contract: {}", caller, e);
+        ErrorInternalServerError("Invalid leaderboard contract")
+    })?;
+
+    Ok((leaderboard, contract_service))
+}
+
+/// Endpoint: GET /api/v0/leaderboards
+pub async fn get_leaderboards(
+    repository: web::Data<dyn Repository>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let leaderboards = repository.get_leaderboards().await.map_err(|e| {
+        error!("get_leaderboards: Failed to retrieve leaderboards: {}", e);
+        ErrorInternalServerError("Failed to retrieve leaderboards")
+    })?;
+
+    Ok(HttpResponse::Ok().json(leaderboards))
+}
+
+/// Endpoint: POST /api/v0/leaderboards, requires X-Admin-Token
+pub async fn create_leaderboard(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    request: web::Json<CreateLeaderboardRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    debug!("create_leaderboard: Creating leaderboard {}", request.slug);
+
+    let leaderboard = leaderboard_service::create_leaderboard(
+        repository.get_ref(),
+        &request.slug,
+        &request.name,
+        request.contract_address.as_deref(),
+    )
+    .await
+    .map_err(|e| {
+        error!("create_leaderboard: Failed to create leaderboard: {}", e);
+        ErrorBadRequest(e.to_string())
+    })?;
+    info!(
+        "create_leaderboard: Created leaderboard {} ({})",
+        leaderboard.slug, leaderboard.id
+    );
+
+    Ok(HttpResponse::Ok().json(leaderboard))
+}
+
+// Score history
+
+fn player_address(address: &str, caller: &str) -> Result<String, actix_web::Error> {
+    let player = address.parse::<Address>().map_err(|e| {
+        error!("{}: Invalid player address: {}", caller, e);
+        ErrorBadRequest("Invalid player address")
+    })?;
+
+    Ok(format!("{:?}", player))
+}
+
+/// Endpoint: GET /api/v0/players/{address}/history
+pub async fn get_score_history(
+    repository: web::Data<dyn Repository>,
+    address: web::Path<String>,
+    query: web::Query<PaginationParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let player = player_address(&address, "get_score_history")?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_score_history").await?;
+
+    let events = repository
+        .score_history(
+            leaderboard.id,
+            &player,
+            query.page.unwrap_or(1),
+            query.per_page.unwrap_or(100),
+        )
+        .await
+        .map_err(|e| {
+            error!("get_score_history: Failed to read score history: {}", e);
+            ErrorInternalServerError("Failed to read score history")
+        })?;
+
+    Ok(HttpResponse::Ok().json(events))
+}
+
+/// Endpoint: GET /api/v0/players/{address}/score?at=
+pub async fn get_player_score(
+    repository: web::Data<dyn Repository>,
+    address: web::Path<String>,
+    query: web::Query<ScoreAtParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let player = player_address(&address, "get_player_score")?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_player_score").await?;
+    let at = query.at.unwrap_or_else(chrono::Utc::now);
+
+    let score = repository
+        .score_as_of(leaderboard.id, &player, at.timestamp_millis())
+        .await
+        .map_err(|e| {
+            error!("get_player_score: Failed to read score history: {}", e);
+            ErrorInternalServerError("Failed to read score history")
+        })?
+        .ok_or_else(|| ErrorNotFound(format!("No score for {} at {}", player, at)))?;
+
+    Ok(HttpResponse::Ok().json(PlayerScore {
+        address: player,
+        score,
+        at,
+    }))
+}
+
+/// Endpoint: POST /api/v0/scores/corrections, requires X-Admin-Token
+pub async fn correct_score(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    params: web::Query<LeaderboardParams>,
+    request: web::Json<ScoreCorrectionRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "correct_score").await?;
+
+    let event = history_service::correct_score(
+        repository.get_ref(),
+        &score_config,
+        leaderboard.id,
+        &request.address,
+        request.score,
+        &request.reason,
+    )
+    .await
+    .map_err(|e| {
+        error!("correct_score: Failed to correct score: {}", e);
+        ErrorBadRequest(e.to_string())
+    })?;
+    info!(
+        "correct_score: Set {} to {} in {} ({})",
+        event.address, request.score, leaderboard.slug, request.reason
+    );
+
+    Ok(HttpResponse::Ok().json(event))
+}
+
+// Rankings
+
+/// The leaderboard's current scores, or the snapshot of `epoch`
+async fn ranking_scope(
+    repository: &dyn Repository,
+    params: &LeaderboardParams,
+    epoch: Option<i64>,
+    caller: &str,
+) -> Result<RankingScope, actix_web::Error> {
+    let leaderboard = find_leaderboard(repository, params, caller).await?;
+
+    Ok(match epoch {
+        Some(number) => RankingScope::Snapshot {
+            epoch_id: find_epoch(repository, &leaderboard, number, caller)
+                .await?
+                .id,
+        },
+        None => RankingScope::Live {
+            leaderboard_id: leaderboard.id,
+        },
+    })
+}
+
+fn ranking_limit(limit: Option<u32>, default: u32) -> Result<u32, actix_web::Error> {
+    match limit.unwrap_or(default) {
+        0 => Err(ErrorBadRequest("limit must be positive")),
+        limit if limit > MAX_RANKING_LIMIT => Err(ErrorBadRequest(format!(
+            "limit must be at most {}",
+            MAX_RANKING_LIMIT
+        ))),
+        limit => Ok(limit),
+    }
+}
+
+/// Endpoint: GET /api/v0/rankings/top
+pub async fn get_top_scores(
+    repository: web::Data<dyn Repository>,
+    query: web::Query<RankingParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let limit = ranking_limit(query.limit, 10)?;
+    let scope = ranking_scope(repository.get_ref(), &params, query.epoch, "get_top_scores").await?;
+
+    let scores = ranking_service::top(repository.get_ref(), scope, limit)
+        .await
+        .map_err(|e| {
+            error!("get_top_scores: Failed to rank scores: {}", e);
+            ErrorInternalServerError("Failed to rank scores")
+        })?;
+
+    Ok(HttpResponse::Ok().json(scores))
+}
+
+/// Endpoint: GET /api/v0/rankings/around/{rank}, `limit` players on each side
+pub async fn get_scores_around(
+    repository: web::Data<dyn Repository>,
+    rank: web::Path<u64>,
+    query: web::Query<RankingParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let rank = rank.into_inner();
+    if rank == 0 {
+        return Err(ErrorBadRequest("Ranks start at 1"));
+    }
+    let radius = ranking_limit(query.limit, 5)?;
+    let scope = ranking_scope(
+        repository.get_ref(),
+        &params,
+        query.epoch,
+        "get_scores_around",
+    )
+    .await?;
+
+    let scores = ranking_service::around(repository.get_ref(), scope, rank, radius)
+        .await
+        .map_err(|e| {
+            error!("get_scores_around: Failed to rank scores: {}", e);
+            ErrorInternalServerError("Failed to rank scores")
+        })?;
+
+    Ok(HttpResponse::Ok().json(scores))
+}
+
+/// Endpoint: GET /api/v0/rankings/quartiles
+pub async fn get_score_quartiles(
+    repository: web::Data<dyn Repository>,
+    query: web::Query<RankingParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let scope = ranking_scope(
+        repository.get_ref(),
+        &params,
+        query.epoch,
+        "get_score_quartiles",
+    )
+    .await?;
+
+    let quartiles = ranking_service::quartiles(repository.get_ref(), scope)
+        .await
+        .map_err(|e| {
+            error!("get_score_quartiles: Failed to compute quartiles: {}", e);
+            ErrorInternalServerError("Failed to compute quartiles")
+        })?;
+
+    Ok(HttpResponse::Ok().json(quartiles))
+}
+
+/// Endpoint: GET /api/v0/players/{address}/rank
+pub async fn get_player_rank(
+    repository: web::Data<dyn Repository>,
+    address: web::Path<String>,
+    query: web::Query<RankingParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let player = player_address(&address, "get_player_rank")?;
+    let scope = ranking_scope(
+        repository.get_ref(),
+        &params,
+        query.epoch,
+        "get_player_rank",
+    )
+    .await?;
+
+    let rank = ranking_service::player_rank(repository.get_ref(), scope, &player)
+        .await
+        .map_err(|e| {
+            error!("get_player_rank: Failed to rank player: {}", e);
+            ErrorInternalServerError("Failed to rank player")
+        })?
+        .ok_or_else(|| ErrorNotFound(format!("No score for {}", player)))?;
+
+    Ok(HttpResponse::Ok().json(rank))
+}
+
+// Epochs
+
+async fn find_epoch(
+    repository: &dyn Repository,
+    leaderboard: &Leaderboard,
+    number: i64,
+    caller: &str,
+) -> Result<Epoch, actix_web::Error> {
+    repository
+        .find_epoch(leaderboard.id, number)
+        .await
+        .map_err(|e| {
+            error!("{}: Failed to read epoch: {}", caller, e);
+            ErrorInternalServerError("Failed to read epoch")
+        })?
+        .ok_or_else(|| ErrorNotFound(format!("Epoch {} not found", number)))
+}
+
+/// Store an epoch's snapshot hash on-chain and remember the tracked transaction.
+async fn anchor_snapshot(
+    repository: &dyn Repository,
+    contract_service: &HashContractService,
+    tx_tracker: &TxTrackerService,
+    epoch: &Epoch,
+    caller: &str,
+) -> Result<TrackedTransaction, actix_web::Error> {
+    let hash_bytes = hex::decode(&epoch.snapshot_hash).map_err(|e| {
+        error!("{}: Failed to decode snapshot hash: {}", caller, e);
+        ErrorInternalServerError(e)
+    })?;
+    let mut hash_array = [0u8; 32];
+    hash_array.copy_from_slice(&hash_bytes);
+
+    let tx_hash = contract_service
+        .store_hash(hash_array, epoch.hashed_at, epoch.record_count as usize)
+        .await
+        .map_err(|e| {
+            error!("{}: Failed to store snapshot hash: {}", caller, e);
+            ErrorInternalServerError("Failed to anchor snapshot")
+        })?;
+    let tracked = track_transaction(tx_tracker, "store_hash", tx_hash).await?;
+
+    repository
+        .set_epoch_anchor(epoch.id, tracked.id)
+        .await
+        .map_err(|e| {
+            error!("{}: Failed to record anchor transaction: {}", caller, e);
+            ErrorInternalServerError("Snapshot anchored but could not be recorded")
+        })?;
+
+    Ok(tracked)
+}
+
+/// Endpoint: GET /api/v0/epochs
+pub async fn get_epochs(
+    repository: web::Data<dyn Repository>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_epochs").await?;
+
+    let epochs = repository.get_epochs(leaderboard.id).await.map_err(|e| {
+        error!("get_epochs: Failed to retrieve epochs: {}", e);
+        ErrorInternalServerError("Failed to retrieve epochs")
+    })?;
+
+    Ok(HttpResponse::Ok().json(epochs))
+}
+
+/// Endpoint: GET /api/v0/epochs/{number}
+pub async fn get_epoch(
+    repository: web::Data<dyn Repository>,
+    number: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_epoch").await?;
+    let epoch = find_epoch(
+        repository.get_ref(),
+        &leaderboard,
+        number.into_inner(),
+        "get_epoch",
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(epoch))
+}
+
+/// Endpoint: GET /api/v0/epochs/{number}/scores
+pub async fn get_epoch_scores(
+    repository: web::Data<dyn Repository>,
+    number: web::Path<i64>,
+    query: web::Query<PaginationParams>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(100);
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "get_epoch_scores").await?;
+    let epoch = find_epoch(
+        repository.get_ref(),
+        &leaderboard,
+        number.into_inner(),
+        "get_epoch_scores",
+    )
+    .await?;
+
+    let scores = repository
+        .get_epoch_scores(epoch.id, page, per_page)
+        .await
+        .map_err(|e| {
+            error!("get_epoch_scores: Failed to retrieve scores: {}", e);
+            ErrorInternalServerError("Failed to retrieve epoch scores")
+        })?;
+
+    Ok(HttpResponse::Ok().json(scores))
+}
+
+/// Endpoint: POST /api/v0/epochs/close, requires X-Admin-Token
+pub async fn close_epoch(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    contract_service: web::Data<HashContractService>,
+    tx_tracker: web::Data<TxTrackerService>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "close_epoch").await?;
+    debug!("close_epoch: Closing epoch of {}", leaderboard.slug);
+
+    let epoch = repository.close_epoch(leaderboard.id).await.map_err(|e| {
+        error!("close_epoch: Failed to close epoch: {}", e);
+        ErrorBadRequest(e.to_string())
+    })?;
+    info!(
+        "close_epoch: Closed epoch {} of {} with {} scores",
+        epoch.number, leaderboard.slug, epoch.record_count
+    );
+
+    // The epoch stays closed if anchoring fails, it can be retried through /anchor
+    let tracked = anchor_snapshot(
+        repository.get_ref(),
+        &contract_service,
+        &tx_tracker,
+        &epoch,
+        "close_epoch",
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "epoch": epoch,
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
+    })))
+}
+
+/// Endpoint: POST /api/v0/epochs/{number}/anchor, requires X-Admin-Token
+pub async fn anchor_epoch(
+    req: HttpRequest,
+    repository: web::Data<dyn Repository>,
+    score_config: web::Data<ScoreConfig>,
+    contract_service: web::Data<HashContractService>,
+    tx_tracker: web::Data<TxTrackerService>,
+    number: web::Path<i64>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    require_admin(&req, &score_config)?;
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "anchor_epoch").await?;
+    let epoch = find_epoch(
+        repository.get_ref(),
+        &leaderboard,
+        number.into_inner(),
+        "anchor_epoch",
+    )
+    .await?;
+
+    let tracked = anchor_snapshot(
+        repository.get_ref(),
+        &contract_service,
+        &tx_tracker,
+        &epoch,
+        "anchor_epoch",
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
+    })))
+}
+
+/// Endpoint: GET /api/v0/migrations
+pub async fn get_migrations(
+    repository: web::Data<dyn Repository>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let migrations = repository.migration_status().await.map_err(|e| {
+        error!("get_migrations: Failed to read migrations: {}", e);
+        ErrorInternalServerError("Failed to read migrations")
+    })?;
+
+    Ok(HttpResponse::Ok().json(migrations))
+}
+
+// Bet history
+
+pub async fn get_player_bets(
+    repository: web::Data<dyn Repository>,
+    address: web::Path<String>,
+    params: web::Query<BetFilterParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let bettor = address.parse::<Address>().map_err(|e| {
+        error!("get_player_bets: Invalid player address: {}", e);
+        ErrorBadRequest("Invalid player address")
+    })?;
+    let bettor = format!("{:?}", bettor);
+    debug!("get_player_bets: Retrieving bets of {}", bettor);
+    bet_history_service::validate_filters(&params).map_err(|e| {
+        error!("get_player_bets: Invalid filters: {}", e);
+        ErrorBadRequest(e.to_string())
+    })?;
+
+    let page =
+        bet_history_service::find_bets(repository.get_ref(), BetScope::Player(&bettor), &params)
+            .await
+            .map_err(|e| {
+                error!("get_player_bets: Failed to retrieve bets: {}", e);
+                ErrorInternalServerError("Failed to retrieve bets")
+            })?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+pub async fn get_player_stats(
+    repository: web::Data<dyn Repository>,
+    address: web::Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let bettor = address.parse::<Address>().map_err(|e| {
+        error!("get_player_stats: Invalid player address: {}", e);
+        ErrorBadRequest("Invalid player address")
+    })?;
+    let bettor = format!("{:?}", bettor);
+    debug!("get_player_stats: Computing stats of {}", bettor);
+
+    let stats = bet_history_service::player_stats(repository.get_ref(), &bettor)
+        .await
+        .map_err(|e| {
+            error!("get_player_stats: Failed to compute stats: {}", e);
+            ErrorInternalServerError("Failed to compute player stats")
+        })?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+pub async fn get_indexed_window(
+    indexer: web::Data<EventIndexerService>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    debug!("get_indexed_window: Retrieving window {}", id);
+
+    let window = indexer
+        .window(id)
+        .await
+        .map_err(|e| {
+            error!("get_indexed_window: Failed to retrieve window: {}", e);
+            ErrorInternalServerError("Failed to retrieve window")
+        })?
+        .ok_or_else(|| ErrorNotFound("Window not found"))?;
+
+    Ok(HttpResponse::Ok().json(window))
+}
+
+pub async fn get_window_bets(
+    repository: web::Data<dyn Repository>,
+    id: web::Path<i64>,
+    params: web::Query<BetFilterParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    debug!("get_window_bets: Retrieving bets of window {}", id);
+    bet_history_service::validate_filters(&params).map_err(|e| {
+        error!("get_window_bets: Invalid filters: {}", e);
+        ErrorBadRequest(e.to_string())
+    })?;
+
+    let page = bet_history_service::find_bets(repository.get_ref(), BetScope::Window(id), &params)
+        .await
+        .map_err(|e| {
+            error!("get_window_bets: Failed to retrieve bets: {}", e);
+            ErrorInternalServerError("Failed to retrieve bets")
+        })?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+// Live updates
+
+/// Server-Sent Events stream of window, bet, pool, settlement and transaction updates.
+pub async fn live_events(
+    repository: web::Data<dyn Repository>,
+    live: web::Data<LiveFeed>,
+    indexer: web::Data<EventIndexerService>,
+    params: web::Query<LeaderboardParams>,
+) -> Result<HttpResponse, actix_web::Error> {
+    debug!("live_events: Client connected");
+    let leaderboard = find_leaderboard(repository.get_ref(), &params, "live_events").await?;
+
+    let window = indexer.latest_window(leaderboard.id).await.map_err(|e| {
+        error!("live_events: Failed to read latest window: {}", e);
+        ErrorInternalServerError("Failed to read latest window")
+    })?;
+
+    let mut pools = Vec::new();
+    if let Some(window) = &window {
+        for address in window.valid_addresses.iter() {
+            let (up, down) = indexer
+                .pool_amounts(window.id, address)
+                .await
+                .map_err(|e| {
+                    error!("live_events: Failed to read pools: {}", e);
+                    ErrorInternalServerError("Failed to read pools")
+                })?;
+            pools.push(PoolUpdate::new(address.clone(), up, down));
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(live.sse_stream(LiveEvent::Snapshot { window, pools })))
+}
+
 // Tokens
 
 pub async fn mint_tokens(
     contract_service: web::Data<BettingTokenService>,
+    tx_tracker: web::Data<TxTrackerService>,
     request: web::Json<MintTokenRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!(
@@ -601,13 +2312,19 @@ pub async fn mint_tokens(
         ErrorInternalServerError("Failed to mint tokens")
     })?;
 
+    let tracked = track_transaction(&tx_tracker, "mint", transaction_result).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_result": hex::encode(transaction_result)
+        "transaction_result": hex::encode(transaction_result),
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
 pub async fn mint_to_address(
     contract_service: web::Data<BettingTokenService>,
+    tx_tracker: web::Data<TxTrackerService>,
     request: web::Json<MintToRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!(
@@ -628,13 +2345,19 @@ pub async fn mint_to_address(
             ErrorInternalServerError("Failed to mint tokens")
         })?;
 
+    let tracked = track_transaction(&tx_tracker, "mint_to", transaction_result).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_result": hex::encode(transaction_result)
+        "transaction_result": hex::encode(transaction_result),
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
 pub async fn burn_tokens(
     contract_service: web::Data<BettingTokenService>,
+    tx_tracker: web::Data<TxTrackerService>,
     request: web::Json<BurnTokenRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     debug!(
@@ -647,8 +2370,13 @@ pub async fn burn_tokens(
         ErrorInternalServerError("Failed to burn tokens")
     })?;
 
+    let tracked = track_transaction(&tx_tracker, "burn", transaction_result).await?;
+
     Ok(HttpResponse::Ok().json(json!({
-        "transaction_result": hex::encode(transaction_result)
+        "transaction_result": hex::encode(transaction_result),
+        "tx_id": tracked.id,
+        "transaction_hash": tracked.tx_hash,
+        "status": tracked.status
     })))
 }
 
@@ -673,5 +2401,43 @@ pub async fn get_token_balance(
     })?;
     debug!("Balance {}", balance);
 
-    Ok(HttpResponse::Ok().json(TokenBalanceResponse { balance: balance }))
+    Ok(HttpResponse::Ok().json(TokenBalanceResponse { balance }))
+}
+
+// Transactions
+
+/// Record a submitted transaction with the tracker so its outcome can be queried later
+async fn track_transaction(
+    tx_tracker: &TxTrackerService,
+    kind: &str,
+    tx_hash: H256,
+) -> Result<TrackedTransaction, actix_web::Error> {
+    tx_tracker.track(kind, tx_hash).await.map_err(|e| {
+        error!(
+            "track_transaction: Failed to track {} transaction {:#x}: {}",
+            kind, tx_hash, e
+        );
+        ErrorInternalServerError("Transaction submitted but could not be tracked")
+    })
+}
+
+/// Get the status of a submitted transaction
+/// Endpoint: GET /api/v0/tx/{id}
+pub async fn get_transaction(
+    tx_tracker: web::Data<TxTrackerService>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let id = id.into_inner();
+    debug!("get_transaction: Retrieving transaction {}", id);
+
+    let tracked = tx_tracker
+        .get(id)
+        .await
+        .map_err(|e| {
+            error!("get_transaction: Failed to retrieve transaction: {}", e);
+            ErrorInternalServerError("Failed to retrieve transaction")
+        })?
+        .ok_or_else(|| ErrorNotFound("Transaction not found"))?;
+
+    Ok(HttpResponse::Ok().json(tracked))
 }
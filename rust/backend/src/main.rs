@@ -3,20 +3,31 @@ use actix_web::{
     web::{self, Data},
     App, HttpResponse, HttpServer,
 };
-use db::init_db;
 use dotenv::dotenv;
 use log::info;
+use repository::Replication;
+use signer::SignerConfig;
 
 use services::{
-    addr_logger_contract_service::AddrLoggerContractService,
-    betting_token_service::BettingTokenService, hash_contract_service::HashContractService,
+    addr_logger_contract_service::AddrLoggerContracts,
+    betting_token_service::BettingTokenService,
+    draw_service::{DrawConfig, DrawService},
+    hash_contract_service::HashContractService,
+    indexer_service::{EventIndexerService, IndexerConfig},
+    live_service::LiveFeed,
+    score_service::ScoreConfig,
+    tx_tracker_service::{TxTrackerConfig, TxTrackerService},
 };
-use std::env;
+use std::{env, time::Duration};
+use tokio::sync::mpsc;
 
+mod config;
 mod db;
 mod handlers;
 mod models;
+mod repository;
 mod services;
+mod signer;
 
 //const DATABASE_URL: &str = "sqlite://addresses.db?mode=rwc";
 
@@ -27,54 +38,162 @@ async fn not_found() -> Result<HttpResponse, actix_web::Error> {
     })))
 }
 
+/// How often a replica checks whether it can take over writing the chain state
+const CHAIN_WRITER_RETRY: Duration = Duration::from_secs(10);
+
+/// Run the event indexer and the transaction tracker once this replica writes the chain state.
+/// Postgres releases the writer lock when the replica's session ends, so another one takes
+/// over within `CHAIN_WRITER_RETRY`.
+fn spawn_chain_writer(
+    replication: &Replication,
+    tx_tracker: TxTrackerService,
+    indexer: EventIndexerService,
+) {
+    let repository = match replication {
+        Replication::Single => None,
+        Replication::Shared(repository) => Some(repository.clone()),
+    };
+    tokio::spawn(async move {
+        let _lock = match repository {
+            Some(repository) => {
+                let lock = repository.lock_chain_writer(CHAIN_WRITER_RETRY).await;
+                info!("This replica now writes the chain state");
+                Some(lock)
+            }
+            None => None,
+        };
+        tx_tracker.spawn();
+        indexer.spawn();
+        // Keep the lock for the lifetime of the replica
+        std::future::pending::<()>().await;
+    });
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+    // sqlite: keeps everything in one file, postgres:// is shared by several replicas
+    let (repository, replication) =
+        repository::connect(&env::var("DATABASE_URL").expect("Set database url"))
+            .await
+            .expect("Failed to initialize database");
+
+    // `backend migrate` only applies pending migrations and prints their status
+    if env::args().nth(1).as_deref() == Some("migrate") {
+        for migration in repository
+            .migration_status()
+            .await
+            .expect("Failed to read migrations")
+        {
+            println!(
+                "{:>4} {:<32} {}{}",
+                migration.version,
+                migration.description,
+                if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                },
+                if migration.checksum_mismatch {
+                    " (changed since applied)"
+                } else {
+                    ""
+                }
+            );
+        }
+        return Ok(());
+    }
+
     let port = 3001;
     info!("Starting server at http://localhost:{}", port);
-
-    let pool = init_db(&env::var("DATABASE_URL").expect("Set database url"))
-        .await
-        .expect("Failed to initialize database");
     // let _pool = SqlitePool::connect("sqlite:data/addresses.db")
     //     .await
     //     .expect("Failed to connect to db");
 
-    let hash_contract_service = HashContractService::new(
+    let signer_config = SignerConfig::from_env().expect("Invalid signer configuration");
+    let client = signer::connect(
         &env::var("RPC_URL").expect("RPC_URL not set"),
-        &env::var("PRIVATE_KEY").expect("PRIVATE_KEY not set"),
-        &env::var("HASH_CONTRACT_ADDRESS").expect("HASH_CONTRACT_ADDRESS not set"),
+        &signer_config,
     )
     .await
+    .expect("Failed to initialize signer");
+
+    let hash_contract_service = HashContractService::new(
+        client.clone(),
+        &env::var("HASH_CONTRACT_ADDRESS").expect("HASH_CONTRACT_ADDRESS not set"),
+    )
     .expect("Failed to initialize hash contract service");
 
     let betting_token_service = BettingTokenService::new(
-        &env::var("RPC_URL").expect("RPC_URL not set"),
-        &env::var("PRIVATE_KEY").expect("PRIVATE_KEY not set"),
+        client.clone(),
         &env::var("TOKEN_CONTRACT_ADDRESS").expect("TOKEN_CONTRACT_ADDRESS not set"),
     )
-    .await
     .expect("Failed to initialize betting token service");
 
-    let addr_logger_contract_service = AddrLoggerContractService::new(
-        &env::var("RPC_URL").expect("RPC_URL not set"),
-        &env::var("PRIVATE_KEY").expect("PRIVATE_KEY not set"),
+    // Leaderboards without a contract of their own use this one
+    let addr_logger_contracts = AddrLoggerContracts::new(
+        client.clone(),
         &env::var("ADDR_LOGGER_CONTRACT_ADDRESS").expect("ADDR_LOGGER_CONTRACT_ADDRESS not set"),
     )
-    .await
     .expect("Failed to initialize address logger contract service");
 
+    // Replicas sharing Postgres pass their live events on to each other
+    let live = match &replication {
+        Replication::Single => LiveFeed::new(),
+        Replication::Shared(repository) => {
+            let (relay, events) = mpsc::unbounded_channel();
+            let live = LiveFeed::relayed(relay);
+            repository
+                .relay_live_events(live.clone(), events)
+                .await
+                .expect("Failed to relay live events");
+            live
+        }
+    };
+    let score_config = ScoreConfig::from_env();
+
+    let draw_service = DrawService::new(
+        &env::var("RPC_URL").expect("RPC_URL not set"),
+        DrawConfig::from_env(),
+    )
+    .expect("Failed to initialize window draws");
+
+    let tx_tracker = TxTrackerService::new(
+        repository.clone(),
+        &env::var("RPC_URL").expect("RPC_URL not set"),
+        TxTrackerConfig::from_env(),
+        live.clone(),
+    )
+    .expect("Failed to initialize transaction tracker");
+
+    let indexer = EventIndexerService::new(
+        repository.clone(),
+        &env::var("RPC_URL").expect("RPC_URL not set"),
+        &env::var("ADDR_LOGGER_CONTRACT_ADDRESS").expect("ADDR_LOGGER_CONTRACT_ADDRESS not set"),
+        IndexerConfig::from_env(),
+        live.clone(),
+    )
+    .expect("Failed to initialize event indexer");
+
+    // Every replica serves the chain state, one of them indexes the chain and tracks transactions
+    spawn_chain_writer(&replication, tx_tracker.clone(), indexer.clone());
+
     HttpServer::new(move || {
         let cors = Cors::permissive(); // Configure based on your needs
 
         App::new()
             .wrap(cors)
-            .app_data(Data::new(pool.clone()))
+            .app_data(Data::from(repository.clone()))
             .app_data(Data::new(hash_contract_service.clone()))
-            .app_data(Data::new(addr_logger_contract_service.clone()))
+            .app_data(Data::new(addr_logger_contracts.clone()))
             .app_data(Data::new(betting_token_service.clone()))
+            .app_data(Data::new(draw_service.clone()))
+            .app_data(Data::new(live.clone()))
+            .app_data(Data::new(score_config.clone()))
+            .app_data(Data::new(tx_tracker.clone()))
+            .app_data(Data::new(indexer.clone()))
             .service(
                 web::scope("/api/v0/addresses")
                     .route("", web::get().to(handlers::get_all_addresses))
@@ -96,8 +215,30 @@ async fn main() -> std::io::Result<()> {
                         web::post().to(handlers::close_betting_window),
                     )
                     .route("/window/status", web::get().to(handlers::get_window_status))
+                    .route(
+                        "/window/draws",
+                        web::post().to(handlers::commit_window_draw),
+                    )
+                    .route(
+                        "/window/draws/{id}",
+                        web::get().to(handlers::get_window_draw),
+                    )
+                    .route(
+                        "/window/draws/{id}/verify",
+                        web::get().to(handlers::verify_window_draw),
+                    )
+                    .route(
+                        "/window/rounds/{id}",
+                        web::get().to(handlers::get_window_round),
+                    )
+                    .route(
+                        "/window/rounds/{id}/markets",
+                        web::get().to(handlers::get_round_markets),
+                    )
                     // Static routes must come before dynamic routes with parameters
                     .route("/bets/count", web::get().to(handlers::get_bet_count))
+                    .route("/bets/quote", web::get().to(handlers::get_bet_quote))
+                    .route("/markets", web::get().to(handlers::get_markets))
                     .route("/bets", web::post().to(handlers::place_bet))
                     .route("/bets/{index}", web::get().to(handlers::get_bet))
                     .route(
@@ -106,13 +247,15 @@ async fn main() -> std::io::Result<()> {
                     )
                     // Payout processing being done by Solidity contract
                     // .route("/payouts", web::post().to(handlers::process_payouts))
-                    // Address generation and storage
+                    // Random seeding, requires ENABLE_SEEDING
                     .route("", web::get().to(handlers::get_addresses))
                     .route(
                         "/generate",
                         web::post().to(handlers::generate_and_store_addresses),
                     )
-                    .route("/stored", web::get().to(handlers::get_stored_addresses)),
+                    .route("/stored", web::get().to(handlers::get_stored_addresses))
+                    .route("/import", web::post().to(handlers::import_addresses))
+                    .route("/export", web::get().to(handlers::export_addresses)),
             )
             .service(
                 web::scope("/api/v0/token")
@@ -124,6 +267,77 @@ async fn main() -> std::io::Result<()> {
                         web::get().to(handlers::get_token_balance),
                     ),
             )
+            .service(
+                web::scope("/api/v0/indexer")
+                    .route("/status", web::get().to(handlers::get_indexer_status)),
+            )
+            .route(
+                "/api/v0/migrations",
+                web::get().to(handlers::get_migrations),
+            )
+            .service(
+                web::scope("/api/v0/scores")
+                    .route("", web::post().to(handlers::submit_scores))
+                    .route("/audit", web::get().to(handlers::get_score_audit))
+                    .route("/corrections", web::post().to(handlers::correct_score)),
+            )
+            .service(
+                web::scope("/api/v0/game-servers")
+                    .route("", web::post().to(handlers::register_game_server))
+                    .route("", web::get().to(handlers::get_game_servers))
+                    .route(
+                        "/{id}/{action}",
+                        web::post().to(handlers::set_game_server_active),
+                    ),
+            )
+            .service(
+                web::scope("/api/v0/leaderboards")
+                    .route("", web::get().to(handlers::get_leaderboards))
+                    .route("", web::post().to(handlers::create_leaderboard)),
+            )
+            .service(
+                web::scope("/api/v0/epochs")
+                    .route("", web::get().to(handlers::get_epochs))
+                    .route("/close", web::post().to(handlers::close_epoch))
+                    .route("/{number}", web::get().to(handlers::get_epoch))
+                    .route(
+                        "/{number}/scores",
+                        web::get().to(handlers::get_epoch_scores),
+                    )
+                    .route("/{number}/anchor", web::post().to(handlers::anchor_epoch)),
+            )
+            .service(
+                web::scope("/api/v0/rankings")
+                    .route("/top", web::get().to(handlers::get_top_scores))
+                    .route("/around/{rank}", web::get().to(handlers::get_scores_around))
+                    .route("/quartiles", web::get().to(handlers::get_score_quartiles)),
+            )
+            .route("/api/v0/live", web::get().to(handlers::live_events))
+            .service(
+                web::scope("/api/v0/players")
+                    .route("/{address}/bets", web::get().to(handlers::get_player_bets))
+                    .route("/{address}/rank", web::get().to(handlers::get_player_rank))
+                    .route(
+                        "/{address}/history",
+                        web::get().to(handlers::get_score_history),
+                    )
+                    .route(
+                        "/{address}/score",
+                        web::get().to(handlers::get_player_score),
+                    )
+                    .route(
+                        "/{address}/stats",
+                        web::get().to(handlers::get_player_stats),
+                    ),
+            )
+            .service(
+                web::scope("/api/v0/windows")
+                    .route("/{id}", web::get().to(handlers::get_indexed_window))
+                    .route("/{id}/bets", web::get().to(handlers::get_window_bets)),
+            )
+            .service(
+                web::scope("/api/v0/tx").route("/{id}", web::get().to(handlers::get_transaction)),
+            )
             .default_service(web::route().to(not_found))
     })
     .bind(("127.0.0.1", port))?
@@ -9,6 +9,176 @@ pub struct AddressScore {
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Leaderboard {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    /// AddressLogger contract running this leaderboard's windows, the default one when unset
+    pub contract_address: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLeaderboardRequest {
+    pub slug: String,
+    pub name: String,
+    pub contract_address: Option<String>,
+}
+
+/// Selects the leaderboard of scoped endpoints, `default` when omitted
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardParams {
+    pub leaderboard: Option<String>,
+}
+
+/// A closed season of a leaderboard, its scores are frozen in `epoch_scores`
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct Epoch {
+    pub id: i64,
+    pub leaderboard_id: i64,
+    pub number: i64,
+    /// Close of the previous epoch, `None` for the first one
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub closed_at: chrono::DateTime<chrono::Utc>,
+    pub snapshot_hash: String,
+    /// Timestamp anchored on-chain together with the hash
+    pub hashed_at: i64,
+    pub record_count: i64,
+    /// Tracked `store_hash` transaction, `None` until anchored
+    pub tx_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub checksum_mismatch: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScoreSubmission {
+    pub address: String,
+    pub score: u32,
+    /// Slug of the leaderboard the score belongs to
+    pub game_id: String,
+    /// Unix seconds at which the game server recorded the score
+    pub timestamp: i64,
+    /// Must increase with every submission of the game server
+    pub nonce: u64,
+    /// EIP-191 signature of the canonical submission by the game server key
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreSubmissionResult {
+    pub address: String,
+    pub game_id: String,
+    pub nonce: u64,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreSubmissionResponse {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub results: Vec<ScoreSubmissionResult>,
+}
+
+/// Outcome of a bulk score import
+#[derive(Debug, Serialize, Default)]
+pub struct ImportReport {
+    pub inserted: u64,
+    /// Players that already had a score on the leaderboard, or appeared earlier in the file
+    pub duplicates: u64,
+    pub rejected: u64,
+    /// The first rejected lines and why
+    pub errors: Vec<ImportError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportError {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// A change of a player's score in `score_events`
+#[derive(Debug, Serialize, Clone)]
+pub struct ScoreEvent {
+    pub id: i64,
+    pub address: String,
+    /// `None` once the epoch closed and the score moved into its snapshot
+    pub score: Option<u32>,
+    pub previous_score: Option<u32>,
+    /// submission, import, seed, correction, epoch or migration
+    pub source: String,
+    /// Game server that signed a submission, or the number of the closed epoch
+    pub source_id: Option<String>,
+    /// Why a score was corrected
+    pub reason: Option<String>,
+    /// Timestamp signed by the game server
+    pub submitted_at: Option<i64>,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAtParams {
+    /// Score as it stood at this time, the current one when omitted
+    pub at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerScore {
+    pub address: String,
+    pub score: u32,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreCorrectionRequest {
+    pub address: String,
+    pub score: u32,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterGameServerRequest {
+    pub name: String,
+    /// Hex encoded secp256k1 public key, compressed or uncompressed
+    pub public_key: String,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct GameServer {
+    pub id: i64,
+    pub name: String,
+    pub public_key: String,
+    /// Address derived from the public key, recovered from submission signatures
+    pub address: String,
+    pub active: bool,
+    pub last_nonce: Option<i64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct ScoreAuditEntry {
+    pub id: i64,
+    pub server_address: Option<String>,
+    pub address: String,
+    pub score: i64,
+    pub game_id: String,
+    pub timestamp: i64,
+    pub nonce: String,
+    pub signature: String,
+    pub status: String,
+    pub reason: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct HashResponse {
     // pub serialized_data: String,
@@ -22,6 +192,177 @@ pub struct AddressQueryParams {
     pub count: Option<u32>,
 }
 
+/// How a betting window picks its players, uniform when omitted
+#[derive(Debug, Deserialize)]
+pub struct WindowSelectionParams {
+    /// Epoch whose snapshot the players are drawn from, the latest one when omitted
+    pub epoch: Option<i64>,
+    /// Players of the window, 3 when omitted
+    pub count: Option<u32>,
+    /// uniform, balanced, stratified or explicit
+    pub strategy: Option<String>,
+    /// Score bands of a stratified selection
+    pub bands: Option<u32>,
+    /// Comma separated players of an explicit selection
+    pub addresses: Option<String>,
+    /// A committed draw whose epoch, selection and seed the window is started with
+    pub draw: Option<i64>,
+}
+
+/// Strategy and parameters a betting window's players were picked with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum WindowSelection {
+    /// `count` players drawn at random
+    Uniform { count: u32 },
+    /// Half of `count` drawn at or above the median score, the rest below it
+    Balanced { count: u32 },
+    /// `count` players spread over `bands` score bands holding equal numbers of players
+    Stratified { count: u32, bands: u32 },
+    /// The listed players
+    Explicit { addresses: Vec<String> },
+}
+
+/// A window selection committed to the hash of `target_block` before the window starts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WindowDraw {
+    pub id: i64,
+    pub leaderboard_id: i64,
+    pub epoch_id: i64,
+    /// Hash of the snapshot the players are drawn from
+    pub snapshot_hash: String,
+    pub selection: WindowSelection,
+    pub target_block: u64,
+    pub committed_at: chrono::DateTime<chrono::Utc>,
+    /// Seed of the draw, the remaining fields are set when a window starts with it
+    pub block_hash: Option<String>,
+    pub addresses: Option<Vec<String>>,
+    pub tx_hash: Option<String>,
+}
+
+/// A draw recomputed from its block hash and snapshot
+#[derive(Debug, Serialize)]
+pub struct DrawVerification {
+    pub draw: WindowDraw,
+    /// Hash of the target block as the chain reports it
+    pub block_hash: String,
+    pub block_hash_matches: bool,
+    /// Canonical hash of the snapshot as stored now
+    pub snapshot_hash: String,
+    pub snapshot_hash_matches: bool,
+    /// Players the draw selects
+    pub addresses: Vec<String>,
+    pub addresses_match: bool,
+}
+
+/// Leaderboard, snapshot and selection of the window started by a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowRequest {
+    pub leaderboard_id: i64,
+    pub epoch_id: Option<i64>,
+    /// Not recorded for windows started before selections were
+    pub selection: Option<WindowSelection>,
+    pub round_id: Option<i64>,
+}
+
+/// Scores of a window's players frozen when it started, anchored on-chain as `round_hash`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct WindowRound {
+    /// The round `round_hash` commits to
+    pub id: i64,
+    pub leaderboard_id: i64,
+    pub epoch_id: Option<i64>,
+    /// Canonical hash of the frozen scores
+    pub scores_hash: String,
+    /// `keccak256(scores_hash ‖ uint256(id))`
+    pub round_hash: String,
+    pub record_count: i64,
+    pub hashed_at: i64,
+    /// `store_hash` transaction anchoring `round_hash`, `None` until sent
+    pub anchor_tx: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    /// csv, ndjson or parquet, csv when omitted
+    pub format: Option<String>,
+    /// Export a closed epoch instead of the current scores
+    pub epoch: Option<i64>,
+    /// Current scores created at or after this time
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Current scores created before this time
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Scores an export reads
+#[derive(Debug, Clone)]
+pub enum ExportScope {
+    /// Frozen scores of an epoch
+    Snapshot { epoch_id: i64 },
+    /// Current scores of a leaderboard created within `[from, to)`
+    Live {
+        leaderboard_id: i64,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+    },
+}
+
+/// Scores a ranking is computed over
+#[derive(Debug, Clone, Copy)]
+pub enum RankingScope {
+    /// Current scores of a leaderboard
+    Live { leaderboard_id: i64 },
+    /// Frozen scores of an epoch
+    Snapshot { epoch_id: i64 },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RankingParams {
+    /// Players to return, the top 10 or 5 on each side of a rank when omitted
+    pub limit: Option<u32>,
+    /// Rank within a closed epoch instead of the current scores
+    pub epoch: Option<i64>,
+}
+
+/// Players above, tied with and in total with a score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreCounts {
+    pub higher: u64,
+    pub equal: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedScore {
+    /// Competition rank, tied scores share the rank of the first of them
+    pub rank: u64,
+    pub address: String,
+    pub score: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerRank {
+    pub address: String,
+    pub score: u32,
+    pub rank: u64,
+    /// Share of players below, plus half of those tied, from 0 to 100
+    pub percentile: f64,
+    /// Players tied with this score, including this one
+    pub tied: u64,
+    pub total: u64,
+}
+
+/// Quartiles of the scores, `None` without scores
+#[derive(Debug, Serialize)]
+pub struct ScoreQuartiles {
+    pub count: u64,
+    pub min: Option<u32>,
+    pub q1: Option<u32>,
+    pub median: Option<u32>,
+    pub q3: Option<u32>,
+    pub max: Option<u32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PaginationParams {
     pub page: Option<u32>,
@@ -36,11 +377,43 @@ pub struct InitRequest {
     // pub token: String,
 }
 
+/// What a bet is on. `top_half` is the up/down bet against the median, the other markets
+/// are settled on the ranks among the window's addresses.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BetMarket {
+    #[default]
+    TopHalf,
+    /// The address ranks in the top `k`
+    TopK { k: u64 },
+    /// Rank bucket of the address among `buckets` (4 or 10) equal ranges, bucket 0 on top
+    RankBucket { buckets: u64 },
+    /// The address outranks `opponent`
+    HeadToHead { opponent: String },
+    /// The address scores over `threshold`
+    OverUnder { threshold: u64 },
+}
+
+/// A market on an address as the prover settles it, `{"rank_bucket": {"address", "buckets"}}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementMarket {
+    TopK { address: String, k: u64 },
+    RankBucket { address: String, buckets: u64 },
+    HeadToHead { address: String, opponent: String },
+    OverUnder { address: String, threshold: u64 },
+}
+
 #[derive(Deserialize)]
 pub struct PlaceBetRequest {
     pub bettor: String,
     pub selected_address: String,
-    pub position: bool,
+    #[serde(default)]
+    pub market: BetMarket,
+    /// Side of a yes/no market: up, in the top K, outranks the opponent, over the threshold
+    pub position: Option<bool>,
+    /// Bucket of a `rank_bucket` market
+    pub bucket: Option<u8>,
     pub amount: String, // ETH amount in string format for precision
 }
 
@@ -62,12 +435,39 @@ pub struct WindowStatusResponse {
     pub active: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BetQuoteParams {
+    pub address: String,
+    pub position: bool,
+    pub amount: String, // Token amount before the fee, in base units
+}
+
+#[derive(Debug, Serialize)]
+pub struct BetQuoteResponse {
+    pub address: String,
+    pub address_index: usize,
+    pub position: bool,
+    pub amount: String,
+    pub fee: String,
+    pub net_stake: String,
+    pub up_amount: String,
+    pub down_amount: String,
+    pub up_amount_after: String,
+    pub down_amount_after: String,
+    /// Share of the winning side after the bet, between 0 and 1
+    pub pool_share: f64,
+    pub potential_payout: String,
+    pub potential_profit: String,
+    pub odds: Option<f64>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct BettingAmountsResponse {
     pub up_amount: String,
     pub down_amount: String,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct ContractAddressesResponse {
     pub operator: String,
@@ -75,6 +475,107 @@ pub struct ContractAddressesResponse {
     pub token: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct IndexedWindow {
+    pub id: i64,
+    pub operator: String,
+    pub valid_addresses: sqlx::types::Json<Vec<String>>,
+    pub started_at: i64,
+    pub start_block: i64,
+    pub start_tx: String,
+    pub closed_at: Option<i64>,
+    pub close_block: Option<i64>,
+    pub close_tx: Option<String>,
+    pub settled_block: Option<i64>,
+    pub settle_tx: Option<String>,
+    pub leaderboard_id: i64,
+    pub contract: Option<String>,
+    /// Snapshot the window's addresses were drawn from
+    pub epoch_id: Option<i64>,
+    pub selection: Option<sqlx::types::Json<WindowSelection>>,
+    /// Round the players' scores were frozen in
+    pub round_id: Option<i64>,
+}
+
+/// A market of a window besides up/down, numbered in the order of its first bet
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct IndexedMarket {
+    pub window_id: i64,
+    pub market_index: i64,
+    pub address: String,
+    pub market: sqlx::types::Json<BetMarket>,
+    pub outcomes: i64,
+    pub winning_outcome: Option<i64>,
+    pub settled_block: Option<i64>,
+    pub settle_tx: Option<String>,
+    /// Amount (after fees) bet on each outcome
+    #[sqlx(skip)]
+    pub pools: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, sqlx::FromRow)]
+pub struct IndexedBet {
+    pub id: i64,
+    pub window_id: i64,
+    pub bet_index: i64,
+    pub bettor: String,
+    /// Address the bet is on, the market's address for market bets
+    pub selected_address: String,
+    /// Side of an up/down bet, `None` for market bets
+    pub position: Option<bool>,
+    /// Market of a market bet, see `IndexedMarket`
+    pub market_index: Option<i64>,
+    /// Outcome a market bet is on
+    pub market_outcome: Option<i64>,
+    pub amount: String,
+    pub outcome: String,
+    pub payout: Option<String>,
+    pub block_number: i64,
+    pub tx_hash: String,
+    pub log_index: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BetFilterParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub window_id: Option<i64>,
+    pub selected_address: Option<String>,
+    pub position: Option<bool>,
+    pub outcome: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BetPageResponse {
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+    pub bets: Vec<IndexedBet>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerStatsResponse {
+    pub address: String,
+    pub total_bets: i64,
+    pub wins: i64,
+    pub losses: i64,
+    pub pending: i64,
+    pub windows: i64,
+    pub volume: String,
+    pub settled_volume: String,
+    pub total_payout: String,
+    pub net_profit: String,
+    pub roi: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexerStatusResponse {
+    pub last_block: Option<i64>,
+    pub window_count: i64,
+    pub bet_count: i64,
+    pub payout_count: i64,
+}
+
 // Token
 
 #[derive(Debug, Deserialize)]
@@ -97,3 +598,21 @@ pub struct BurnTokenRequest {
 pub struct TokenBalanceResponse {
     pub balance: u64,
 }
+
+// Transactions
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct TrackedTransaction {
+    pub id: i64,
+    pub kind: String,
+    pub tx_hash: String,
+    pub status: String,
+    pub block_number: Option<i64>,
+    pub block_hash: Option<String>,
+    pub confirmations: i64,
+    pub gas_used: Option<String>,
+    pub revert_reason: Option<String>,
+    pub reorg_count: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
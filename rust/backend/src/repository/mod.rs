@@ -0,0 +1,449 @@
+//! Data shared by all replicas of the backend: leaderboards, scores, epochs, game servers,
+//! the requests behind betting windows and the chain state (tracked transactions and indexed
+//! contract events). SQLite and Postgres implement the same `Repository`, the backend is
+//! chosen by the scheme of the database URL.
+
+use crate::db;
+use crate::models::{
+    AddressScore, BetFilterParams, BetMarket, Epoch, ExportScope, GameServer, IndexedBet,
+    IndexedMarket, IndexedWindow, IndexerStatusResponse, Leaderboard, MigrationStatus,
+    RankingScope, ScoreAuditEntry, ScoreCounts, ScoreEvent, ScoreSubmission, ScoreSubmissionResult,
+    TrackedTransaction, WindowDraw, WindowRequest, WindowRound, WindowSelection,
+};
+use crate::services::bet_history_service::BetScope;
+use crate::services::market_service::MarketStake;
+use crate::services::payout_service::PoolBet;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use sqlx::migrate::Migrator;
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+#[macro_use]
+mod sql;
+pub mod postgres;
+pub mod sqlite;
+
+#[cfg(test)]
+mod tests;
+
+pub use postgres::PostgresRepository;
+pub use sqlite::SqliteRepository;
+
+/// A signed score after signature recovery and validation, ready to be stored
+pub struct CheckedSubmission<'a> {
+    pub submission: &'a ScoreSubmission,
+    /// Recovered game server address, or why the signature is invalid
+    pub signer: Result<Address, String>,
+    /// Normalized player address, or why the submission is invalid
+    pub address: Result<String, String>,
+}
+
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn migration_status(&self) -> Result<Vec<MigrationStatus>>;
+
+    async fn get_leaderboards(&self) -> Result<Vec<Leaderboard>>;
+    async fn find_leaderboard(&self, slug: &str) -> Result<Option<Leaderboard>>;
+    async fn create_leaderboard(
+        &self,
+        slug: &str,
+        name: &str,
+        contract_address: Option<String>,
+    ) -> Result<Leaderboard>;
+    /// Contract address and id of leaderboards with a contract of their own
+    async fn contract_leaderboards(&self) -> Result<Vec<(String, i64)>>;
+
+    async fn insert_addresses(
+        &self,
+        leaderboard_id: i64,
+        addresses: &[AddressScore],
+    ) -> Result<Vec<AddressScore>>;
+    /// Insert new players and skip those already on the leaderboard, returns the inserted count
+    async fn import_addresses(
+        &self,
+        leaderboard_id: i64,
+        addresses: &[AddressScore],
+    ) -> Result<u64>;
+    async fn get_stored_addresses(
+        &self,
+        leaderboard_id: i64,
+        page: u32,
+        per_page: u32,
+    ) -> Result<Vec<AddressScore>>;
+    async fn get_all_addresses(&self, leaderboard_id: i64) -> Result<Vec<AddressScore>>;
+    /// Up to `limit` scores of an export scope with an address after `after`, in byte order of
+    /// address
+    async fn export_scores(
+        &self,
+        scope: &ExportScope,
+        after: &str,
+        limit: u32,
+    ) -> Result<Vec<AddressScore>>;
+
+    /// Scores in leaderboard order, highest first and ties by address
+    async fn ranked_scores(
+        &self,
+        scope: RankingScope,
+        offset: u64,
+        limit: u32,
+    ) -> Result<Vec<AddressScore>>;
+    async fn player_score(&self, scope: RankingScope, address: &str) -> Result<Option<u32>>;
+    async fn count_scores(&self, scope: RankingScope, score: u32) -> Result<ScoreCounts>;
+    /// The score at `offset` in ascending order
+    async fn score_at(&self, scope: RankingScope, offset: u64) -> Result<Option<u32>>;
+    /// Random players whose score is within `band`
+    async fn sample_band(
+        &self,
+        scope: RankingScope,
+        band: Range<i64>,
+        count: u32,
+    ) -> Result<Vec<String>>;
+
+    /// Changes of a player's score, oldest first
+    async fn score_history(
+        &self,
+        leaderboard_id: i64,
+        address: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<Vec<ScoreEvent>>;
+    /// A player's score as it stood at `at`, in unix milliseconds
+    async fn score_as_of(&self, leaderboard_id: i64, address: &str, at: i64)
+        -> Result<Option<u32>>;
+    /// Set a player's score outside of a submission
+    async fn correct_score(
+        &self,
+        leaderboard_id: i64,
+        address: &str,
+        score: u32,
+        reason: &str,
+    ) -> Result<ScoreEvent>;
+
+    async fn get_epochs(&self, leaderboard_id: i64) -> Result<Vec<Epoch>>;
+    async fn find_epoch(&self, leaderboard_id: i64, number: i64) -> Result<Option<Epoch>>;
+    async fn get_epoch(&self, id: i64) -> Result<Option<Epoch>>;
+    async fn latest_epoch(&self, leaderboard_id: i64) -> Result<Option<Epoch>>;
+    async fn get_epoch_scores(
+        &self,
+        epoch_id: i64,
+        page: u32,
+        per_page: u32,
+    ) -> Result<Vec<AddressScore>>;
+    /// Move the current scores of a leaderboard into a new, hashed epoch
+    async fn close_epoch(&self, leaderboard_id: i64) -> Result<Epoch>;
+    async fn set_epoch_anchor(&self, epoch_id: i64, tx_id: i64) -> Result<()>;
+
+    async fn insert_game_server(
+        &self,
+        name: &str,
+        public_key: &str,
+        address: &str,
+    ) -> Result<GameServer>;
+    async fn get_game_servers(&self) -> Result<Vec<GameServer>>;
+    async fn set_game_server_active(&self, id: i64, active: bool) -> Result<bool>;
+    async fn get_audit_entries(&self, page: u32, per_page: u32) -> Result<Vec<ScoreAuditEntry>>;
+    /// Check nonces and upsert a batch of checked submissions in one transaction
+    async fn store_submissions(
+        &self,
+        submissions: &[CheckedSubmission<'_>],
+    ) -> Result<Vec<ScoreSubmissionResult>>;
+
+    async fn record_window_request(
+        &self,
+        tx_hash: &str,
+        leaderboard_id: i64,
+        epoch_id: Option<i64>,
+        selection: &WindowSelection,
+        round_id: Option<i64>,
+    ) -> Result<()>;
+    /// How the window started by a transaction was requested
+    async fn window_request(&self, tx_hash: &str) -> Result<Option<WindowRequest>>;
+
+    /// Commit a leaderboard's next window to the hash of `target_block`
+    async fn create_draw(
+        &self,
+        leaderboard_id: i64,
+        epoch_id: i64,
+        snapshot_hash: &str,
+        selection: &WindowSelection,
+        target_block: u64,
+    ) -> Result<WindowDraw>;
+    async fn window_draw(&self, id: i64) -> Result<Option<WindowDraw>>;
+    /// The draw a leaderboard's next window has to be started with
+    async fn pending_draw(&self, leaderboard_id: i64) -> Result<Option<WindowDraw>>;
+    /// Record the seed, players and window of a pending draw
+    async fn complete_draw(
+        &self,
+        id: i64,
+        block_hash: &str,
+        addresses: &[String],
+        tx_hash: &str,
+    ) -> Result<()>;
+
+    /// Freeze the scores of a window's players in a new round and hash them
+    async fn create_round(
+        &self,
+        leaderboard_id: i64,
+        epoch_id: Option<i64>,
+        scope: RankingScope,
+        addresses: &[String],
+    ) -> Result<WindowRound>;
+    async fn window_round(&self, id: i64) -> Result<Option<WindowRound>>;
+    /// Frozen scores of a round, sorted by address
+    async fn round_scores(&self, round_id: i64) -> Result<Vec<AddressScore>>;
+    async fn set_round_anchor(&self, round_id: i64, tx_hash: &str) -> Result<()>;
+
+    /// Record a freshly submitted transaction as pending
+    async fn track_transaction(&self, kind: &str, tx_hash: &str) -> Result<TrackedTransaction>;
+    async fn transaction(&self, id: i64) -> Result<Option<TrackedTransaction>>;
+    /// Transactions that are not final yet, oldest first. Reverted transactions stay open
+    /// until confirmed, a reorg may still include them elsewhere.
+    async fn open_transactions(&self, confirmations: u64) -> Result<Vec<TrackedTransaction>>;
+    async fn update_transaction(&self, id: i64, receipt: &TransactionReceipt<'_>) -> Result<()>;
+    async fn set_transaction_status(&self, id: i64, status: &str) -> Result<()>;
+    /// Back to pending without a receipt after the transaction's block was reorged away
+    async fn reset_transaction(&self, id: i64) -> Result<()>;
+
+    /// Last block the indexer has processed
+    async fn indexer_cursor(&self) -> Result<Option<u64>>;
+    /// Hashes of the indexed blocks up to `block`, newest first
+    async fn indexed_blocks(&self, block: u64) -> Result<Vec<(u64, String)>>;
+    /// Start a batch of indexed events, nothing is visible before it is committed
+    async fn begin_chain_batch(&self) -> Result<Box<dyn ChainBatch>>;
+    /// Discard everything indexed after `ancestor` and move the cursor back to it
+    async fn rollback_chain(&self, ancestor: u64) -> Result<()>;
+    async fn indexer_status(&self) -> Result<IndexerStatusResponse>;
+
+    async fn latest_window(&self, leaderboard_id: i64) -> Result<Option<IndexedWindow>>;
+    async fn indexed_window(&self, window_id: i64) -> Result<Option<IndexedWindow>>;
+    /// Window whose players' scores were frozen in `round_id`
+    async fn round_window(&self, round_id: i64) -> Result<Option<IndexedWindow>>;
+    async fn bet_count(&self, window_id: i64) -> Result<i64>;
+    /// Up/down bet of a window by its index
+    async fn indexed_bet(&self, window_id: i64, bet_index: i64) -> Result<Option<IndexedBet>>;
+    /// Up/down bets of a window in the order they were placed
+    async fn pool_bets(&self, window_id: i64) -> Result<Vec<PoolBet>>;
+    /// Markets of a window in the contract's order, without their pools
+    async fn window_markets(&self, window_id: i64) -> Result<Vec<IndexedMarket>>;
+    /// Bets on a market in the order they were placed
+    async fn market_stakes(&self, window_id: i64, market_index: i64) -> Result<Vec<MarketStake>>;
+    /// Total and one page of the up/down and market bets in `scope` matching `filters`,
+    /// newest first
+    async fn find_bets(
+        &self,
+        scope: BetScope<'_>,
+        filters: &BetFilterParams,
+        page: u32,
+        per_page: u32,
+    ) -> Result<(i64, Vec<IndexedBet>)>;
+    /// Window, outcome, amount and payout of every up/down and market bet of a player
+    async fn bet_outcomes(&self, bettor: &str) -> Result<Vec<BetOutcome>>;
+}
+
+/// Window, outcome, amount and payout of an indexed bet
+pub type BetOutcome = (i64, String, String, Option<String>);
+
+/// Receipt of a tracked transaction in a canonical block
+pub struct TransactionReceipt<'a> {
+    pub status: &'a str,
+    pub block_number: u64,
+    pub block_hash: &'a str,
+    pub confirmations: u64,
+    pub gas_used: Option<String>,
+    pub revert_reason: Option<String>,
+    pub reorg_count: i64,
+}
+
+/// Position of an indexed event in the chain
+pub struct EventLog<'a> {
+    pub block_number: i64,
+    pub tx_hash: &'a str,
+    pub log_index: i64,
+}
+
+/// A window as started on-chain, with the request it was started by
+pub struct NewWindow<'a> {
+    pub operator: String,
+    pub addresses: &'a [String],
+    pub started_at: i64,
+    pub contract: &'a str,
+    pub request: WindowRequest,
+}
+
+/// Chain state written by one indexer batch, in one database transaction. Events attach to
+/// the latest window of the contract that emitted them.
+#[async_trait]
+pub trait ChainBatch: Send {
+    async fn set_cursor(&mut self, block: u64) -> Result<()>;
+    async fn record_block(
+        &mut self,
+        block_number: u64,
+        block_hash: &str,
+        timestamp: u64,
+    ) -> Result<()>;
+
+    async fn insert_window(&mut self, window: &NewWindow<'_>, log: &EventLog<'_>) -> Result<i64>;
+    /// Close the contract's latest window, `None` when it was closed already
+    async fn close_window(
+        &mut self,
+        contract: &str,
+        closed_at: i64,
+        log: &EventLog<'_>,
+    ) -> Result<Option<i64>>;
+    async fn contract_window(&mut self, contract: &str) -> Result<Option<i64>>;
+    async fn window(&mut self, window_id: i64) -> Result<IndexedWindow>;
+
+    /// Append a bet to a window, `None` when it was indexed already
+    async fn insert_bet(
+        &mut self,
+        window_id: i64,
+        bettor: &str,
+        selected_address: &str,
+        position: bool,
+        amount: U256,
+        log: &EventLog<'_>,
+    ) -> Result<Option<i64>>;
+    async fn pool_bets(&mut self, window_id: i64) -> Result<Vec<PoolBet>>;
+    async fn insert_payout(
+        &mut self,
+        contract: &str,
+        bettor: &str,
+        amount: U256,
+        is_winner: bool,
+        log: &EventLog<'_>,
+    ) -> Result<()>;
+    /// Windows with up/down payouts between `from` and `to`
+    async fn paid_windows(&mut self, from: u64, to: u64) -> Result<Vec<i64>>;
+    /// Bettor, amount, block and transaction of a window's up/down payouts, in order
+    async fn window_payouts(
+        &mut self,
+        window_id: i64,
+    ) -> Result<Vec<(String, String, i64, String)>>;
+    async fn set_bet_outcome(
+        &mut self,
+        window_id: i64,
+        bet_index: i64,
+        outcome: &str,
+        payout: Option<String>,
+    ) -> Result<()>;
+    async fn set_window_settled(
+        &mut self,
+        window_id: i64,
+        block_number: i64,
+        tx_hash: &str,
+    ) -> Result<()>;
+
+    /// Index and number of outcomes of a window's market, `None` until its first bet
+    async fn market_index(
+        &mut self,
+        window_id: i64,
+        kind: u8,
+        address: &str,
+        param: U256,
+    ) -> Result<Option<(i64, i64)>>;
+    /// Number a window's next market
+    async fn insert_market(
+        &mut self,
+        window_id: i64,
+        kind: u8,
+        param: U256,
+        address: &str,
+        market: &BetMarket,
+        block_number: i64,
+    ) -> Result<()>;
+    async fn insert_market_bet(
+        &mut self,
+        window_id: i64,
+        market_index: i64,
+        bettor: &str,
+        outcome: u8,
+        amount: U256,
+        log: &EventLog<'_>,
+    ) -> Result<()>;
+    async fn market_stakes(
+        &mut self,
+        window_id: i64,
+        market_index: i64,
+    ) -> Result<Vec<MarketStake>>;
+    /// Record the winning outcome and the payout of each bet, in bet order
+    async fn settle_market(
+        &mut self,
+        window_id: i64,
+        market_index: i64,
+        outcome: u8,
+        payouts: &[Option<U256>],
+        log: &EventLog<'_>,
+    ) -> Result<()>;
+
+    async fn commit(self: Box<Self>) -> Result<()>;
+}
+
+pub type DynRepository = Arc<dyn Repository>;
+
+fn is_postgres(database_url: &str) -> bool {
+    database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+}
+
+fn check_scheme(database_url: &str) -> Result<()> {
+    if is_postgres(database_url) || database_url.starts_with("sqlite:") {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Unsupported database URL, expected sqlite: or postgres://"
+        ))
+    }
+}
+
+/// How replicas share the database
+pub enum Replication {
+    /// A SQLite database has a single replica, which writes the chain state
+    Single,
+    /// Replicas share a Postgres database. The one holding the chain writer lock indexes the
+    /// chain and follows transactions, live events are relayed through the database.
+    Shared(PostgresRepository),
+}
+
+/// Open the database and apply pending migrations
+pub async fn connect(database_url: &str) -> Result<(DynRepository, Replication)> {
+    check_scheme(database_url)?;
+    if !is_postgres(database_url) {
+        let pool = db::init_db(database_url).await?;
+        return Ok((Arc::new(SqliteRepository::new(pool)), Replication::Single));
+    }
+
+    let repository = PostgresRepository::connect(database_url).await?;
+    Ok((
+        Arc::new(repository.clone()),
+        Replication::Shared(repository),
+    ))
+}
+
+/// Rows of `_sqlx_migrations`
+type AppliedMigration = (i64, bool, Vec<u8>, chrono::DateTime<chrono::Utc>);
+
+/// Known migrations and whether they have been applied.
+fn migration_status(migrator: &Migrator, applied: Vec<AppliedMigration>) -> Vec<MigrationStatus> {
+    let applied = applied
+        .into_iter()
+        .map(|(version, success, checksum, installed_on)| {
+            (version, (success, checksum, installed_on))
+        })
+        .collect::<HashMap<_, _>>();
+
+    migrator
+        .iter()
+        .map(|migration| {
+            let applied = applied.get(&migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.is_some_and(|(success, _, _)| *success),
+                applied_at: applied.map(|(_, _, installed_on)| *installed_on),
+                // An applied migration whose file was edited afterwards
+                checksum_mismatch: applied
+                    .is_some_and(|(_, checksum, _)| **checksum != *migration.checksum),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+use crate::services::live_service::{LiveEvent, LiveFeed};
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgConnection, PgListener, PgPool, PgPoolOptions, Postgres};
+use std::{str::FromStr, time::Duration};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Versioned migrations of the shared Postgres schema
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Advisory lock key of the chain writer, "zkChain" in ASCII
+const CHAIN_WRITER_LOCK: i64 = 0x007a_6b43_6861_696e;
+
+/// Channel the replicas relay their live events on
+const LIVE_CHANNEL: &str = "live_events";
+
+/// Session holding the chain writer lock, which Postgres releases when the session ends
+pub struct ChainWriterLock {
+    _session: PgConnection,
+}
+
+/// A live event relayed to the other replicas
+#[derive(Serialize, Deserialize)]
+struct RelayedEvent {
+    /// Replica that published the event, which skips it when it comes back
+    origin: u64,
+    event: LiveEvent,
+}
+
+/// Repository on a Postgres database shared by several replicas
+#[derive(Clone)]
+pub struct PostgresRepository {
+    pool: PgPool,
+}
+
+impl PostgresRepository {
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        Self::connect_with(PgConnectOptions::from_str(database_url)?).await
+    }
+
+    pub async fn connect_with(options: PgConnectOptions) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .connect_with(options)
+            .await?;
+        POSTGRES_MIGRATOR.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Become the replica writing the chain state, `None` while another replica is
+    pub async fn try_lock_chain_writer(&self) -> Result<Option<ChainWriterLock>> {
+        let mut conn = self.pool.acquire().await?.detach();
+        let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+            .bind(CHAIN_WRITER_LOCK)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(locked.then_some(ChainWriterLock { _session: conn }))
+    }
+
+    /// Wait until this replica writes the chain state, checking every `retry`
+    pub async fn lock_chain_writer(&self, retry: Duration) -> ChainWriterLock {
+        let mut interval = tokio::time::interval(retry);
+        loop {
+            interval.tick().await;
+            match self.try_lock_chain_writer().await {
+                Ok(Some(lock)) => return lock,
+                Ok(None) => {}
+                Err(e) => warn!("replication: Failed to take the chain writer lock: {}", e),
+            }
+        }
+    }
+
+    /// Send the events published on this replica, handed over by `events`, to the other
+    /// replicas and publish theirs on `live`.
+    pub async fn relay_live_events(
+        &self,
+        live: LiveFeed,
+        mut events: UnboundedReceiver<LiveEvent>,
+    ) -> Result<()> {
+        let origin = rand::random::<u64>();
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(LIVE_CHANNEL).await?;
+
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let payload = match serde_json::to_string(&RelayedEvent { origin, event }) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        warn!("replication: Failed to serialize live event: {}", e);
+                        continue;
+                    }
+                };
+                if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+                    .bind(LIVE_CHANNEL)
+                    .bind(payload)
+                    .execute(&pool)
+                    .await
+                {
+                    warn!("replication: Failed to relay live event: {}", e);
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            info!("replication: Relaying live events on {}", LIVE_CHANNEL);
+            loop {
+                // Reconnects on its own, notifications sent in between are lost
+                let notification = match listener.recv().await {
+                    Ok(notification) => notification,
+                    Err(e) => {
+                        warn!("replication: Failed to receive live events: {}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+                match serde_json::from_str::<RelayedEvent>(notification.payload()) {
+                    Ok(relayed) if relayed.origin != origin => live.publish_local(relayed.event),
+                    Ok(_) => {}
+                    Err(e) => warn!("replication: Skipping undecodable live event: {}", e),
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl_repository!(
+    PostgresRepository,
+    Postgres,
+    PgConnection,
+    POSTGRES_MIGRATOR
+);
@@ -0,0 +1,2198 @@
+//! Queries shared by the SQLite and Postgres repositories. Both drivers accept `$N`
+//! placeholders and the schemas only differ in column types, so one implementation is
+//! expanded for each backend.
+
+pub(super) const LEADERBOARD_COLUMNS: &str = "id, slug, name, contract_address, created_at";
+pub(super) const EPOCH_COLUMNS: &str = "id, leaderboard_id, number, started_at, closed_at, \
+                                        snapshot_hash, hashed_at, record_count, tx_id";
+pub(super) const GAME_SERVER_COLUMNS: &str =
+    "id, name, public_key, address, active, last_nonce, created_at";
+pub(super) const ROUND_COLUMNS: &str = "id, leaderboard_id, epoch_id, scores_hash, round_hash, \
+                                        record_count, hashed_at, anchor_tx";
+pub(super) const AUDIT_COLUMNS: &str = "id, server_address, address, score, game_id, \
+                                        timestamp, nonce, signature, status, reason, created_at";
+
+pub(super) const TX_COLUMNS: &str = "id, kind, tx_hash, status, block_number, block_hash, \
+                                     confirmations, gas_used, revert_reason, reorg_count, \
+                                     created_at, updated_at";
+pub(super) const WINDOW_COLUMNS: &str = "id, operator, valid_addresses, started_at, start_block, \
+                                         start_tx, closed_at, close_block, close_tx, \
+                                         settled_block, settle_tx, leaderboard_id, contract, \
+                                         epoch_id, selection, round_id";
+pub(super) const BET_COLUMNS: &str = "id, window_id, bet_index, bettor, selected_address, \
+                                      position, market_index, market_outcome, amount, outcome, \
+                                      payout, block_number, tx_hash, log_index";
+/// Up/down and market bets in the shape of `BET_COLUMNS`
+pub(super) const ALL_BETS: &str = "(SELECT id, window_id, bet_index, bettor, selected_address, \
+    position, CAST(NULL AS BIGINT) AS market_index, CAST(NULL AS BIGINT) AS market_outcome, \
+    amount, outcome, payout, block_number, tx_hash, log_index FROM bets \
+    UNION ALL \
+    SELECT b.id, b.window_id, b.bet_index, b.bettor, m.address, NULL, b.market_index, \
+    b.position, b.amount, b.outcome, b.payout, b.block_number, b.tx_hash, b.log_index \
+    FROM market_bets b JOIN markets m \
+    ON m.window_id = b.window_id AND m.market_index = b.market_index) AS all_bets";
+pub(super) const MARKET_COLUMNS: &str = "window_id, market_index, address, market, outcomes, \
+                                         winning_outcome, settled_block, settle_tx";
+
+/// Rows per multi-row insert, within the parameter limits of both backends
+pub(super) const INSERT_CHUNK_SIZE: usize = 500;
+
+/// id, address, score and created_at of a stored score
+pub(super) type AddressRow = (i64, String, i64, chrono::DateTime<chrono::Utc>);
+
+/// Address, score and created_at of a frozen or exported score
+pub(super) type EpochScoreRow = (String, i64, Option<chrono::DateTime<chrono::Utc>>);
+
+/// Address, score, created_at and submitted_at of a score moved into a snapshot
+pub(super) type SnapshotRow = (
+    String,
+    i64,
+    Option<chrono::DateTime<chrono::Utc>>,
+    Option<i64>,
+);
+
+pub(super) fn from_row(
+    (id, address, score, created_at): AddressRow,
+) -> crate::models::AddressScore {
+    crate::models::AddressScore {
+        id: Some(id),
+        address,
+        score: score as u32,
+        created_at: Some(created_at),
+    }
+}
+
+/// Columns of `score_events` read into a `ScoreEvent`
+pub(super) const SCORE_EVENT_COLUMNS: &str = "id, address, score, previous_score, source, \
+                                               source_id, reason, submitted_at, recorded_at";
+
+pub(super) type ScoreEventRow = (
+    i64,
+    String,
+    Option<i64>,
+    Option<i64>,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+    i64,
+);
+
+pub(super) fn event_from_row(row: ScoreEventRow) -> crate::models::ScoreEvent {
+    let (id, address, score, previous_score, source, source_id, reason, submitted_at, recorded_at) =
+        row;
+
+    crate::models::ScoreEvent {
+        id,
+        address,
+        score: score.map(|score| score as u32),
+        previous_score: previous_score.map(|score| score as u32),
+        source,
+        source_id,
+        reason,
+        submitted_at,
+        recorded_at: chrono::DateTime::from_timestamp_millis(recorded_at).unwrap_or_default(),
+    }
+}
+
+/// A row of `score_events` to append
+pub(super) struct NewScoreEvent<'a> {
+    pub leaderboard_id: i64,
+    pub address: &'a str,
+    pub score: Option<u32>,
+    pub previous_score: Option<u32>,
+    pub source: &'static str,
+    pub source_id: Option<&'a str>,
+    pub submitted_at: Option<i64>,
+}
+
+/// Columns of `window_draws` read into a `WindowDraw`
+pub(super) const DRAW_COLUMNS: &str = "id, leaderboard_id, epoch_id, snapshot_hash, selection, \
+                                       target_block, committed_at, block_hash, addresses, tx_hash";
+
+pub(super) type DrawRow = (
+    i64,
+    i64,
+    i64,
+    String,
+    String,
+    i64,
+    i64,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+pub(super) fn draw_from_row(row: DrawRow) -> anyhow::Result<crate::models::WindowDraw> {
+    let (
+        id,
+        leaderboard_id,
+        epoch_id,
+        snapshot_hash,
+        selection,
+        target_block,
+        committed_at,
+        block_hash,
+        addresses,
+        tx_hash,
+    ) = row;
+
+    Ok(crate::models::WindowDraw {
+        id,
+        leaderboard_id,
+        epoch_id,
+        snapshot_hash,
+        selection: serde_json::from_str(&selection)?,
+        target_block: target_block as u64,
+        committed_at: chrono::DateTime::from_timestamp_millis(committed_at).unwrap_or_default(),
+        block_hash,
+        addresses: addresses
+            .map(|addresses| serde_json::from_str(&addresses))
+            .transpose()?,
+        tx_hash,
+    })
+}
+
+/// Table and filter holding the scores of a ranking scope, with the `$1` it filters on
+pub(super) fn score_source(scope: crate::models::RankingScope) -> (&'static str, i64) {
+    match scope {
+        crate::models::RankingScope::Live { leaderboard_id } => {
+            ("addresses WHERE leaderboard_id = $1", leaderboard_id)
+        }
+        crate::models::RankingScope::Snapshot { epoch_id } => {
+            ("epoch_scores WHERE epoch_id = $1", epoch_id)
+        }
+    }
+}
+
+/// `INSERT INTO addresses` of a chunk of scores, to be completed by the caller
+pub(super) fn insert_scores<'a, DB: sqlx::Database>(
+    leaderboard_id: i64,
+    addresses: &'a [crate::models::AddressScore],
+) -> sqlx::QueryBuilder<'a, DB>
+where
+    i64: sqlx::Encode<'a, DB> + sqlx::Type<DB>,
+    &'a str: sqlx::Encode<'a, DB> + sqlx::Type<DB>,
+{
+    let mut query =
+        sqlx::QueryBuilder::new("INSERT INTO addresses (address, score, leaderboard_id) ");
+    query.push_values(addresses, |mut row, addr| {
+        row.push_bind(addr.address.as_str())
+            .push_bind(addr.score as i64)
+            .push_bind(leaderboard_id);
+    });
+    query
+}
+
+/// Implement `Repository` for `$repository { pool }` on `$database`, whose connections are
+/// `$connection`.
+macro_rules! impl_repository {
+    ($repository:ident, $database:ty, $connection:ty, $migrator:expr) => {
+        use crate::models::{
+            AddressScore, Epoch, ExportScope, GameServer, Leaderboard, MigrationStatus,
+            RankingScope, ScoreAuditEntry, ScoreCounts, ScoreEvent, ScoreSubmission,
+            ScoreSubmissionResult, WindowDraw, WindowRequest, WindowRound, WindowSelection,
+        };
+        use crate::repository::sql::{
+            draw_from_row, event_from_row, from_row, insert_scores, score_source, AddressRow,
+            DrawRow, NewScoreEvent, DRAW_COLUMNS, ROUND_COLUMNS,
+            ScoreEventRow, SCORE_EVENT_COLUMNS, EpochScoreRow, SnapshotRow,
+            AUDIT_COLUMNS, EPOCH_COLUMNS, GAME_SERVER_COLUMNS, INSERT_CHUNK_SIZE,
+            LEADERBOARD_COLUMNS,
+        };
+        use crate::models::{
+            BetFilterParams, BetMarket, IndexedBet, IndexedMarket, IndexedWindow,
+            IndexerStatusResponse, TrackedTransaction,
+        };
+        use crate::repository::sql::{
+            ALL_BETS, BET_COLUMNS, MARKET_COLUMNS, TX_COLUMNS, WINDOW_COLUMNS,
+        };
+        use crate::repository::{
+            BetOutcome, ChainBatch, CheckedSubmission, EventLog, NewWindow, Repository,
+            TransactionReceipt,
+        };
+        use crate::services::bet_history_service::BetScope;
+        use crate::services::indexer_service::{OUTCOME_LOST, OUTCOME_WON};
+        use crate::services::market_service::{self, MarketStake};
+        use crate::services::payout_service::PoolBet;
+        use crate::services::tx_tracker_service::{STATUS_MINED, STATUS_PENDING, STATUS_REVERTED};
+        use ethers::types::U256;
+        use crate::services::hash_service;
+        use crate::services::history_service::{
+            EVENT_CORRECTION, EVENT_EPOCH, EVENT_IMPORT, EVENT_SEED, EVENT_SUBMISSION,
+        };
+        use crate::services::score_service::{
+            SUBMISSION_DUPLICATE, SUBMISSION_INSERTED, SUBMISSION_REJECTED, SUBMISSION_REPLAYED,
+            SUBMISSION_STALE, SUBMISSION_UPDATED,
+        };
+        use anyhow::anyhow;
+        use async_trait::async_trait;
+
+        async fn audit(
+            conn: &mut $connection,
+            server: Option<&str>,
+            submission: &ScoreSubmission,
+            status: &str,
+            reason: &str,
+        ) -> anyhow::Result<()> {
+            sqlx::query(
+                r#"
+                INSERT INTO score_audit
+                    (server_address, address, score, game_id, timestamp, nonce, signature,
+                     status, reason)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(server)
+            .bind(&submission.address)
+            .bind(submission.score as i64)
+            .bind(&submission.game_id)
+            .bind(submission.timestamp)
+            .bind(submission.nonce.to_string())
+            .bind(&submission.signature)
+            .bind(status)
+            .bind(reason)
+            .execute(&mut *conn)
+            .await?;
+
+            Ok(())
+        }
+
+        /// Append score events, all recorded now
+        async fn record_events(
+            conn: &mut $connection,
+            events: &[NewScoreEvent<'_>],
+        ) -> anyhow::Result<()> {
+            let recorded_at = chrono::Utc::now().timestamp_millis();
+
+            for chunk in events.chunks(INSERT_CHUNK_SIZE) {
+                let mut query = sqlx::QueryBuilder::<$database>::new(
+                    "INSERT INTO score_events (leaderboard_id, address, score, previous_score, \
+                     source, source_id, submitted_at, recorded_at) ",
+                );
+                query.push_values(chunk, |mut row, event| {
+                    row.push_bind(event.leaderboard_id)
+                        .push_bind(event.address)
+                        .push_bind(event.score.map(|score| score as i64))
+                        .push_bind(event.previous_score.map(|score| score as i64))
+                        .push_bind(event.source)
+                        .push_bind(event.source_id)
+                        .push_bind(event.submitted_at)
+                        .push_bind(recorded_at);
+                });
+                query.build().execute(&mut *conn).await?;
+            }
+
+            Ok(())
+        }
+
+        async fn store_cursor(conn: &mut $connection, block: u64) -> anyhow::Result<()> {
+            sqlx::query(
+                "INSERT INTO indexer_state (id, last_block) VALUES (1, $1) \
+                 ON CONFLICT (id) DO UPDATE SET last_block = excluded.last_block",
+            )
+            .bind(block as i64)
+            .execute(&mut *conn)
+            .await?;
+
+            Ok(())
+        }
+
+        async fn fetch_window(
+            conn: &mut $connection,
+            window_id: i64,
+        ) -> anyhow::Result<Option<IndexedWindow>> {
+            let window = sqlx::query_as::<_, IndexedWindow>(&format!(
+                "SELECT {} FROM windows WHERE id = $1",
+                WINDOW_COLUMNS
+            ))
+            .bind(window_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            Ok(window)
+        }
+
+        async fn fetch_pool_bets(
+            conn: &mut $connection,
+            window_id: i64,
+        ) -> anyhow::Result<Vec<PoolBet>> {
+            let rows = sqlx::query_as::<_, (String, String, bool, String)>(
+                "SELECT bettor, selected_address, position, amount FROM bets \
+                 WHERE window_id = $1 ORDER BY bet_index",
+            )
+            .bind(window_id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+            rows.into_iter()
+                .map(|(bettor, selected_address, position, amount)| {
+                    Ok(PoolBet {
+                        bettor,
+                        selected_address,
+                        position,
+                        amount: U256::from_dec_str(&amount)?,
+                    })
+                })
+                .collect()
+        }
+
+        async fn fetch_market_stakes(
+            conn: &mut $connection,
+            window_id: i64,
+            market_index: i64,
+        ) -> anyhow::Result<Vec<MarketStake>> {
+            let rows = sqlx::query_as::<_, (i64, String)>(
+                "SELECT position, amount FROM market_bets \
+                 WHERE window_id = $1 AND market_index = $2 ORDER BY bet_index",
+            )
+            .bind(window_id)
+            .bind(market_index)
+            .fetch_all(&mut *conn)
+            .await?;
+
+            rows.into_iter()
+                .map(|(position, amount)| {
+                    Ok(MarketStake {
+                        outcome: position as u8,
+                        amount: U256::from_dec_str(&amount)?,
+                    })
+                })
+                .collect()
+        }
+
+        /// Restrict a bet query to `scope` and the optional filters
+        fn push_bet_filters<'a>(
+            query: &mut sqlx::QueryBuilder<'a, $database>,
+            scope: &BetScope<'a>,
+            filters: &'a BetFilterParams,
+        ) {
+            match scope {
+                BetScope::Player(bettor) => query.push(" WHERE bettor = ").push_bind(*bettor),
+                BetScope::Window(window_id) => {
+                    query.push(" WHERE window_id = ").push_bind(*window_id)
+                }
+            };
+            if let Some(window_id) = filters.window_id {
+                query.push(" AND window_id = ").push_bind(window_id);
+            }
+            if let Some(selected_address) = &filters.selected_address {
+                query
+                    .push(" AND selected_address = ")
+                    .push_bind(selected_address.to_lowercase());
+            }
+            if let Some(position) = filters.position {
+                query.push(" AND position = ").push_bind(position);
+            }
+            if let Some(outcome) = &filters.outcome {
+                query.push(" AND outcome = ").push_bind(outcome.as_str());
+            }
+        }
+
+        #[async_trait]
+        impl Repository for $repository {
+            async fn migration_status(&self) -> anyhow::Result<Vec<MigrationStatus>> {
+                let applied = sqlx::query_as(
+                    "SELECT version, success, checksum, installed_on FROM _sqlx_migrations",
+                )
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(crate::repository::migration_status(&$migrator, applied))
+            }
+
+            async fn get_leaderboards(&self) -> anyhow::Result<Vec<Leaderboard>> {
+                let leaderboards = sqlx::query_as::<_, Leaderboard>(&format!(
+                    "SELECT {} FROM leaderboards ORDER BY id",
+                    LEADERBOARD_COLUMNS
+                ))
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(leaderboards)
+            }
+
+            async fn find_leaderboard(&self, slug: &str) -> anyhow::Result<Option<Leaderboard>> {
+                let leaderboard = sqlx::query_as::<_, Leaderboard>(&format!(
+                    "SELECT {} FROM leaderboards WHERE slug = $1",
+                    LEADERBOARD_COLUMNS
+                ))
+                .bind(slug)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(leaderboard)
+            }
+
+            async fn create_leaderboard(
+                &self,
+                slug: &str,
+                name: &str,
+                contract_address: Option<String>,
+            ) -> anyhow::Result<Leaderboard> {
+                let leaderboard = sqlx::query_as::<_, Leaderboard>(&format!(
+                    "INSERT INTO leaderboards (slug, name, contract_address) VALUES ($1, $2, $3) \
+                     RETURNING {}",
+                    LEADERBOARD_COLUMNS
+                ))
+                .bind(slug)
+                .bind(name)
+                .bind(contract_address)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(leaderboard)
+            }
+
+            async fn contract_leaderboards(&self) -> anyhow::Result<Vec<(String, i64)>> {
+                let rows = sqlx::query_as::<_, (String, i64)>(
+                    "SELECT contract_address, id FROM leaderboards \
+                     WHERE contract_address IS NOT NULL",
+                )
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows)
+            }
+
+            async fn insert_addresses(
+                &self,
+                leaderboard_id: i64,
+                addresses: &[AddressScore],
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let mut stored_addresses = Vec::with_capacity(addresses.len());
+                let mut tx = self.pool.begin().await?;
+
+                for chunk in addresses.chunks(INSERT_CHUNK_SIZE) {
+                    let mut query = insert_scores::<$database>(leaderboard_id, chunk);
+                    query.push(" RETURNING id, address, score, created_at");
+                    let stored = query
+                        .build_query_as::<AddressRow>()
+                        .fetch_all(&mut *tx)
+                        .await?;
+
+                    stored_addresses.extend(stored.into_iter().map(from_row));
+                }
+
+                let events: Vec<NewScoreEvent> = stored_addresses
+                    .iter()
+                    .map(|stored| NewScoreEvent {
+                        leaderboard_id,
+                        address: &stored.address,
+                        score: Some(stored.score),
+                        previous_score: None,
+                        source: EVENT_SEED,
+                        source_id: None,
+                        submitted_at: None,
+                    })
+                    .collect();
+                record_events(&mut tx, &events).await?;
+
+                tx.commit().await?;
+                Ok(stored_addresses)
+            }
+
+            async fn import_addresses(
+                &self,
+                leaderboard_id: i64,
+                addresses: &[AddressScore],
+            ) -> anyhow::Result<u64> {
+                let mut inserted = Vec::new();
+                let mut tx = self.pool.begin().await?;
+
+                for chunk in addresses.chunks(INSERT_CHUNK_SIZE) {
+                    let mut query = insert_scores::<$database>(leaderboard_id, chunk);
+                    query.push(" ON CONFLICT (leaderboard_id, address) DO NOTHING");
+                    query.push(" RETURNING address, score");
+                    inserted.extend(
+                        query
+                            .build_query_as::<(String, i64)>()
+                            .fetch_all(&mut *tx)
+                            .await?,
+                    );
+                }
+
+                let events: Vec<NewScoreEvent> = inserted
+                    .iter()
+                    .map(|(address, score)| NewScoreEvent {
+                        leaderboard_id,
+                        address,
+                        score: Some(*score as u32),
+                        previous_score: None,
+                        source: EVENT_IMPORT,
+                        source_id: None,
+                        submitted_at: None,
+                    })
+                    .collect();
+                record_events(&mut tx, &events).await?;
+
+                tx.commit().await?;
+                Ok(inserted.len() as u64)
+            }
+
+            async fn get_stored_addresses(
+                &self,
+                leaderboard_id: i64,
+                page: u32,
+                per_page: u32,
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let offset = (page.max(1) - 1) * per_page;
+
+                let addresses = sqlx::query_as::<_, AddressRow>(
+                    r#"
+                    SELECT id, address, score, created_at
+                    FROM addresses
+                    WHERE leaderboard_id = $1
+                    ORDER BY created_at DESC
+                    LIMIT $2 OFFSET $3
+                    "#,
+                )
+                .bind(leaderboard_id)
+                .bind(per_page as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(addresses.into_iter().map(from_row).collect())
+            }
+
+            async fn get_all_addresses(
+                &self,
+                leaderboard_id: i64,
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let addresses = sqlx::query_as::<_, AddressRow>(
+                    r#"
+                    SELECT id, address, score, created_at
+                    FROM addresses
+                    WHERE leaderboard_id = $1
+                    ORDER BY created_at DESC
+                    "#,
+                )
+                .bind(leaderboard_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(addresses.into_iter().map(from_row).collect())
+            }
+
+            async fn export_scores(
+                &self,
+                scope: &ExportScope,
+                after: &str,
+                limit: u32,
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let rows = match scope {
+                    ExportScope::Snapshot { epoch_id } => {
+                        sqlx::query_as::<_, EpochScoreRow>(
+                            "SELECT address, score, created_at FROM epoch_scores \
+                             WHERE epoch_id = $1 AND address > $2 ORDER BY address LIMIT $3",
+                        )
+                        .bind(epoch_id)
+                        .bind(after)
+                        .bind(limit as i64)
+                        .fetch_all(&self.pool)
+                        .await?
+                    }
+                    // Timestamps are bound naive, SQLite stores them as UTC text
+                    ExportScope::Live {
+                        leaderboard_id,
+                        from,
+                        to,
+                    } => {
+                        sqlx::query_as::<_, EpochScoreRow>(
+                            "SELECT address, score, created_at FROM addresses \
+                             WHERE leaderboard_id = $1 AND address > $2 \
+                             AND ($3 IS NULL OR created_at >= $3) \
+                             AND ($4 IS NULL OR created_at < $4) \
+                             ORDER BY address LIMIT $5",
+                        )
+                        .bind(leaderboard_id)
+                        .bind(after)
+                        .bind(from.map(|from| from.naive_utc()))
+                        .bind(to.map(|to| to.naive_utc()))
+                        .bind(limit as i64)
+                        .fetch_all(&self.pool)
+                        .await?
+                    }
+                };
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(address, score, created_at)| AddressScore {
+                        id: None,
+                        address,
+                        score: score as u32,
+                        created_at,
+                    })
+                    .collect())
+            }
+
+            async fn ranked_scores(
+                &self,
+                scope: RankingScope,
+                offset: u64,
+                limit: u32,
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let (source, key) = score_source(scope);
+                let rows = sqlx::query_as::<_, (String, i64)>(&format!(
+                    "SELECT address, score FROM {} ORDER BY score DESC, address \
+                     LIMIT $2 OFFSET $3",
+                    source
+                ))
+                .bind(key)
+                .bind(limit as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(address, score)| AddressScore {
+                        id: None,
+                        address,
+                        score: score as u32,
+                        created_at: None,
+                    })
+                    .collect())
+            }
+
+            async fn player_score(
+                &self,
+                scope: RankingScope,
+                address: &str,
+            ) -> anyhow::Result<Option<u32>> {
+                let (source, key) = score_source(scope);
+                let score = sqlx::query_scalar::<_, i64>(&format!(
+                    "SELECT score FROM {} AND address = $2",
+                    source
+                ))
+                .bind(key)
+                .bind(address)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(score.map(|score| score as u32))
+            }
+
+            async fn count_scores(
+                &self,
+                scope: RankingScope,
+                score: u32,
+            ) -> anyhow::Result<ScoreCounts> {
+                let (source, key) = score_source(scope);
+                let (higher, equal, total) = sqlx::query_as::<_, (i64, i64, i64)>(&format!(
+                    "SELECT COALESCE(SUM(CASE WHEN score > $2 THEN 1 ELSE 0 END), 0), \
+                     COALESCE(SUM(CASE WHEN score = $2 THEN 1 ELSE 0 END), 0), COUNT(*) \
+                     FROM {}",
+                    source
+                ))
+                .bind(key)
+                .bind(score as i64)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(ScoreCounts {
+                    higher: higher as u64,
+                    equal: equal as u64,
+                    total: total as u64,
+                })
+            }
+
+            async fn score_at(
+                &self,
+                scope: RankingScope,
+                offset: u64,
+            ) -> anyhow::Result<Option<u32>> {
+                let (source, key) = score_source(scope);
+                let score = sqlx::query_scalar::<_, i64>(&format!(
+                    "SELECT score FROM {} ORDER BY score LIMIT 1 OFFSET $2",
+                    source
+                ))
+                .bind(key)
+                .bind(offset as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(score.map(|score| score as u32))
+            }
+
+            async fn sample_band(
+                &self,
+                scope: RankingScope,
+                band: std::ops::Range<i64>,
+                count: u32,
+            ) -> anyhow::Result<Vec<String>> {
+                let (source, key) = score_source(scope);
+                let addresses = sqlx::query_scalar::<_, String>(&format!(
+                    "SELECT address FROM {} AND score >= $2 AND score < $3 \
+                     ORDER BY RANDOM() LIMIT $4",
+                    source
+                ))
+                .bind(key)
+                .bind(band.start)
+                .bind(band.end)
+                .bind(count as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(addresses)
+            }
+
+            async fn score_history(
+                &self,
+                leaderboard_id: i64,
+                address: &str,
+                page: u32,
+                per_page: u32,
+            ) -> anyhow::Result<Vec<ScoreEvent>> {
+                let offset = (page.max(1) - 1) * per_page;
+
+                let rows = sqlx::query_as::<_, ScoreEventRow>(&format!(
+                    "SELECT {} FROM score_events WHERE leaderboard_id = $1 AND address = $2 \
+                     ORDER BY recorded_at, id LIMIT $3 OFFSET $4",
+                    SCORE_EVENT_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .bind(address)
+                .bind(per_page as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows.into_iter().map(event_from_row).collect())
+            }
+
+            async fn score_as_of(
+                &self,
+                leaderboard_id: i64,
+                address: &str,
+                at: i64,
+            ) -> anyhow::Result<Option<u32>> {
+                let score = sqlx::query_scalar::<_, Option<i64>>(
+                    "SELECT score FROM score_events \
+                     WHERE leaderboard_id = $1 AND address = $2 AND recorded_at <= $3 \
+                     ORDER BY recorded_at DESC, id DESC LIMIT 1",
+                )
+                .bind(leaderboard_id)
+                .bind(address)
+                .bind(at)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(score.flatten().map(|score| score as u32))
+            }
+
+            async fn correct_score(
+                &self,
+                leaderboard_id: i64,
+                address: &str,
+                score: u32,
+                reason: &str,
+            ) -> anyhow::Result<ScoreEvent> {
+                let mut tx = self.pool.begin().await?;
+
+                let previous_score = sqlx::query_scalar::<_, i64>(
+                    "SELECT score FROM addresses WHERE leaderboard_id = $1 AND address = $2",
+                )
+                .bind(leaderboard_id)
+                .bind(address)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let statement = match previous_score {
+                    Some(_) => {
+                        "UPDATE addresses SET score = $1, updated_at = CURRENT_TIMESTAMP \
+                         WHERE leaderboard_id = $2 AND address = $3"
+                    }
+                    None => {
+                        "INSERT INTO addresses (score, leaderboard_id, address, updated_at) \
+                         VALUES ($1, $2, $3, CURRENT_TIMESTAMP)"
+                    }
+                };
+                sqlx::query(statement)
+                    .bind(score as i64)
+                    .bind(leaderboard_id)
+                    .bind(address)
+                    .execute(&mut *tx)
+                    .await?;
+
+                let row = sqlx::query_as::<_, ScoreEventRow>(&format!(
+                    "INSERT INTO score_events (leaderboard_id, address, score, previous_score, \
+                     source, reason, recorded_at) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                     RETURNING {}",
+                    SCORE_EVENT_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .bind(address)
+                .bind(score as i64)
+                .bind(previous_score)
+                .bind(EVENT_CORRECTION)
+                .bind(reason)
+                .bind(chrono::Utc::now().timestamp_millis())
+                .fetch_one(&mut *tx)
+                .await?;
+
+                tx.commit().await?;
+                Ok(event_from_row(row))
+            }
+
+            async fn get_epochs(&self, leaderboard_id: i64) -> anyhow::Result<Vec<Epoch>> {
+                let epochs = sqlx::query_as::<_, Epoch>(&format!(
+                    "SELECT {} FROM epochs WHERE leaderboard_id = $1 ORDER BY number DESC",
+                    EPOCH_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(epochs)
+            }
+
+            async fn find_epoch(
+                &self,
+                leaderboard_id: i64,
+                number: i64,
+            ) -> anyhow::Result<Option<Epoch>> {
+                let epoch = sqlx::query_as::<_, Epoch>(&format!(
+                    "SELECT {} FROM epochs WHERE leaderboard_id = $1 AND number = $2",
+                    EPOCH_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .bind(number)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(epoch)
+            }
+
+            async fn get_epoch(&self, id: i64) -> anyhow::Result<Option<Epoch>> {
+                let epoch = sqlx::query_as::<_, Epoch>(&format!(
+                    "SELECT {} FROM epochs WHERE id = $1",
+                    EPOCH_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(epoch)
+            }
+
+            async fn latest_epoch(&self, leaderboard_id: i64) -> anyhow::Result<Option<Epoch>> {
+                let epoch = sqlx::query_as::<_, Epoch>(&format!(
+                    "SELECT {} FROM epochs WHERE leaderboard_id = $1 \
+                     ORDER BY number DESC LIMIT 1",
+                    EPOCH_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(epoch)
+            }
+
+            async fn get_epoch_scores(
+                &self,
+                epoch_id: i64,
+                page: u32,
+                per_page: u32,
+            ) -> anyhow::Result<Vec<AddressScore>> {
+                let offset = (page.max(1) - 1) * per_page;
+
+                let rows = sqlx::query_as::<_, EpochScoreRow>(
+                    "SELECT address, score, created_at FROM epoch_scores WHERE epoch_id = $1 \
+                     ORDER BY score DESC, address LIMIT $2 OFFSET $3",
+                )
+                .bind(epoch_id)
+                .bind(per_page as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(address, score, created_at)| AddressScore {
+                        id: None,
+                        address,
+                        score: score as u32,
+                        created_at,
+                    })
+                    .collect())
+            }
+
+            async fn close_epoch(&self, leaderboard_id: i64) -> anyhow::Result<Epoch> {
+                let mut tx = self.pool.begin().await?;
+
+                // Taking the rows out first locks them, scores submitted meanwhile go to the
+                // next epoch
+                let rows = sqlx::query_as::<_, SnapshotRow>(
+                    "DELETE FROM addresses WHERE leaderboard_id = $1 \
+                     RETURNING address, score, created_at, submitted_at",
+                )
+                .bind(leaderboard_id)
+                .fetch_all(&mut *tx)
+                .await?;
+                if rows.is_empty() {
+                    return Err(anyhow!("No scores to snapshot"));
+                }
+
+                let hash = hash_service::hash_address_data(
+                    rows.iter()
+                        .map(|(address, score, created_at, _)| AddressScore {
+                            id: None,
+                            address: address.clone(),
+                            score: *score as u32,
+                            created_at: *created_at,
+                        })
+                        .collect(),
+                )?;
+
+                let epoch = sqlx::query_as::<_, Epoch>(&format!(
+                    r#"
+                    INSERT INTO epochs
+                        (leaderboard_id, number, started_at, snapshot_hash, hashed_at,
+                         record_count)
+                    VALUES (
+                        $1,
+                        (SELECT COALESCE(MAX(number), 0) + 1 FROM epochs WHERE leaderboard_id = $1),
+                        (SELECT MAX(closed_at) FROM epochs WHERE leaderboard_id = $1),
+                        $2, $3, $4
+                    )
+                    RETURNING {}
+                    "#,
+                    EPOCH_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .bind(&hash.hash)
+                .bind(hash.timestamp)
+                .bind(hash.record_count as i64)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                for chunk in rows.chunks(INSERT_CHUNK_SIZE) {
+                    let mut query = sqlx::QueryBuilder::<$database>::new(
+                        "INSERT INTO epoch_scores \
+                         (epoch_id, address, score, created_at, submitted_at) ",
+                    );
+                    query.push_values(chunk, |mut row, (address, score, created_at, submitted_at)| {
+                        row.push_bind(epoch.id)
+                            .push_bind(address)
+                            .push_bind(score)
+                            .push_bind(created_at)
+                            .push_bind(submitted_at);
+                    });
+                    query.build().execute(&mut *tx).await?;
+                }
+
+                let number = epoch.number.to_string();
+                let events: Vec<NewScoreEvent> = rows
+                    .iter()
+                    .map(|(address, score, _, _)| NewScoreEvent {
+                        leaderboard_id,
+                        address,
+                        score: None,
+                        previous_score: Some(*score as u32),
+                        source: EVENT_EPOCH,
+                        source_id: Some(&number),
+                        submitted_at: None,
+                    })
+                    .collect();
+                record_events(&mut tx, &events).await?;
+
+                tx.commit().await?;
+                Ok(epoch)
+            }
+
+            async fn set_epoch_anchor(&self, epoch_id: i64, tx_id: i64) -> anyhow::Result<()> {
+                sqlx::query("UPDATE epochs SET tx_id = $1 WHERE id = $2")
+                    .bind(tx_id)
+                    .bind(epoch_id)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn insert_game_server(
+                &self,
+                name: &str,
+                public_key: &str,
+                address: &str,
+            ) -> anyhow::Result<GameServer> {
+                let server = sqlx::query_as::<_, GameServer>(&format!(
+                    "INSERT INTO game_servers (name, public_key, address) VALUES ($1, $2, $3) \
+                     RETURNING {}",
+                    GAME_SERVER_COLUMNS
+                ))
+                .bind(name)
+                .bind(public_key)
+                .bind(address)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(server)
+            }
+
+            async fn get_game_servers(&self) -> anyhow::Result<Vec<GameServer>> {
+                let servers = sqlx::query_as::<_, GameServer>(&format!(
+                    "SELECT {} FROM game_servers ORDER BY id",
+                    GAME_SERVER_COLUMNS
+                ))
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(servers)
+            }
+
+            async fn set_game_server_active(&self, id: i64, active: bool) -> anyhow::Result<bool> {
+                let updated = sqlx::query("UPDATE game_servers SET active = $1 WHERE id = $2")
+                    .bind(active)
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(updated.rows_affected() > 0)
+            }
+
+            async fn get_audit_entries(
+                &self,
+                page: u32,
+                per_page: u32,
+            ) -> anyhow::Result<Vec<ScoreAuditEntry>> {
+                let offset = (page.max(1) - 1) * per_page;
+
+                let entries = sqlx::query_as::<_, ScoreAuditEntry>(&format!(
+                    "SELECT {} FROM score_audit ORDER BY id DESC LIMIT $1 OFFSET $2",
+                    AUDIT_COLUMNS
+                ))
+                .bind(per_page as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(entries)
+            }
+
+            async fn store_submissions(
+                &self,
+                submissions: &[CheckedSubmission<'_>],
+            ) -> anyhow::Result<Vec<ScoreSubmissionResult>> {
+                let mut results = Vec::with_capacity(submissions.len());
+                let mut tx = self.pool.begin().await?;
+
+                for checked in submissions {
+                    let submission = checked.submission;
+                    let result = |address: &str, status: &str, reason: Option<String>| {
+                        ScoreSubmissionResult {
+                            address: address.to_string(),
+                            game_id: submission.game_id.clone(),
+                            nonce: submission.nonce,
+                            status: status.to_string(),
+                            reason,
+                        }
+                    };
+                    let rejected = |address: &str, reason: String| {
+                        result(address, SUBMISSION_REJECTED, Some(reason))
+                    };
+
+                    let signer = match &checked.signer {
+                        Ok(signer) => format!("{:?}", signer),
+                        Err(reason) => {
+                            audit(&mut tx, None, submission, SUBMISSION_REJECTED, reason).await?;
+                            results.push(rejected(&submission.address, reason.clone()));
+                            continue;
+                        }
+                    };
+
+                    let server = Some(signer.as_str());
+
+                    let found = sqlx::query_as::<_, (i64, bool)>(
+                        "SELECT id, active FROM game_servers WHERE address = $1",
+                    )
+                    .bind(&signer)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    let server_id = match found {
+                        Some((id, true)) => id,
+                        Some((_, false)) | None => {
+                            let reason = "Signer is not an active game server".to_string();
+                            audit(&mut tx, server, submission, SUBMISSION_REJECTED, &reason).await?;
+                            results.push(rejected(&submission.address, reason));
+                            continue;
+                        }
+                    };
+
+                    let address = match &checked.address {
+                        Ok(address) => address,
+                        Err(reason) => {
+                            audit(&mut tx, server, submission, SUBMISSION_REJECTED, reason).await?;
+                            results.push(rejected(&submission.address, reason.clone()));
+                            continue;
+                        }
+                    };
+
+                    let leaderboard_id =
+                        sqlx::query_scalar::<_, i64>("SELECT id FROM leaderboards WHERE slug = $1")
+                            .bind(&submission.game_id)
+                            .fetch_optional(&mut *tx)
+                            .await?;
+                    let Some(leaderboard_id) = leaderboard_id else {
+                        let reason = "Unknown leaderboard".to_string();
+                        audit(&mut tx, server, submission, SUBMISSION_REJECTED, &reason).await?;
+                        results.push(rejected(address, reason));
+                        continue;
+                    };
+
+                    // Conditional so replicas sharing a Postgres database can't both accept
+                    // the same nonce, the second update waits for the first and matches nothing
+                    let advanced = sqlx::query(
+                        "UPDATE game_servers SET last_nonce = $1 \
+                         WHERE id = $2 AND (last_nonce IS NULL OR last_nonce < $1)",
+                    )
+                    .bind(submission.nonce as i64)
+                    .bind(server_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    if advanced.rows_affected() == 0 {
+                        let reason = "Nonce was already used".to_string();
+                        audit(&mut tx, server, submission, SUBMISSION_REPLAYED, &reason).await?;
+                        results.push(result(address, SUBMISSION_REPLAYED, Some(reason)));
+                        continue;
+                    }
+
+                    let existing = sqlx::query_as::<_, (i64, i64, Option<i64>)>(
+                        "SELECT id, score, submitted_at FROM addresses \
+                         WHERE leaderboard_id = $1 AND address = $2",
+                    )
+                    .bind(leaderboard_id)
+                    .bind(address)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+
+                    let previous_score = existing.map(|(_, score, _)| score as u32);
+                    let status = match existing {
+                        None => {
+                            sqlx::query(
+                                "INSERT INTO addresses \
+                                 (address, score, leaderboard_id, submitted_at, updated_at) \
+                                 VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)",
+                            )
+                            .bind(address)
+                            .bind(submission.score as i64)
+                            .bind(leaderboard_id)
+                            .bind(submission.timestamp)
+                            .execute(&mut *tx)
+                            .await?;
+                            SUBMISSION_INSERTED
+                        }
+                        Some((_, score, Some(submitted_at)))
+                            if submitted_at == submission.timestamp
+                                && score == submission.score as i64 =>
+                        {
+                            SUBMISSION_DUPLICATE
+                        }
+                        Some((_, _, Some(submitted_at)))
+                            if submitted_at >= submission.timestamp =>
+                        {
+                            SUBMISSION_STALE
+                        }
+                        Some((id, _, _)) => {
+                            sqlx::query(
+                                "UPDATE addresses \
+                                 SET score = $1, submitted_at = $2, updated_at = CURRENT_TIMESTAMP \
+                                 WHERE id = $3",
+                            )
+                            .bind(submission.score as i64)
+                            .bind(submission.timestamp)
+                            .bind(id)
+                            .execute(&mut *tx)
+                            .await?;
+                            SUBMISSION_UPDATED
+                        }
+                    };
+                    if status == SUBMISSION_INSERTED || status == SUBMISSION_UPDATED {
+                        let event = NewScoreEvent {
+                            leaderboard_id,
+                            address,
+                            score: Some(submission.score),
+                            previous_score,
+                            source: EVENT_SUBMISSION,
+                            source_id: server,
+                            submitted_at: Some(submission.timestamp),
+                        };
+                        record_events(&mut tx, &[event]).await?;
+                    }
+                    results.push(result(address, status, None));
+                }
+
+                tx.commit().await?;
+                Ok(results)
+            }
+
+            async fn record_window_request(
+                &self,
+                tx_hash: &str,
+                leaderboard_id: i64,
+                epoch_id: Option<i64>,
+                selection: &WindowSelection,
+                round_id: Option<i64>,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    "INSERT INTO window_requests \
+                     (tx_hash, leaderboard_id, epoch_id, selection, round_id) \
+                     VALUES ($1, $2, $3, $4, $5) \
+                     ON CONFLICT (tx_hash) DO UPDATE \
+                     SET leaderboard_id = excluded.leaderboard_id, \
+                         epoch_id = excluded.epoch_id, selection = excluded.selection, \
+                         round_id = excluded.round_id",
+                )
+                .bind(tx_hash)
+                .bind(leaderboard_id)
+                .bind(epoch_id)
+                .bind(serde_json::to_string(selection)?)
+                .bind(round_id)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn window_request(
+                &self,
+                tx_hash: &str,
+            ) -> anyhow::Result<Option<WindowRequest>> {
+                let row = sqlx::query_as::<_, (i64, Option<i64>, Option<String>, Option<i64>)>(
+                    "SELECT leaderboard_id, epoch_id, selection, round_id FROM window_requests \
+                     WHERE tx_hash = $1",
+                )
+                .bind(tx_hash)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                row.map(|(leaderboard_id, epoch_id, selection, round_id)| {
+                    Ok(WindowRequest {
+                        leaderboard_id,
+                        epoch_id,
+                        selection: selection
+                            .map(|selection| serde_json::from_str(&selection))
+                            .transpose()?,
+                        round_id,
+                    })
+                })
+                .transpose()
+            }
+
+            async fn create_draw(
+                &self,
+                leaderboard_id: i64,
+                epoch_id: i64,
+                snapshot_hash: &str,
+                selection: &WindowSelection,
+                target_block: u64,
+            ) -> anyhow::Result<WindowDraw> {
+                let row = sqlx::query_as::<_, DrawRow>(&format!(
+                    "INSERT INTO window_draws \
+                     (leaderboard_id, epoch_id, snapshot_hash, selection, target_block, \
+                      committed_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+                    DRAW_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .bind(epoch_id)
+                .bind(snapshot_hash)
+                .bind(serde_json::to_string(selection)?)
+                .bind(target_block as i64)
+                .bind(chrono::Utc::now().timestamp_millis())
+                .fetch_one(&self.pool)
+                .await?;
+
+                draw_from_row(row)
+            }
+
+            async fn window_draw(&self, id: i64) -> anyhow::Result<Option<WindowDraw>> {
+                let row = sqlx::query_as::<_, DrawRow>(&format!(
+                    "SELECT {} FROM window_draws WHERE id = $1",
+                    DRAW_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                row.map(draw_from_row).transpose()
+            }
+
+            async fn pending_draw(
+                &self,
+                leaderboard_id: i64,
+            ) -> anyhow::Result<Option<WindowDraw>> {
+                let row = sqlx::query_as::<_, DrawRow>(&format!(
+                    "SELECT {} FROM window_draws WHERE leaderboard_id = $1 AND tx_hash IS NULL",
+                    DRAW_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                row.map(draw_from_row).transpose()
+            }
+
+            async fn complete_draw(
+                &self,
+                id: i64,
+                block_hash: &str,
+                addresses: &[String],
+                tx_hash: &str,
+            ) -> anyhow::Result<()> {
+                let completed = sqlx::query(
+                    "UPDATE window_draws SET block_hash = $1, addresses = $2, tx_hash = $3 \
+                     WHERE id = $4 AND tx_hash IS NULL",
+                )
+                .bind(block_hash)
+                .bind(serde_json::to_string(addresses)?)
+                .bind(tx_hash)
+                .bind(id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+                if completed == 0 {
+                    return Err(anyhow!("Draw {} is not pending", id));
+                }
+
+                Ok(())
+            }
+
+            async fn create_round(
+                &self,
+                leaderboard_id: i64,
+                epoch_id: Option<i64>,
+                scope: RankingScope,
+                addresses: &[String],
+            ) -> anyhow::Result<WindowRound> {
+                let mut tx = self.pool.begin().await?;
+
+                // The hashes need the round number, they are filled in once it is known
+                let round_id = sqlx::query_scalar::<_, i64>(
+                    "INSERT INTO window_rounds \
+                     (leaderboard_id, epoch_id, scores_hash, round_hash, record_count, hashed_at) \
+                     VALUES ($1, $2, '', '', 0, 0) RETURNING id",
+                )
+                .bind(leaderboard_id)
+                .bind(epoch_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                let (source, key) = score_source(scope);
+                for chunk in addresses.chunks(INSERT_CHUNK_SIZE) {
+                    // $1 is the source's key, the addresses follow
+                    let placeholders = (2..chunk.len() + 2)
+                        .map(|i| format!("${}", i))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let select = format!(
+                        "SELECT address, score FROM {} AND address IN ({})",
+                        source, placeholders
+                    );
+                    let scores = chunk
+                        .iter()
+                        .fold(sqlx::query_as::<_, (String, i64)>(&select).bind(key), |q, a| {
+                            q.bind(a)
+                        })
+                        .fetch_all(&mut *tx)
+                        .await?;
+                    if scores.is_empty() {
+                        continue;
+                    }
+
+                    let mut query = sqlx::QueryBuilder::<$database>::new(
+                        "INSERT INTO window_round_scores (round_id, address, score) ",
+                    );
+                    query.push_values(&scores, |mut row, (address, score)| {
+                        row.push_bind(round_id).push_bind(address).push_bind(score);
+                    });
+                    query.push(" ON CONFLICT DO NOTHING");
+                    query.build().execute(&mut *tx).await?;
+                }
+
+                let scores = sqlx::query_as::<_, (String, i64)>(
+                    "SELECT address, score FROM window_round_scores WHERE round_id = $1 \
+                     ORDER BY address",
+                )
+                .bind(round_id)
+                .fetch_all(&mut *tx)
+                .await?;
+                let mut hasher = hash_service::CanonicalHasher::new();
+                for (address, score) in &scores {
+                    hasher.update(address, *score as u32)?;
+                }
+                let scores_hash = hasher.finalize();
+                let round_hash = hash_service::round_hash(&scores_hash, round_id)?;
+
+                let round = sqlx::query_as::<_, WindowRound>(&format!(
+                    "UPDATE window_rounds \
+                     SET scores_hash = $1, round_hash = $2, record_count = $3, hashed_at = $4 \
+                     WHERE id = $5 RETURNING {}",
+                    ROUND_COLUMNS
+                ))
+                .bind(&scores_hash)
+                .bind(&round_hash)
+                .bind(scores.len() as i64)
+                .bind(chrono::Utc::now().timestamp())
+                .bind(round_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                tx.commit().await?;
+                Ok(round)
+            }
+
+            async fn window_round(&self, id: i64) -> anyhow::Result<Option<WindowRound>> {
+                let round = sqlx::query_as::<_, WindowRound>(&format!(
+                    "SELECT {} FROM window_rounds WHERE id = $1",
+                    ROUND_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(round)
+            }
+
+            async fn round_scores(&self, round_id: i64) -> anyhow::Result<Vec<AddressScore>> {
+                let rows = sqlx::query_as::<_, (String, i64)>(
+                    "SELECT address, score FROM window_round_scores WHERE round_id = $1 \
+                     ORDER BY address",
+                )
+                .bind(round_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(address, score)| AddressScore {
+                        id: None,
+                        address,
+                        score: score as u32,
+                        created_at: None,
+                    })
+                    .collect())
+            }
+
+            async fn set_round_anchor(&self, round_id: i64, tx_hash: &str) -> anyhow::Result<()> {
+                sqlx::query("UPDATE window_rounds SET anchor_tx = $1 WHERE id = $2")
+                    .bind(tx_hash)
+                    .bind(round_id)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn track_transaction(
+                &self,
+                kind: &str,
+                tx_hash: &str,
+            ) -> anyhow::Result<TrackedTransaction> {
+                let tracked = sqlx::query_as::<_, TrackedTransaction>(&format!(
+                    "INSERT INTO transactions (kind, tx_hash, status) VALUES ($1, $2, $3) \
+                     RETURNING {}",
+                    TX_COLUMNS
+                ))
+                .bind(kind)
+                .bind(tx_hash)
+                .bind(STATUS_PENDING)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(tracked)
+            }
+
+            async fn transaction(&self, id: i64) -> anyhow::Result<Option<TrackedTransaction>> {
+                let tracked = sqlx::query_as::<_, TrackedTransaction>(&format!(
+                    "SELECT {} FROM transactions WHERE id = $1",
+                    TX_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(tracked)
+            }
+
+            async fn open_transactions(
+                &self,
+                confirmations: u64,
+            ) -> anyhow::Result<Vec<TrackedTransaction>> {
+                let open = sqlx::query_as::<_, TrackedTransaction>(&format!(
+                    "SELECT {} FROM transactions \
+                     WHERE status IN ($1, $2) OR (status = $3 AND confirmations < $4) \
+                     ORDER BY id",
+                    TX_COLUMNS
+                ))
+                .bind(STATUS_PENDING)
+                .bind(STATUS_MINED)
+                .bind(STATUS_REVERTED)
+                .bind(confirmations as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(open)
+            }
+
+            async fn update_transaction(
+                &self,
+                id: i64,
+                receipt: &TransactionReceipt<'_>,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    r#"
+                    UPDATE transactions
+                    SET status = $1, block_number = $2, block_hash = $3, confirmations = $4,
+                        gas_used = $5, revert_reason = $6, reorg_count = $7,
+                        updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $8
+                    "#,
+                )
+                .bind(receipt.status)
+                .bind(receipt.block_number as i64)
+                .bind(receipt.block_hash)
+                .bind(receipt.confirmations as i64)
+                .bind(&receipt.gas_used)
+                .bind(&receipt.revert_reason)
+                .bind(receipt.reorg_count)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn set_transaction_status(&self, id: i64, status: &str) -> anyhow::Result<()> {
+                sqlx::query(
+                    "UPDATE transactions SET status = $1, updated_at = CURRENT_TIMESTAMP \
+                     WHERE id = $2",
+                )
+                .bind(status)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn reset_transaction(&self, id: i64) -> anyhow::Result<()> {
+                sqlx::query(
+                    r#"
+                    UPDATE transactions
+                    SET status = $1, block_number = NULL, block_hash = NULL, confirmations = 0,
+                        gas_used = NULL, revert_reason = NULL, reorg_count = reorg_count + 1,
+                        updated_at = CURRENT_TIMESTAMP
+                    WHERE id = $2
+                    "#,
+                )
+                .bind(STATUS_PENDING)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn indexer_cursor(&self) -> anyhow::Result<Option<u64>> {
+                let cursor = sqlx::query_scalar::<_, i64>(
+                    "SELECT last_block FROM indexer_state WHERE id = 1",
+                )
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(cursor.map(|block| block as u64))
+            }
+
+            async fn indexed_blocks(&self, block: u64) -> anyhow::Result<Vec<(u64, String)>> {
+                let blocks = sqlx::query_as::<_, (i64, String)>(
+                    "SELECT block_number, block_hash FROM indexed_blocks \
+                     WHERE block_number <= $1 ORDER BY block_number DESC",
+                )
+                .bind(block as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(blocks
+                    .into_iter()
+                    .map(|(number, hash)| (number as u64, hash))
+                    .collect())
+            }
+
+            async fn begin_chain_batch(&self) -> anyhow::Result<Box<dyn ChainBatch>> {
+                Ok(Box::new(SqlChainBatch {
+                    tx: self.pool.begin().await?,
+                }))
+            }
+
+            async fn rollback_chain(&self, ancestor: u64) -> anyhow::Result<()> {
+                let mut tx = self.pool.begin().await?;
+
+                for statement in [
+                    "DELETE FROM payouts WHERE block_number > $1",
+                    "UPDATE bets SET outcome = 'pending', payout = NULL \
+                     WHERE window_id IN (SELECT id FROM windows WHERE settled_block > $1)",
+                    "UPDATE windows SET settled_block = NULL, settle_tx = NULL \
+                     WHERE settled_block > $1",
+                    "UPDATE market_bets SET outcome = 'pending', payout = NULL \
+                     WHERE (window_id, market_index) IN \
+                     (SELECT window_id, market_index FROM markets WHERE settled_block > $1)",
+                    "UPDATE markets \
+                     SET winning_outcome = NULL, settled_block = NULL, settle_tx = NULL \
+                     WHERE settled_block > $1",
+                    "DELETE FROM market_bets WHERE block_number > $1",
+                    "DELETE FROM markets WHERE block_number > $1",
+                    "DELETE FROM bets WHERE block_number > $1",
+                    "DELETE FROM windows WHERE start_block > $1",
+                    "UPDATE windows SET closed_at = NULL, close_block = NULL, close_tx = NULL \
+                     WHERE close_block > $1",
+                    "DELETE FROM indexed_blocks WHERE block_number > $1",
+                ] {
+                    sqlx::query(statement)
+                        .bind(ancestor as i64)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                store_cursor(&mut tx, ancestor).await?;
+
+                tx.commit().await?;
+                Ok(())
+            }
+
+            async fn indexer_status(&self) -> anyhow::Result<IndexerStatusResponse> {
+                let (window_count, bet_count, payout_count) =
+                    sqlx::query_as::<_, (i64, i64, i64)>(
+                        "SELECT (SELECT COUNT(*) FROM windows), (SELECT COUNT(*) FROM bets), \
+                         (SELECT COUNT(*) FROM payouts)",
+                    )
+                    .fetch_one(&self.pool)
+                    .await?;
+
+                Ok(IndexerStatusResponse {
+                    last_block: self.indexer_cursor().await?.map(|block| block as i64),
+                    window_count,
+                    bet_count,
+                    payout_count,
+                })
+            }
+
+            async fn latest_window(
+                &self,
+                leaderboard_id: i64,
+            ) -> anyhow::Result<Option<IndexedWindow>> {
+                let window = sqlx::query_as::<_, IndexedWindow>(&format!(
+                    "SELECT {} FROM windows WHERE leaderboard_id = $1 ORDER BY id DESC LIMIT 1",
+                    WINDOW_COLUMNS
+                ))
+                .bind(leaderboard_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(window)
+            }
+
+            async fn indexed_window(
+                &self,
+                window_id: i64,
+            ) -> anyhow::Result<Option<IndexedWindow>> {
+                let mut conn = self.pool.acquire().await?;
+                fetch_window(&mut conn, window_id).await
+            }
+
+            async fn round_window(&self, round_id: i64) -> anyhow::Result<Option<IndexedWindow>> {
+                let window = sqlx::query_as::<_, IndexedWindow>(&format!(
+                    "SELECT {} FROM windows WHERE round_id = $1",
+                    WINDOW_COLUMNS
+                ))
+                .bind(round_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(window)
+            }
+
+            async fn bet_count(&self, window_id: i64) -> anyhow::Result<i64> {
+                let count =
+                    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM bets WHERE window_id = $1")
+                        .bind(window_id)
+                        .fetch_one(&self.pool)
+                        .await?;
+
+                Ok(count)
+            }
+
+            async fn indexed_bet(
+                &self,
+                window_id: i64,
+                bet_index: i64,
+            ) -> anyhow::Result<Option<IndexedBet>> {
+                let bet = sqlx::query_as::<_, IndexedBet>(&format!(
+                    "SELECT {} FROM {} WHERE window_id = $1 AND bet_index = $2 \
+                     AND market_index IS NULL",
+                    BET_COLUMNS, ALL_BETS
+                ))
+                .bind(window_id)
+                .bind(bet_index)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(bet)
+            }
+
+            async fn pool_bets(&self, window_id: i64) -> anyhow::Result<Vec<PoolBet>> {
+                let mut conn = self.pool.acquire().await?;
+                fetch_pool_bets(&mut conn, window_id).await
+            }
+
+            async fn window_markets(&self, window_id: i64) -> anyhow::Result<Vec<IndexedMarket>> {
+                let markets = sqlx::query_as::<_, IndexedMarket>(&format!(
+                    "SELECT {} FROM markets WHERE window_id = $1 ORDER BY market_index",
+                    MARKET_COLUMNS
+                ))
+                .bind(window_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(markets)
+            }
+
+            async fn market_stakes(
+                &self,
+                window_id: i64,
+                market_index: i64,
+            ) -> anyhow::Result<Vec<MarketStake>> {
+                let mut conn = self.pool.acquire().await?;
+                fetch_market_stakes(&mut conn, window_id, market_index).await
+            }
+
+            async fn find_bets(
+                &self,
+                scope: BetScope<'_>,
+                filters: &BetFilterParams,
+                page: u32,
+                per_page: u32,
+            ) -> anyhow::Result<(i64, Vec<IndexedBet>)> {
+                let mut count = sqlx::QueryBuilder::<$database>::new(format!(
+                    "SELECT COUNT(*) FROM {}",
+                    ALL_BETS
+                ));
+                push_bet_filters(&mut count, &scope, filters);
+                let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+                let mut select = sqlx::QueryBuilder::<$database>::new(format!(
+                    "SELECT {} FROM {}",
+                    BET_COLUMNS, ALL_BETS
+                ));
+                push_bet_filters(&mut select, &scope, filters);
+                select
+                    .push(" ORDER BY block_number DESC, log_index DESC LIMIT ")
+                    .push_bind(per_page as i64)
+                    .push(" OFFSET ")
+                    .push_bind(((page.max(1) - 1) * per_page) as i64);
+                let bets = select
+                    .build_query_as::<IndexedBet>()
+                    .fetch_all(&self.pool)
+                    .await?;
+
+                Ok((total, bets))
+            }
+
+            async fn bet_outcomes(&self, bettor: &str) -> anyhow::Result<Vec<BetOutcome>> {
+                let rows = sqlx::query_as::<_, BetOutcome>(&format!(
+                    "SELECT window_id, outcome, amount, payout FROM {} WHERE bettor = $1",
+                    ALL_BETS
+                ))
+                .bind(bettor)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(rows)
+            }
+        }
+
+        /// Indexer batch in a transaction of `$repository`
+        pub struct SqlChainBatch {
+            tx: sqlx::Transaction<'static, $database>,
+        }
+
+        #[async_trait]
+        impl ChainBatch for SqlChainBatch {
+            async fn set_cursor(&mut self, block: u64) -> anyhow::Result<()> {
+                store_cursor(&mut self.tx, block).await
+            }
+
+            async fn record_block(
+                &mut self,
+                block_number: u64,
+                block_hash: &str,
+                timestamp: u64,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    "INSERT INTO indexed_blocks (block_number, block_hash, timestamp) \
+                     VALUES ($1, $2, $3) ON CONFLICT (block_number) DO UPDATE \
+                     SET block_hash = excluded.block_hash, timestamp = excluded.timestamp",
+                )
+                .bind(block_number as i64)
+                .bind(block_hash)
+                .bind(timestamp as i64)
+                .execute(&mut *self.tx)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn insert_window(
+                &mut self,
+                window: &NewWindow<'_>,
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<i64> {
+                let window_id = sqlx::query_scalar::<_, i64>(
+                    r#"
+                    INSERT INTO windows
+                        (id, operator, valid_addresses, started_at, start_block, start_tx,
+                         leaderboard_id, contract, epoch_id, selection, round_id)
+                    VALUES ((SELECT COALESCE(MAX(id), 0) + 1 FROM windows),
+                            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    RETURNING id
+                    "#,
+                )
+                .bind(&window.operator)
+                .bind(sqlx::types::Json(window.addresses))
+                .bind(window.started_at)
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(window.request.leaderboard_id)
+                .bind(window.contract)
+                .bind(window.request.epoch_id)
+                .bind(window.request.selection.as_ref().map(sqlx::types::Json))
+                .bind(window.request.round_id)
+                .fetch_one(&mut *self.tx)
+                .await?;
+
+                Ok(window_id)
+            }
+
+            async fn close_window(
+                &mut self,
+                contract: &str,
+                closed_at: i64,
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<Option<i64>> {
+                let window_id = sqlx::query_scalar::<_, i64>(
+                    "UPDATE windows SET closed_at = $1, close_block = $2, close_tx = $3 \
+                     WHERE id = (SELECT MAX(id) FROM windows WHERE contract = $4) \
+                     AND close_block IS NULL RETURNING id",
+                )
+                .bind(closed_at)
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(contract)
+                .fetch_optional(&mut *self.tx)
+                .await?;
+
+                Ok(window_id)
+            }
+
+            async fn contract_window(&mut self, contract: &str) -> anyhow::Result<Option<i64>> {
+                let window_id = sqlx::query_scalar::<_, Option<i64>>(
+                    "SELECT MAX(id) FROM windows WHERE contract = $1",
+                )
+                .bind(contract)
+                .fetch_one(&mut *self.tx)
+                .await?;
+
+                Ok(window_id)
+            }
+
+            async fn window(&mut self, window_id: i64) -> anyhow::Result<IndexedWindow> {
+                fetch_window(&mut self.tx, window_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("Window {} is not indexed", window_id))
+            }
+
+            async fn insert_bet(
+                &mut self,
+                window_id: i64,
+                bettor: &str,
+                selected_address: &str,
+                position: bool,
+                amount: U256,
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<Option<i64>> {
+                let bet_index = sqlx::query_scalar::<_, i64>(
+                    r#"
+                    INSERT INTO bets
+                        (window_id, bet_index, bettor, selected_address, position, amount,
+                         block_number, tx_hash, log_index)
+                    VALUES ($1, (SELECT COUNT(*) FROM bets WHERE window_id = $1),
+                            $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (tx_hash, log_index) DO NOTHING
+                    RETURNING bet_index
+                    "#,
+                )
+                .bind(window_id)
+                .bind(bettor)
+                .bind(selected_address)
+                .bind(position)
+                .bind(amount.to_string())
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(log.log_index)
+                .fetch_optional(&mut *self.tx)
+                .await?;
+
+                Ok(bet_index)
+            }
+
+            async fn pool_bets(&mut self, window_id: i64) -> anyhow::Result<Vec<PoolBet>> {
+                fetch_pool_bets(&mut self.tx, window_id).await
+            }
+
+            async fn insert_payout(
+                &mut self,
+                contract: &str,
+                bettor: &str,
+                amount: U256,
+                is_winner: bool,
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<()> {
+                // Markets are settled before their payouts are emitted
+                sqlx::query(
+                    r#"
+                    INSERT INTO payouts
+                        (window_id, bettor, amount, is_winner, block_number, tx_hash, log_index,
+                         market)
+                    VALUES ((SELECT MAX(id) FROM windows WHERE contract = $1), $2, $3, $4, $5,
+                            $6, $7, EXISTS (SELECT 1 FROM markets WHERE settle_tx = $6))
+                    ON CONFLICT (tx_hash, log_index) DO NOTHING
+                    "#,
+                )
+                .bind(contract)
+                .bind(bettor)
+                .bind(amount.to_string())
+                .bind(is_winner)
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(log.log_index)
+                .execute(&mut *self.tx)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn paid_windows(&mut self, from: u64, to: u64) -> anyhow::Result<Vec<i64>> {
+                let windows = sqlx::query_scalar::<_, i64>(
+                    "SELECT DISTINCT window_id FROM payouts \
+                     WHERE block_number BETWEEN $1 AND $2 AND window_id IS NOT NULL \
+                     AND NOT market",
+                )
+                .bind(from as i64)
+                .bind(to as i64)
+                .fetch_all(&mut *self.tx)
+                .await?;
+
+                Ok(windows)
+            }
+
+            async fn window_payouts(
+                &mut self,
+                window_id: i64,
+            ) -> anyhow::Result<Vec<(String, String, i64, String)>> {
+                let payouts = sqlx::query_as::<_, (String, String, i64, String)>(
+                    "SELECT bettor, amount, block_number, tx_hash FROM payouts \
+                     WHERE window_id = $1 AND NOT market ORDER BY block_number, log_index",
+                )
+                .bind(window_id)
+                .fetch_all(&mut *self.tx)
+                .await?;
+
+                Ok(payouts)
+            }
+
+            async fn set_bet_outcome(
+                &mut self,
+                window_id: i64,
+                bet_index: i64,
+                outcome: &str,
+                payout: Option<String>,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    "UPDATE bets SET outcome = $1, payout = $2 \
+                     WHERE window_id = $3 AND bet_index = $4",
+                )
+                .bind(outcome)
+                .bind(payout)
+                .bind(window_id)
+                .bind(bet_index)
+                .execute(&mut *self.tx)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn set_window_settled(
+                &mut self,
+                window_id: i64,
+                block_number: i64,
+                tx_hash: &str,
+            ) -> anyhow::Result<()> {
+                sqlx::query("UPDATE windows SET settled_block = $1, settle_tx = $2 WHERE id = $3")
+                    .bind(block_number)
+                    .bind(tx_hash)
+                    .bind(window_id)
+                    .execute(&mut *self.tx)
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn market_index(
+                &mut self,
+                window_id: i64,
+                kind: u8,
+                address: &str,
+                param: U256,
+            ) -> anyhow::Result<Option<(i64, i64)>> {
+                let market = sqlx::query_as::<_, (i64, i64)>(
+                    "SELECT market_index, outcomes FROM markets \
+                     WHERE window_id = $1 AND kind = $2 AND address = $3 AND param = $4",
+                )
+                .bind(window_id)
+                .bind(kind as i64)
+                .bind(address)
+                .bind(param.to_string())
+                .fetch_optional(&mut *self.tx)
+                .await?;
+
+                Ok(market)
+            }
+
+            async fn insert_market(
+                &mut self,
+                window_id: i64,
+                kind: u8,
+                param: U256,
+                address: &str,
+                market: &BetMarket,
+                block_number: i64,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO markets
+                        (window_id, market_index, kind, address, param, market, outcomes,
+                         block_number)
+                    VALUES ($1, (SELECT COUNT(*) FROM markets WHERE window_id = $1),
+                            $2, $3, $4, $5, $6, $7)
+                    "#,
+                )
+                .bind(window_id)
+                .bind(kind as i64)
+                .bind(address)
+                .bind(param.to_string())
+                .bind(sqlx::types::Json(market))
+                .bind(market_service::outcomes(market) as i64)
+                .bind(block_number)
+                .execute(&mut *self.tx)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn insert_market_bet(
+                &mut self,
+                window_id: i64,
+                market_index: i64,
+                bettor: &str,
+                outcome: u8,
+                amount: U256,
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO market_bets
+                        (window_id, market_index, bet_index, bettor, position, amount,
+                         block_number, tx_hash, log_index)
+                    VALUES ($1, $2, (SELECT COUNT(*) FROM market_bets WHERE window_id = $1),
+                            $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (tx_hash, log_index) DO NOTHING
+                    "#,
+                )
+                .bind(window_id)
+                .bind(market_index)
+                .bind(bettor)
+                .bind(outcome as i64)
+                .bind(amount.to_string())
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(log.log_index)
+                .execute(&mut *self.tx)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn market_stakes(
+                &mut self,
+                window_id: i64,
+                market_index: i64,
+            ) -> anyhow::Result<Vec<MarketStake>> {
+                fetch_market_stakes(&mut self.tx, window_id, market_index).await
+            }
+
+            async fn settle_market(
+                &mut self,
+                window_id: i64,
+                market_index: i64,
+                outcome: u8,
+                payouts: &[Option<U256>],
+                log: &EventLog<'_>,
+            ) -> anyhow::Result<()> {
+                sqlx::query(
+                    "UPDATE markets SET winning_outcome = $1, settled_block = $2, settle_tx = $3 \
+                     WHERE window_id = $4 AND market_index = $5",
+                )
+                .bind(outcome as i64)
+                .bind(log.block_number)
+                .bind(log.tx_hash)
+                .bind(window_id)
+                .bind(market_index)
+                .execute(&mut *self.tx)
+                .await?;
+
+                let bet_indices = sqlx::query_scalar::<_, i64>(
+                    "SELECT bet_index FROM market_bets \
+                     WHERE window_id = $1 AND market_index = $2 ORDER BY bet_index",
+                )
+                .bind(window_id)
+                .bind(market_index)
+                .fetch_all(&mut *self.tx)
+                .await?;
+                for (bet_index, payout) in bet_indices.into_iter().zip(payouts) {
+                    let outcome = if payout.is_some() {
+                        OUTCOME_WON
+                    } else {
+                        OUTCOME_LOST
+                    };
+                    sqlx::query(
+                        "UPDATE market_bets SET outcome = $1, payout = $2 \
+                         WHERE window_id = $3 AND bet_index = $4",
+                    )
+                    .bind(outcome)
+                    .bind(payout.map(|payout| payout.to_string()))
+                    .bind(window_id)
+                    .bind(bet_index)
+                    .execute(&mut *self.tx)
+                    .await?;
+                }
+
+                Ok(())
+            }
+
+            async fn commit(self: Box<Self>) -> anyhow::Result<()> {
+                self.tx.commit().await?;
+                Ok(())
+            }
+        }
+    };
+}
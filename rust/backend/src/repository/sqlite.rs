@@ -0,0 +1,16 @@
+use crate::db::MIGRATOR;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
+
+/// Repository on a SQLite database opened by `db::init_db`
+#[derive(Clone)]
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+impl_repository!(SqliteRepository, Sqlite, SqliteConnection, MIGRATOR);
@@ -0,0 +1,1074 @@
+//! Runs against SQLite, and against Postgres when `TEST_POSTGRES_URL` names a server the
+//! tests may create databases on (see `docker-compose.test.yml`).
+
+use super::*;
+use crate::models::RankedScore;
+use crate::models::WindowSelectionParams;
+use crate::services::export_service::{self, ExportFormat, EXPORT_PAGE_SIZE};
+use crate::services::import_service::{ImportFormat, ScoreImporter, IMPORT_BATCH_SIZE};
+use crate::services::ranking_service;
+use crate::services::score_service::{
+    self, canonical_message, ScoreConfig, SUBMISSION_DUPLICATE, SUBMISSION_INSERTED,
+    SUBMISSION_REJECTED, SUBMISSION_REPLAYED, SUBMISSION_STALE, SUBMISSION_UPDATED,
+};
+use crate::services::{
+    address_service, draw_service, hash_service, history_service, leaderboard_service,
+    market_service, selection_service,
+};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::H256;
+use ethers_core::rand::thread_rng;
+use futures_util::TryStreamExt;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Connection, PgConnection};
+use std::{env, str::FromStr};
+
+/// A fresh repository per backend, named after the test
+async fn repositories(name: &str) -> Vec<DynRepository> {
+    let path = env::temp_dir().join(format!("{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let pool = db::init_db(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let mut repositories: Vec<DynRepository> = vec![Arc::new(SqliteRepository::new(pool))];
+
+    if let Some(repository) = postgres_repository(name).await {
+        repositories.push(Arc::new(repository));
+    }
+
+    repositories
+}
+
+/// A fresh Postgres repository named after the test, when `TEST_POSTGRES_URL` is set
+async fn postgres_repository(name: &str) -> Option<PostgresRepository> {
+    let url = env::var("TEST_POSTGRES_URL").ok()?;
+    let database = format!("{}_{}", name.replace('-', "_"), std::process::id());
+    let mut admin = PgConnection::connect(&url).await.unwrap();
+    for statement in [
+        format!("DROP DATABASE IF EXISTS {}", database),
+        format!("CREATE DATABASE {}", database),
+    ] {
+        sqlx::query(&statement).execute(&mut admin).await.unwrap();
+    }
+    let options = PgConnectOptions::from_str(&url)
+        .unwrap()
+        .database(&database);
+    Some(PostgresRepository::connect_with(options).await.unwrap())
+}
+
+#[tokio::test]
+async fn keeps_the_chain_state_and_rolls_it_back() {
+    let filters = BetFilterParams {
+        page: None,
+        per_page: None,
+        window_id: None,
+        selected_address: None,
+        position: None,
+        outcome: None,
+    };
+    let addresses = vec!["0xaaa".to_string(), "0xbbb".to_string()];
+
+    for repository in repositories("repository-chain").await {
+        let tx = repository
+            .track_transaction("start_window", "0x01")
+            .await
+            .unwrap();
+        assert_eq!(repository.open_transactions(3).await.unwrap().len(), 1);
+        let receipt = TransactionReceipt {
+            status: "confirmed",
+            block_number: 10,
+            block_hash: "0xb10",
+            confirmations: 3,
+            gas_used: Some("21000".to_string()),
+            revert_reason: None,
+            reorg_count: 0,
+        };
+        repository
+            .update_transaction(tx.id, &receipt)
+            .await
+            .unwrap();
+        assert!(repository.open_transactions(3).await.unwrap().is_empty());
+        assert_eq!(
+            repository
+                .transaction(tx.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .block_number,
+            Some(10)
+        );
+
+        for (block, hash, tx_hash) in [(10, "0xb10", "0x01"), (11, "0xb11", "0x02")] {
+            let log = EventLog {
+                block_number: block as i64,
+                tx_hash,
+                log_index: 0,
+            };
+            let mut batch = repository.begin_chain_batch().await.unwrap();
+            batch
+                .record_block(block, hash, 1_700_000_000)
+                .await
+                .unwrap();
+            if block == 10 {
+                let window = NewWindow {
+                    operator: "0xoperator".to_string(),
+                    addresses: &addresses,
+                    started_at: 1_700_000_000,
+                    contract: "0xcontract",
+                    request: WindowRequest {
+                        leaderboard_id: 1,
+                        epoch_id: None,
+                        selection: None,
+                        round_id: None,
+                    },
+                };
+                assert_eq!(batch.insert_window(&window, &log).await.unwrap(), 1);
+            } else {
+                let window_id = batch.contract_window("0xcontract").await.unwrap().unwrap();
+                let bet = batch
+                    .insert_bet(window_id, "0xbettor", "0xaaa", true, U256::from(5), &log)
+                    .await
+                    .unwrap();
+                assert_eq!(bet, Some(0));
+                // Replayed events are skipped
+                let replayed = batch
+                    .insert_bet(window_id, "0xbettor", "0xaaa", true, U256::from(5), &log)
+                    .await
+                    .unwrap();
+                assert_eq!(replayed, None);
+
+                let quartiles = BetMarket::RankBucket { buckets: 4 };
+                let (kind, param) = market_service::encode(&quartiles).unwrap().unwrap();
+                batch
+                    .insert_market(window_id, kind, param, "0xbbb", &quartiles, 11)
+                    .await
+                    .unwrap();
+                let log = EventLog {
+                    log_index: 1,
+                    ..log
+                };
+                batch
+                    .insert_market_bet(window_id, 0, "0xbettor", 2, U256::from(7), &log)
+                    .await
+                    .unwrap();
+            }
+            batch.set_cursor(block).await.unwrap();
+            batch.commit().await.unwrap();
+        }
+
+        assert_eq!(repository.indexer_cursor().await.unwrap(), Some(11));
+        let window = repository.latest_window(1).await.unwrap().unwrap();
+        assert_eq!(window.valid_addresses.0, addresses);
+        assert_eq!(repository.bet_count(window.id).await.unwrap(), 1);
+        let (total, bets) = repository
+            .find_bets(BetScope::Player("0xbettor"), &filters, 1, 10)
+            .await
+            .unwrap();
+        // Market bets come with the up/down bets, newest first
+        assert_eq!(total, 2);
+        assert_eq!(
+            (bets[0].selected_address.as_str(), bets[0].market_outcome),
+            ("0xbbb", Some(2))
+        );
+        assert_eq!(
+            (bets[1].amount.as_str(), bets[1].position),
+            ("5", Some(true))
+        );
+        assert_eq!(repository.bet_outcomes("0xbettor").await.unwrap().len(), 2);
+
+        // A batch that isn't committed leaves nothing behind
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        batch.set_cursor(12).await.unwrap();
+        drop(batch);
+        assert_eq!(repository.indexer_cursor().await.unwrap(), Some(11));
+
+        repository.rollback_chain(10).await.unwrap();
+        assert_eq!(repository.indexer_cursor().await.unwrap(), Some(10));
+        assert_eq!(
+            repository.indexed_blocks(11).await.unwrap(),
+            [(10, "0xb10".to_string())]
+        );
+        assert_eq!(repository.bet_count(window.id).await.unwrap(), 0);
+        assert!(repository
+            .bet_outcomes("0xbettor")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repository
+            .indexed_window(window.id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
+
+#[tokio::test]
+async fn one_replica_writes_the_chain_state() {
+    let Some(repository) = postgres_repository("repository-chain-writer").await else {
+        return;
+    };
+
+    let lock = repository.try_lock_chain_writer().await.unwrap();
+    assert!(lock.is_some());
+    assert!(repository.try_lock_chain_writer().await.unwrap().is_none());
+    // Postgres releases the lock with the session
+    drop(lock);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(repository.try_lock_chain_writer().await.unwrap().is_some());
+}
+
+async fn signed(
+    wallet: &LocalWallet,
+    address: &str,
+    score: u32,
+    timestamp: i64,
+    nonce: u64,
+) -> ScoreSubmission {
+    signed_for(wallet, "arena", address, score, timestamp, nonce).await
+}
+
+async fn signed_for(
+    wallet: &LocalWallet,
+    game_id: &str,
+    address: &str,
+    score: u32,
+    timestamp: i64,
+    nonce: u64,
+) -> ScoreSubmission {
+    let mut submission = ScoreSubmission {
+        address: address.to_string(),
+        score,
+        game_id: game_id.to_string(),
+        timestamp,
+        nonce,
+        signature: String::new(),
+    };
+    let message = canonical_message(&submission).unwrap();
+    submission.signature = wallet.sign_message(message).await.unwrap().to_string();
+    submission
+}
+
+#[tokio::test]
+async fn migrates_and_manages_leaderboards() {
+    for repository in repositories("repository-leaderboards").await {
+        let repository = repository.as_ref();
+        assert!(repository
+            .migration_status()
+            .await
+            .unwrap()
+            .iter()
+            .all(|migration| migration.applied && !migration.checksum_mismatch));
+
+        let contract = format!("{:?}", Address::repeat_byte(0x42));
+        let arena = leaderboard_service::create_leaderboard(
+            repository,
+            "arena",
+            " Arena ",
+            Some(&contract.to_uppercase().replace("0X", "0x")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(arena.name, "Arena");
+        assert!(
+            leaderboard_service::create_leaderboard(repository, "arena", "Again", None)
+                .await
+                .is_err()
+        );
+        let slugs: Vec<String> = repository
+            .get_leaderboards()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|leaderboard| leaderboard.slug)
+            .collect();
+        assert_eq!(slugs, vec!["default", "arena"]);
+        assert_eq!(
+            leaderboard_service::contract_leaderboards(repository)
+                .await
+                .unwrap(),
+            vec![(Address::repeat_byte(0x42), arena.id)]
+        );
+
+        let uniform = WindowSelection::Uniform { count: 3 };
+        let stratified = WindowSelection::Stratified { count: 6, bands: 3 };
+        repository
+            .record_window_request("0xabc", 1, None, &uniform, None)
+            .await
+            .unwrap();
+        repository
+            .record_window_request("0xabc", arena.id, None, &stratified, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            repository.window_request("0xabc").await.unwrap(),
+            Some(WindowRequest {
+                leaderboard_id: arena.id,
+                epoch_id: None,
+                selection: Some(stratified),
+                round_id: None,
+            })
+        );
+        assert!(repository.window_request("0xdef").await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn closes_epochs_into_hashed_snapshots() {
+    for repository in repositories("repository-epochs").await {
+        let repository = repository.as_ref();
+
+        let seeded = address_service::generate_and_store_addresses(repository, 1, 5)
+            .await
+            .unwrap();
+        let first = repository.close_epoch(1).await.unwrap();
+        assert_eq!((first.number, first.record_count), (1, 5));
+        assert!(first.started_at.is_none());
+        assert_eq!(
+            first.snapshot_hash,
+            hash_service::hash_address_data(seeded).unwrap().hash
+        );
+        assert!(repository.get_all_addresses(1).await.unwrap().is_empty());
+        assert!(repository.close_epoch(1).await.is_err());
+
+        address_service::generate_and_store_addresses(repository, 1, 2)
+            .await
+            .unwrap();
+        let second = repository.close_epoch(1).await.unwrap();
+        assert_eq!(second.number, 2);
+        assert_eq!(second.started_at, Some(first.closed_at));
+
+        let frozen = repository.get_epoch_scores(first.id, 1, 100).await.unwrap();
+        assert_eq!(frozen.len(), 5);
+        assert!(frozen.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(
+            repository
+                .sample_band(
+                    RankingScope::Snapshot {
+                        epoch_id: second.id
+                    },
+                    i64::MIN..i64::MAX,
+                    3
+                )
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            repository.latest_epoch(1).await.unwrap().unwrap().id,
+            second.id
+        );
+    }
+}
+
+#[tokio::test]
+async fn freezes_window_scores_in_rounds() {
+    for repository in repositories("repository-rounds").await {
+        let repository = repository.as_ref();
+
+        let seeded = address_service::generate_and_store_addresses(repository, 1, 5)
+            .await
+            .unwrap();
+        let players: Vec<AddressScore> = seeded[1..4].to_vec();
+        let mut addresses: Vec<String> = players.iter().map(|p| p.address.clone()).collect();
+        addresses.push(format!("{:?}", Address::repeat_byte(0x99)));
+        let live = RankingScope::Live { leaderboard_id: 1 };
+
+        let round = repository
+            .create_round(1, None, live, &addresses)
+            .await
+            .unwrap();
+        assert_eq!(round.record_count, 3);
+        assert_eq!(
+            round.scores_hash,
+            hash_service::hash_address_data(players.clone())
+                .unwrap()
+                .hash
+        );
+        assert_eq!(
+            round.round_hash,
+            hash_service::round_hash(&round.scores_hash, round.id).unwrap()
+        );
+        assert!(round.anchor_tx.is_none());
+
+        // Later rounds of the same scores commit to a different hash
+        let next = repository
+            .create_round(1, None, live, &addresses)
+            .await
+            .unwrap();
+        assert_eq!(next.scores_hash, round.scores_hash);
+        assert_ne!(next.round_hash, round.round_hash);
+
+        // The round keeps its scores once the live leaderboard moves on
+        repository.close_epoch(1).await.unwrap();
+        repository
+            .set_round_anchor(round.id, "0xabc")
+            .await
+            .unwrap();
+        let frozen = repository.round_scores(round.id).await.unwrap();
+        assert_eq!(
+            hash_service::hash_address_data(frozen).unwrap().hash,
+            round.scores_hash
+        );
+        assert_eq!(
+            repository
+                .window_round(round.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .anchor_tx,
+            Some("0xabc".to_string())
+        );
+        assert!(repository
+            .window_round(next.id + 1)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
+
+#[tokio::test]
+async fn imports_scores_in_batches_and_skips_duplicates() {
+    for repository in repositories("repository-import").await {
+        let repository = repository.as_ref();
+
+        // More rows than one multi-row insert takes
+        let seeded = address_service::generate_and_store_addresses(repository, 1, 1200)
+            .await
+            .unwrap();
+        assert_eq!(seeded.len(), 1200);
+        assert!(seeded.iter().all(|address| address.id.is_some()));
+
+        let existing = &seeded[0].address;
+        let new_rows = (0..IMPORT_BATCH_SIZE + 5)
+            .map(|i| format!("{:?},{}\n", Address::from_low_u64_be(i as u64 + 1), i))
+            .collect::<String>();
+        let body = format!(
+            "address,score\r\n{},10\nnot-an-address,5\n{:?},1\n{}{:?},7",
+            existing,
+            Address::from_low_u64_be(1),
+            new_rows,
+            Address::from_low_u64_be(1),
+        );
+
+        let mut importer = ScoreImporter::new(repository, 1, ImportFormat::Csv, u32::MAX);
+        // Chunks that split lines and CRLF endings
+        for chunk in body.as_bytes().chunks(37) {
+            importer.push(chunk).await.unwrap();
+        }
+        let report = importer.finish().await.unwrap();
+        assert_eq!(
+            (report.inserted, report.duplicates, report.rejected),
+            (IMPORT_BATCH_SIZE as u64 + 5, 3, 1)
+        );
+        assert_eq!(report.errors[0].line, 3);
+        assert_eq!(
+            repository.get_all_addresses(1).await.unwrap().len(),
+            1200 + IMPORT_BATCH_SIZE + 5
+        );
+
+        let mut importer = ScoreImporter::new(repository, 1, ImportFormat::Ndjson, 100);
+        importer
+            .push(b"{\"address\":\"0xFF000000000000000000000000000000000000AA\",\"score\":50}\n")
+            .await
+            .unwrap();
+        importer
+            .push(b"{\"address\":\"0xaa\"}\n{\"addr")
+            .await
+            .unwrap();
+        importer
+            .push(b"ess\":\"0x00000000000000000000000000000000000000bb\",\"score\":101}")
+            .await
+            .unwrap();
+        let report = importer.finish().await.unwrap();
+        assert_eq!((report.inserted, report.rejected), (1, 2));
+        assert_eq!(report.errors[1].reason, "Score exceeds 100");
+    }
+}
+
+#[tokio::test]
+async fn ranks_ties_and_computes_quartiles() {
+    for repository in repositories("repository-ranking").await {
+        let repository = repository.as_ref();
+        let player = |n: u64| format!("{:?}", Address::from_low_u64_be(n));
+        let scores: Vec<AddressScore> = [500, 300, 300, 300, 100, 50]
+            .into_iter()
+            .enumerate()
+            .map(|(i, score)| AddressScore {
+                id: None,
+                address: player(i as u64 + 1),
+                score,
+                created_at: None,
+            })
+            .collect();
+        repository.import_addresses(1, &scores).await.unwrap();
+        let live = RankingScope::Live { leaderboard_id: 1 };
+        let ranks = |scores: Vec<RankedScore>| -> Vec<(u64, u32)> {
+            scores.into_iter().map(|s| (s.rank, s.score)).collect()
+        };
+
+        let top = ranking_service::top(repository, live, 3).await.unwrap();
+        assert_eq!(top[1].address, player(2));
+        assert_eq!(ranks(top), vec![(1, 500), (2, 300), (2, 300)]);
+        // A page starting inside the tie
+        assert_eq!(
+            ranks(
+                ranking_service::around(repository, live, 4, 1)
+                    .await
+                    .unwrap()
+            ),
+            vec![(2, 300), (2, 300), (5, 100)]
+        );
+        assert!(ranking_service::around(repository, live, 9, 1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let fifth = ranking_service::player_rank(repository, live, &player(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((fifth.rank, fifth.percentile, fifth.total), (5, 25.0, 6));
+        let tied = ranking_service::player_rank(repository, live, &player(4))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((tied.rank, tied.tied), (2, 3));
+        assert!((tied.percentile - 58.33).abs() < 0.01);
+        assert!(ranking_service::player_rank(repository, live, &player(9))
+            .await
+            .unwrap()
+            .is_none());
+
+        let quartiles = ranking_service::quartiles(repository, live).await.unwrap();
+        assert_eq!(
+            (
+                quartiles.min,
+                quartiles.q1,
+                quartiles.median,
+                quartiles.q3,
+                quartiles.max
+            ),
+            (Some(50), Some(200), Some(300), Some(300), Some(500))
+        );
+
+        let epoch = repository.close_epoch(1).await.unwrap();
+        let snapshot = RankingScope::Snapshot { epoch_id: epoch.id };
+        assert_eq!(
+            ranks(ranking_service::top(repository, snapshot, 2).await.unwrap()),
+            vec![(1, 500), (2, 300)]
+        );
+        let empty = ranking_service::quartiles(repository, live).await.unwrap();
+        assert_eq!((empty.count, empty.median), (0, None));
+    }
+}
+
+#[tokio::test]
+async fn selects_window_players_by_strategy() {
+    for repository in repositories("repository-selection").await {
+        let repository = repository.as_ref();
+        let player = |n: u64| format!("{:?}", Address::from_low_u64_be(n));
+        let import = |leaderboard_id: i64, scores: &[u32]| {
+            let scores: Vec<AddressScore> = scores
+                .iter()
+                .enumerate()
+                .map(|(i, score)| AddressScore {
+                    id: None,
+                    address: player(i as u64 + 1),
+                    score: *score,
+                    created_at: None,
+                })
+                .collect();
+            async move { repository.import_addresses(leaderboard_id, &scores).await }
+        };
+        import(1, &[10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120])
+            .await
+            .unwrap();
+        let live = RankingScope::Live { leaderboard_id: 1 };
+        let scores_of = |players: Vec<String>| async move {
+            let mut scores = Vec::new();
+            for player in players {
+                scores.push(
+                    repository
+                        .player_score(live, &player)
+                        .await
+                        .unwrap()
+                        .unwrap(),
+                );
+            }
+            scores.sort();
+            scores
+        };
+
+        // The median is 65, the extra player comes from the upper half
+        let balanced =
+            selection_service::select(repository, live, &WindowSelection::Balanced { count: 5 })
+                .await
+                .unwrap();
+        let scores = scores_of(balanced).await;
+        assert_eq!(scores.iter().filter(|score| **score >= 65).count(), 3);
+        assert_eq!(scores.len(), 5);
+
+        // Bands split at 45 and 85
+        let stratified = selection_service::select(
+            repository,
+            live,
+            &WindowSelection::Stratified { count: 6, bands: 3 },
+        )
+        .await
+        .unwrap();
+        let scores = scores_of(stratified).await;
+        assert!(scores[..2].iter().all(|score| *score < 45));
+        assert!(scores[2..4].iter().all(|score| (45..85).contains(score)));
+        assert!(scores[4..].iter().all(|score| *score >= 85));
+
+        let everyone =
+            selection_service::select(repository, live, &WindowSelection::Uniform { count: 20 })
+                .await
+                .unwrap();
+        assert_eq!(everyone.len(), 12);
+
+        // A band short of players leaves its share to the other one
+        let arena = leaderboard_service::create_leaderboard(repository, "arena", "Arena", None)
+            .await
+            .unwrap();
+        import(arena.id, &[100, 100, 100, 1]).await.unwrap();
+        let arena_scope = RankingScope::Live {
+            leaderboard_id: arena.id,
+        };
+        let lopsided = selection_service::select(
+            repository,
+            arena_scope,
+            &WindowSelection::Balanced { count: 4 },
+        )
+        .await
+        .unwrap();
+        assert_eq!(lopsided.len(), 4);
+
+        let params = WindowSelectionParams {
+            epoch: None,
+            count: None,
+            strategy: Some("explicit".to_string()),
+            bands: None,
+            addresses: Some(format!(
+                "{:x}, {:?},{:?}",
+                Address::from_low_u64_be(1),
+                Address::from_low_u64_be(1),
+                Address::from_low_u64_be(99)
+            )),
+            draw: None,
+        };
+        let explicit = selection_service::parse_selection(&params).unwrap();
+        assert_eq!(
+            explicit,
+            WindowSelection::Explicit {
+                addresses: vec![player(1), player(99)]
+            }
+        );
+        let WindowSelection::Explicit { addresses } = &explicit else {
+            unreachable!()
+        };
+        assert_eq!(
+            selection_service::unranked_players(repository, live, addresses)
+                .await
+                .unwrap(),
+            vec![player(99)]
+        );
+
+        let stratified = |bands| WindowSelectionParams {
+            epoch: None,
+            count: Some(3),
+            strategy: Some("stratified".to_string()),
+            bands,
+            addresses: None,
+            draw: None,
+        };
+        assert!(selection_service::parse_selection(&stratified(Some(4))).is_err());
+        assert!(selection_service::parse_selection(&stratified(None)).is_err());
+        assert_eq!(
+            selection_service::parse_selection(&stratified(Some(3))).unwrap(),
+            WindowSelection::Stratified { count: 3, bands: 3 }
+        );
+    }
+}
+
+#[tokio::test]
+async fn draws_windows_from_a_committed_seed() {
+    for repository in repositories("repository-draws").await {
+        let repository = repository.as_ref();
+        let scores: Vec<AddressScore> = (1..=20)
+            .map(|i| AddressScore {
+                id: None,
+                address: format!("{:?}", Address::from_low_u64_be(i)),
+                score: i as u32 * 10,
+                created_at: None,
+            })
+            .collect();
+        repository.import_addresses(1, &scores).await.unwrap();
+        let epoch = repository.close_epoch(1).await.unwrap();
+        let selection = WindowSelection::Stratified { count: 6, bands: 3 };
+        assert!(draw_service::check_draw(&epoch, &selection).is_some());
+        let anchored = Epoch {
+            tx_id: Some(1),
+            ..epoch.clone()
+        };
+        assert!(draw_service::check_draw(&anchored, &selection).is_none());
+
+        let draw = repository
+            .create_draw(1, epoch.id, &epoch.snapshot_hash, &selection, 100)
+            .await
+            .unwrap();
+        assert_eq!(
+            repository.pending_draw(1).await.unwrap(),
+            Some(draw.clone())
+        );
+        // One pending draw per leaderboard
+        assert!(repository
+            .create_draw(1, epoch.id, &epoch.snapshot_hash, &selection, 101)
+            .await
+            .is_err());
+
+        let seed = H256::repeat_byte(7);
+        let drawn = draw_service::draw_players(repository, &draw, seed)
+            .await
+            .unwrap();
+        assert_eq!(drawn.snapshot_hash, epoch.snapshot_hash);
+        let again = draw_service::draw_players(repository, &draw, seed)
+            .await
+            .unwrap();
+        assert_eq!(drawn.addresses, again.addresses);
+        let other = draw_service::draw_players(repository, &draw, H256::repeat_byte(8))
+            .await
+            .unwrap();
+        assert_ne!(drawn.addresses, other.addresses);
+
+        // Two players from each band, listed by key
+        let snapshot_hash = hex::decode(&epoch.snapshot_hash).unwrap();
+        let keys: Vec<[u8; 32]> = drawn
+            .addresses
+            .iter()
+            .map(|address| draw_service::draw_key(seed, &snapshot_hash, address).unwrap())
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        let snapshot = RankingScope::Snapshot { epoch_id: epoch.id };
+        let mut bands = [0; 3];
+        for address in &drawn.addresses {
+            let score = repository
+                .player_score(snapshot, address)
+                .await
+                .unwrap()
+                .unwrap();
+            bands[if score >= 135 {
+                0
+            } else if score >= 75 {
+                1
+            } else {
+                2
+            }] += 1;
+        }
+        assert_eq!(bands, [2, 2, 2]);
+
+        repository
+            .complete_draw(draw.id, &format!("{:#x}", seed), &drawn.addresses, "0xabc")
+            .await
+            .unwrap();
+        assert!(repository
+            .complete_draw(draw.id, &format!("{:#x}", seed), &drawn.addresses, "0xdef")
+            .await
+            .is_err());
+        assert!(repository.pending_draw(1).await.unwrap().is_none());
+        let completed = repository.window_draw(draw.id).await.unwrap().unwrap();
+        assert_eq!(completed.addresses, Some(drawn.addresses));
+        assert_eq!(completed.tx_hash.as_deref(), Some("0xabc"));
+    }
+}
+
+#[tokio::test]
+async fn records_score_history_and_past_values() {
+    for repository in repositories("repository-history").await {
+        let repository = repository.as_ref();
+        let config = ScoreConfig::from_env();
+        let player = "0x00000000000000000000000000000000000000aa";
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let public_key = hex::encode(
+            wallet
+                .signer()
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+        score_service::register_game_server(repository, "arena-eu", &public_key)
+            .await
+            .unwrap();
+        let arena = leaderboard_service::create_leaderboard(repository, "arena", "Arena", None)
+            .await
+            .unwrap();
+        // Events are recorded in milliseconds, step past them
+        let now = || async {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            let now = chrono::Utc::now().timestamp_millis();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            now
+        };
+
+        let before = now().await;
+        for (score, timestamp, nonce) in [(500, 1_700_000_000, 1), (700, 1_700_000_100, 2)] {
+            score_service::submit_scores(
+                repository,
+                &[signed(&wallet, player, score, timestamp, nonce).await],
+                &config,
+            )
+            .await
+            .unwrap();
+        }
+        let submitted = now().await;
+        history_service::correct_score(repository, &config, arena.id, player, 650, "Cheated")
+            .await
+            .unwrap();
+        assert!(
+            history_service::correct_score(repository, &config, arena.id, player, 1, " ")
+                .await
+                .is_err()
+        );
+        let corrected = now().await;
+        repository.close_epoch(arena.id).await.unwrap();
+        let closed = now().await;
+
+        let history = repository
+            .score_history(arena.id, player, 1, 10)
+            .await
+            .unwrap();
+        let changes: Vec<(&str, Option<u32>, Option<u32>)> = history
+            .iter()
+            .map(|e| (e.source.as_str(), e.previous_score, e.score))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("submission", None, Some(500)),
+                ("submission", Some(500), Some(700)),
+                ("correction", Some(700), Some(650)),
+                ("epoch", Some(650), None),
+            ]
+        );
+        assert_eq!(
+            history[0].source_id,
+            Some(format!("{:?}", wallet.address()))
+        );
+        assert_eq!(history[1].submitted_at, Some(1_700_000_100));
+        assert_eq!(history[2].reason.as_deref(), Some("Cheated"));
+        assert_eq!(history[3].source_id.as_deref(), Some("1"));
+
+        let score_at = |at| repository.score_as_of(arena.id, player, at);
+        assert_eq!(score_at(before).await.unwrap(), None);
+        assert_eq!(score_at(submitted).await.unwrap(), Some(700));
+        assert_eq!(score_at(corrected).await.unwrap(), Some(650));
+        assert_eq!(score_at(closed).await.unwrap(), None);
+
+        let imported = AddressScore {
+            id: None,
+            address: player.to_string(),
+            score: 10,
+            created_at: None,
+        };
+        for _ in 0..2 {
+            repository
+                .import_addresses(arena.id, std::slice::from_ref(&imported))
+                .await
+                .unwrap();
+        }
+        let history = repository
+            .score_history(arena.id, player, 2, 4)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].source, "import");
+    }
+}
+
+async fn export(repository: &DynRepository, scope: ExportScope, format: ExportFormat) -> Vec<u8> {
+    export_service::export_scores(repository.clone(), scope, format)
+        .unwrap()
+        .try_fold(Vec::new(), |mut out, chunk| async move {
+            out.extend_from_slice(&chunk);
+            Ok(out)
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn exports_scopes_with_their_canonical_hash() {
+    for repository in repositories("repository-export").await {
+        let count = EXPORT_PAGE_SIZE + 10;
+        address_service::generate_and_store_addresses(repository.as_ref(), 1, count)
+            .await
+            .unwrap();
+        let epoch = repository.close_epoch(1).await.unwrap();
+        let snapshot = ExportScope::Snapshot { epoch_id: epoch.id };
+
+        let ndjson = export(&repository, snapshot.clone(), ExportFormat::Ndjson).await;
+        let lines: Vec<serde_json::Value> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), count as usize + 1);
+        assert!(lines[..count as usize]
+            .windows(2)
+            .all(|w| w[0]["address"].as_str() < w[1]["address"].as_str()));
+        assert_eq!(lines[count as usize]["hash"], epoch.snapshot_hash.as_str());
+        assert_eq!(lines[count as usize]["record_count"], count);
+
+        let parquet = export(&repository, snapshot, ExportFormat::Parquet).await;
+        let reader = SerializedFileReader::new(actix_web::web::Bytes::from(parquet)).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), count as i64);
+        let hash = metadata
+            .key_value_metadata()
+            .unwrap()
+            .iter()
+            .find(|kv| kv.key == "hash")
+            .and_then(|kv| kv.value.clone());
+        assert_eq!(hash, Some(epoch.snapshot_hash.clone()));
+
+        let current = address_service::generate_and_store_addresses(repository.as_ref(), 1, 3)
+            .await
+            .unwrap();
+        let live = |from, to| ExportScope::Live {
+            leaderboard_id: 1,
+            from,
+            to,
+        };
+        let hour = chrono::Duration::hours(1);
+        let now = chrono::Utc::now();
+
+        let csv = export(&repository, live(Some(now - hour), None), ExportFormat::Csv).await;
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "address,score,created_at");
+        assert_eq!(
+            lines[4],
+            format!(
+                "# hash={},record_count=3",
+                hash_service::hash_address_data(current).unwrap().hash
+            )
+        );
+
+        let csv = export(&repository, live(None, Some(now - hour)), ExportFormat::Csv).await;
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            format!(
+                "address,score,created_at\n# hash={},record_count=0\n",
+                hash_service::hash_address_data(Vec::new()).unwrap().hash
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn verifies_signatures_upserts_scores_and_audits_replays() {
+    for repository in repositories("repository-scores").await {
+        let repository = repository.as_ref();
+        let config = ScoreConfig::from_env();
+        let player = "0x00000000000000000000000000000000000000AA";
+
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let public_key = hex::encode(
+            wallet
+                .signer()
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+        let arena = leaderboard_service::create_leaderboard(repository, "arena", "Arena", None)
+            .await
+            .unwrap();
+        let server = score_service::register_game_server(repository, "arena-eu", &public_key)
+            .await
+            .unwrap();
+        assert_eq!(server.address, format!("{:?}", wallet.address()));
+        let stranger = LocalWallet::new(&mut thread_rng());
+
+        let statuses = |results: Vec<ScoreSubmissionResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.status).collect()
+        };
+
+        let mut tampered = signed(&wallet, player, 500, 1_700_000_000, 3).await;
+        tampered.score = 900;
+        let first = score_service::submit_scores(
+            repository,
+            &[
+                signed(&wallet, player, 500, 1_700_000_000, 1).await,
+                signed(&wallet, player, 500, 1_700_000_000, 2).await,
+                signed(&stranger, player, 999, 1_700_000_000, 1).await,
+                tampered,
+                signed_for(&wallet, "unknown", player, 500, 1_700_000_000, 5).await,
+            ],
+            &config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(first[0].address, player.to_lowercase());
+        assert_eq!(first[4].reason.as_deref(), Some("Unknown leaderboard"));
+        assert_eq!(
+            statuses(first),
+            vec![
+                SUBMISSION_INSERTED,
+                SUBMISSION_DUPLICATE,
+                SUBMISSION_REJECTED,
+                SUBMISSION_REJECTED,
+                SUBMISSION_REJECTED
+            ]
+        );
+
+        let second = score_service::submit_scores(
+            repository,
+            &[
+                signed(&wallet, player, 400, 1_699_999_999, 3).await,
+                signed(&wallet, player, 700, 1_700_000_100, 4).await,
+                signed(&wallet, player, 800, 1_700_000_200, 4).await,
+            ],
+            &config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            statuses(second),
+            vec![SUBMISSION_STALE, SUBMISSION_UPDATED, SUBMISSION_REPLAYED]
+        );
+
+        let scores = repository.get_all_addresses(arena.id).await.unwrap();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].score, 700);
+
+        let audit = repository.get_audit_entries(1, 10).await.unwrap();
+        let audited: Vec<&str> = audit.iter().map(|e| e.status.as_str()).collect();
+        assert_eq!(
+            audited,
+            vec![
+                SUBMISSION_REPLAYED,
+                SUBMISSION_REJECTED,
+                SUBMISSION_REJECTED,
+                SUBMISSION_REJECTED
+            ]
+        );
+        assert_eq!(audit[0].server_address, Some(server.address.clone()));
+
+        assert!(repository
+            .set_game_server_active(server.id, false)
+            .await
+            .unwrap());
+        let rejected = score_service::submit_scores(
+            repository,
+            &[signed(&wallet, player, 900, 1_700_000_300, 6).await],
+            &config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(statuses(rejected), vec![SUBMISSION_REJECTED]);
+        assert!(!repository.get_game_servers().await.unwrap()[0].active);
+    }
+}
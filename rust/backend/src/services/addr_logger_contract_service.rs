@@ -1,10 +1,9 @@
+use crate::models::Leaderboard;
+use crate::signer::SignerClient;
 use anyhow::Result;
 use ethers::{
-    middleware::SignerMiddleware,
     prelude::abigen,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
-    types::{Address, U256},
+    types::{Address, H256, U256},
 };
 use std::sync::Arc;
 
@@ -13,47 +12,71 @@ abigen!(
     AddrLogger,
     r#"[
     function init(address operator, address authorized_contract, address treasury, address token) external returns (uint8[] memory)
-    function startBettingWindow(address[] memory addresses) external returns (uint8[] memory)
+    function startBettingWindow(address[] memory addresses, bytes32 round_hash) external returns (uint8[] memory)
     function closeBettingWindow() external returns (uint8[] memory)
     function placeBet(address bettor, address selected_address, bool position, uint256 amount) external returns (uint8[] memory)
+    function placeMarketBet(address bettor, uint8 kind, address selected_address, uint256 param, uint8 outcome, uint256 amount) external returns (uint8[] memory)
     function getWindowActive() external view returns (bool)
+    function getWindowNumber() external view returns (uint256)
     function getBet(uint256 index) external view returns (address, address, bool, uint256)
     function getBetCount() external view returns (uint256)
     function processPayouts(bool[] memory winners) external
+    function processMarketPayouts(uint8[] memory outcomes) external
+    function getMarketCount() external view returns (uint256)
+    function getMarket(uint256 index) external view returns (uint8, address, uint256, uint8)
+    function getMarketPool(uint256 index, uint8 outcome) external view returns (uint256)
     function isValidAddress(address _address) external view returns (bool)
     function getOperator() external view returns (address)
     function getTreasury() external view returns (address)
     function getToken() external view returns (address)
     function getUpAmount(uint256 addr_index) external view returns (uint256)
     function getDownAmount(uint256 addr_index) external view returns (uint256)
-
+    event WindowStarted(address indexed operator, address[] validAddresses, uint256 timestamp)
+    event WindowClosed(address indexed operator, uint256 timestamp)
+    event BetPlaced(address indexed bettor, address indexed selectedAddress, bool position, uint256 amount)
+    event PayoutProcessed(address indexed bettor, uint256 amount, bool isWinner)
+    event MarketBetPlaced(address indexed bettor, uint8 kind, address indexed selectedAddress, uint256 param, uint8 outcome, uint256 amount)
+    event MarketSettled(uint8 kind, address indexed selectedAddress, uint256 param, uint8 outcome)
     ]"#
 );
 
+/// AddressLogger contract of each leaderboard. Leaderboards without their own contract
+/// share the default one and therefore can't run betting windows at the same time.
+#[derive(Clone)]
+pub struct AddrLoggerContracts {
+    client: Arc<SignerClient>,
+    default: AddrLoggerContractService,
+}
+
+impl AddrLoggerContracts {
+    pub fn new(client: Arc<SignerClient>, default_address: &str) -> Result<Self> {
+        let default = AddrLoggerContractService::new(client.clone(), default_address)?;
+        Ok(Self { client, default })
+    }
+
+    pub fn for_leaderboard(&self, leaderboard: &Leaderboard) -> Result<AddrLoggerContractService> {
+        match &leaderboard.contract_address {
+            Some(address) => AddrLoggerContractService::new(self.client.clone(), address),
+            None => Ok(self.default.clone()),
+        }
+    }
+}
+
 #[derive(Clone)]
 
 pub struct AddrLoggerContractService {
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    contract: AddrLogger<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    contract: AddrLogger<SignerClient>,
 }
 
 impl AddrLoggerContractService {
-    pub async fn new(rpc_url: &str, private_key: &str, contract_address: &str) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet = private_key.parse::<LocalWallet>()?;
-        let chain_id = provider.get_chainid().await?.as_u64();
-        let client = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(chain_id),
-        ));
-
+    pub fn new(client: Arc<SignerClient>, contract_address: &str) -> Result<Self> {
         let address = contract_address
             .parse::<Address>()
             .map_err(|e| anyhow::anyhow!("Failed to parse contract address: {}", e))?;
 
-        let contract = AddrLogger::new(address, client.clone());
+        let contract = AddrLogger::new(address, client);
 
-        Ok(Self { client, contract })
+        Ok(Self { contract })
     }
 
     pub async fn init(
@@ -62,16 +85,15 @@ impl AddrLoggerContractService {
         authorized_contract: Address,
         treasury: Address,
         token: Address,
-    ) -> Result<String> {
-        let tx = self
+    ) -> Result<H256> {
+        let tx_hash = self
             .contract
             .init(operator, authorized_contract, treasury, token)
             .send()
             .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+            .tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
         // Ok(tx
         //     .logs
         //     .into_iter()
@@ -80,29 +102,27 @@ impl AddrLoggerContractService {
         //     .unwrap_or_default())
     }
 
-    pub async fn start_betting_window(&self, addresses: Vec<Address>) -> Result<String> {
+    /// Start a window on `addresses`, settled by proofs on the scores hashed into `round_hash`
+    pub async fn start_betting_window(
+        &self,
+        addresses: Vec<Address>,
+        round_hash: [u8; 32],
+    ) -> Result<H256> {
         print!("Addresses: {:?}", addresses);
-        let tx = self
+        let tx_hash = self
             .contract
-            .start_betting_window(addresses)
+            .start_betting_window(addresses, round_hash)
             .send()
             .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+            .tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
     }
 
-    pub async fn close_betting_window(&self) -> Result<String> {
-        let tx = self
-            .contract
-            .close_betting_window()
-            .send()
-            .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+    pub async fn close_betting_window(&self) -> Result<H256> {
+        let tx_hash = self.contract.close_betting_window().send().await?.tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
     }
 
     pub async fn place_bet(
@@ -111,22 +131,45 @@ impl AddrLoggerContractService {
         selected_address: Address,
         position: bool,
         amount: U256,
-    ) -> Result<String> {
-        let tx = self
+    ) -> Result<H256> {
+        let tx_hash = self
             .contract
             .place_bet(bettor, selected_address, position, amount)
             .send()
             .await?
+            .tx_hash();
+
+        Ok(tx_hash)
+    }
+
+    pub async fn place_market_bet(
+        &self,
+        bettor: Address,
+        kind: u8,
+        selected_address: Address,
+        param: U256,
+        outcome: u8,
+        amount: U256,
+    ) -> Result<H256> {
+        let tx_hash = self
+            .contract
+            .place_market_bet(bettor, kind, selected_address, param, outcome, amount)
+            .send()
             .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+            .tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
     }
 
     pub async fn get_window_active(&self) -> Result<bool> {
         Ok(self.contract.get_window_active().call().await?)
     }
 
+    /// Number of the contract's latest window, 0 before the first one
+    pub async fn get_window_number(&self) -> Result<U256> {
+        Ok(self.contract.get_window_number().call().await?)
+    }
+
     pub async fn get_bet(&self, index: U256) -> Result<(Address, Address, bool, U256)> {
         Ok(self.contract.get_bet(index).call().await?)
     }
@@ -135,30 +178,32 @@ impl AddrLoggerContractService {
         Ok(self.contract.get_bet_count().call().await?)
     }
 
-    pub async fn process_payouts(&self, winners: Vec<bool>) -> Result<String> {
-        let tx = self
+    pub async fn process_payouts(&self, winners: Vec<bool>) -> Result<H256> {
+        let tx_hash = self
             .contract
             .process_payouts(winners)
             .send()
             .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+            .tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
     }
 
     pub async fn is_valid_address(&self, address: Address) -> Result<bool> {
         Ok(self.contract.is_valid_address(address).call().await?)
     }
 
+    #[allow(dead_code)]
     pub async fn get_operator(&self) -> Result<Address> {
         Ok(self.contract.get_operator().call().await?)
     }
 
+    #[allow(dead_code)]
     pub async fn get_treasury(&self) -> Result<Address> {
         Ok(self.contract.get_treasury().call().await?)
     }
 
+    #[allow(dead_code)]
     pub async fn get_token(&self) -> Result<Address> {
         Ok(self.contract.get_token().call().await?)
     }
@@ -1,6 +1,6 @@
 use crate::models::AddressScore;
+use crate::repository::Repository;
 use rand::{rng, Rng};
-use sqlx::SqlitePool;
 
 pub fn generate_address() -> String {
     let mut rng = rng();
@@ -25,96 +25,11 @@ pub fn generate_addresses(count: u32) -> Vec<AddressScore> {
 }
 
 pub async fn generate_and_store_addresses(
-    pool: &SqlitePool,
+    repository: &dyn Repository,
+    leaderboard_id: i64,
     count: u32,
 ) -> Result<Vec<AddressScore>, anyhow::Error> {
-    let addresses = generate_addresses(count);
-    let mut stored_addresses = Vec::with_capacity(addresses.len());
-
-    for addr in addresses {
-        let stored = sqlx::query!(
-            r#"
-            INSERT INTO addresses (address, score)
-            VALUES (?, ?)
-            RETURNING id, address, score, created_at
-            "#,
-            addr.address,
-            addr.score
-        )
-        .fetch_one(pool)
-        .await?;
-
-        stored_addresses.push(AddressScore {
-            id: Some(stored.id),
-            address: stored.address,
-            score: stored.score as u32,
-            created_at: Some(chrono::DateTime::from_naive_utc_and_offset(
-                stored
-                    .created_at
-                    .expect("Time created not found for stored"),
-                chrono::Utc,
-            )),
-        });
-    }
-
-    Ok(stored_addresses)
-}
-
-pub async fn get_stored_addresses(
-    pool: &SqlitePool,
-    page: u32,
-    per_page: u32,
-) -> Result<Vec<AddressScore>, anyhow::Error> {
-    let offset = (page - 1) * per_page;
-
-    let addresses = sqlx::query!(
-        r#"
-        SELECT id, address, score, created_at
-        FROM addresses
-        ORDER BY created_at DESC
-        LIMIT ? OFFSET ?
-        "#,
-        per_page,
-        offset
-    )
-    .fetch_all(pool)
-    .await?;
-
-    Ok(addresses
-        .into_iter()
-        .map(|row| AddressScore {
-            id: Some(row.id),
-            address: row.address,
-            score: row.score as u32,
-            created_at: Some(chrono::DateTime::from_naive_utc_and_offset(
-                row.created_at.expect("Time created not found for row"),
-                chrono::Utc,
-            )),
-        })
-        .collect())
-}
-
-pub async fn get_all_addresses(pool: &SqlitePool) -> Result<Vec<AddressScore>, anyhow::Error> {
-    let addresses = sqlx::query!(
-        r#"
-        SELECT id, address, score, created_at
-        FROM addresses
-        ORDER BY created_at DESC
-        "#
-    )
-    .fetch_all(pool)
-    .await?;
-
-    Ok(addresses
-        .into_iter()
-        .map(|row| AddressScore {
-            id: Some(row.id),
-            address: row.address,
-            score: row.score as u32,
-            created_at: Some(chrono::DateTime::from_naive_utc_and_offset(
-                row.created_at.expect("Time created not found for row"),
-                chrono::Utc,
-            )),
-        })
-        .collect())
+    repository
+        .insert_addresses(leaderboard_id, &generate_addresses(count))
+        .await
 }
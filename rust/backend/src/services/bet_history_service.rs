@@ -0,0 +1,111 @@
+use crate::models::{BetFilterParams, BetPageResponse, PlayerStatsResponse};
+use crate::repository::Repository;
+use crate::services::indexer_service::{OUTCOME_LOST, OUTCOME_PENDING, OUTCOME_WON};
+use crate::services::payout_service;
+use anyhow::{anyhow, Result};
+use ethers::types::{I256, U256};
+
+const DEFAULT_PER_PAGE: u32 = 50;
+const MAX_PER_PAGE: u32 = 500;
+
+/// Which bets a page is drawn from
+pub enum BetScope<'a> {
+    Player(&'a str),
+    Window(i64),
+}
+
+/// Reject filters that can't match any bet, before they reach the database.
+pub fn validate_filters(params: &BetFilterParams) -> Result<()> {
+    if let Some(outcome) = &params.outcome {
+        if ![OUTCOME_PENDING, OUTCOME_WON, OUTCOME_LOST].contains(&outcome.as_str()) {
+            return Err(anyhow!("Unknown outcome '{}'", outcome));
+        }
+    }
+    Ok(())
+}
+
+/// Page through the indexed bets of a player or a window, newest first.
+/// The filters must have passed [`validate_filters`].
+pub async fn find_bets(
+    repository: &dyn Repository,
+    scope: BetScope<'_>,
+    params: &BetFilterParams,
+) -> Result<BetPageResponse> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+
+    let (total, bets) = repository.find_bets(scope, params, page, per_page).await?;
+
+    Ok(BetPageResponse {
+        page,
+        per_page,
+        total,
+        bets,
+    })
+}
+
+/// Aggregate a player's indexed bets. Amounts are token base units after the fee.
+pub async fn player_stats(
+    repository: &dyn Repository,
+    bettor: &str,
+) -> Result<PlayerStatsResponse> {
+    let rows = repository.bet_outcomes(bettor).await?;
+
+    let mut stats = PlayerStatsResponse {
+        address: bettor.to_string(),
+        total_bets: rows.len() as i64,
+        wins: 0,
+        losses: 0,
+        pending: 0,
+        windows: 0,
+        volume: String::new(),
+        settled_volume: String::new(),
+        total_payout: String::new(),
+        net_profit: String::new(),
+        roi: None,
+    };
+    let mut windows = std::collections::BTreeSet::new();
+    let mut volume = U256::zero();
+    let mut settled_volume = U256::zero();
+    let mut total_payout = U256::zero();
+
+    for (window_id, outcome, amount, payout) in &rows {
+        let amount = U256::from_dec_str(amount)?;
+        windows.insert(*window_id);
+        volume += amount;
+
+        match outcome.as_str() {
+            OUTCOME_WON => {
+                stats.wins += 1;
+                settled_volume += amount;
+                if let Some(payout) = payout {
+                    total_payout += U256::from_dec_str(payout)?;
+                }
+            }
+            OUTCOME_LOST => {
+                stats.losses += 1;
+                settled_volume += amount;
+            }
+            _ => stats.pending += 1,
+        }
+    }
+
+    let net_profit = I256::from_raw(total_payout) - I256::from_raw(settled_volume);
+    if !settled_volume.is_zero() {
+        // Lossy, only meant for display
+        let net = net_profit.to_string().parse::<f64>().unwrap_or_default();
+        let staked = payout_service::to_f64(settled_volume);
+        stats.roi = Some(net / staked);
+    }
+
+    stats.windows = windows.len() as i64;
+    stats.volume = volume.to_string();
+    stats.settled_volume = settled_volume.to_string();
+    stats.total_payout = total_payout.to_string();
+    stats.net_profit = net_profit.to_string();
+
+    Ok(stats)
+}
@@ -1,10 +1,8 @@
+use crate::signer::SignerClient;
 use anyhow::Result;
 use ethers::{
-    middleware::SignerMiddleware,
     prelude::abigen,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
-    types::{Address, U256},
+    types::{Address, H256, U256},
 };
 use log::debug;
 use std::sync::Arc;
@@ -26,27 +24,18 @@ abigen!(
 
 #[derive(Clone)]
 pub struct BettingTokenService {
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    contract: BettingToken<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    contract: BettingToken<SignerClient>,
 }
 
 impl BettingTokenService {
-    pub async fn new(rpc_url: &str, private_key: &str, contract_address: &str) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet = private_key.parse::<LocalWallet>()?;
-        let chain_id = provider.get_chainid().await?.as_u64();
-        let client = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(chain_id),
-        ));
-
+    pub fn new(client: Arc<SignerClient>, contract_address: &str) -> Result<Self> {
         let address = contract_address
             .parse::<Address>()
             .map_err(|e| anyhow::anyhow!("Failed to parse contract address: {}", e))?;
 
-        let contract = BettingToken::new(address, client.clone());
+        let contract = BettingToken::new(address, client);
 
-        Ok(Self { client, contract })
+        Ok(Self { contract })
     }
 
     const DECIMALS: u32 = 18;
@@ -61,47 +50,28 @@ impl BettingTokenService {
         (amount / U256::from(10).pow(U256::from(Self::DECIMALS))).as_u64()
     }
 
-    pub async fn mint(&self, value: u64) -> Result<Vec<u8>> {
+    pub async fn mint(&self, value: u64) -> Result<H256> {
         let amount = Self::to_token_amount(value);
         print!("[betting token service] Amount: {}", amount);
-        let tx = self
-            .contract
-            .mint(amount)
-            .send()
-            .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
-
-        Ok(tx.transaction_hash.as_bytes().to_vec())
+        let tx_hash = self.contract.mint(amount).send().await?.tx_hash();
+
+        Ok(tx_hash)
     }
 
-    pub async fn mint_to(&self, to: Address, value: u64) -> Result<Vec<u8>> {
+    pub async fn mint_to(&self, to: Address, value: u64) -> Result<H256> {
         let amount = Self::to_token_amount(value);
         debug!("Raw amount with decimals: {}", amount);
 
-        let tx = self
-            .contract
-            .mint_to(to, amount)
-            .send()
-            .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+        let tx_hash = self.contract.mint_to(to, amount).send().await?.tx_hash();
 
-        println!("Transaction: {:?}", tx);
-        Ok(tx.transaction_hash.as_bytes().to_vec())
+        Ok(tx_hash)
     }
 
-    pub async fn burn(&self, value: u64) -> Result<Vec<u8>> {
+    pub async fn burn(&self, value: u64) -> Result<H256> {
         let amount = Self::to_token_amount(value);
-        let tx = self
-            .contract
-            .burn(amount)
-            .send()
-            .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
-
-        Ok(tx.transaction_hash.as_bytes().to_vec())
+        let tx_hash = self.contract.burn(amount).send().await?.tx_hash();
+
+        Ok(tx_hash)
     }
 
     pub async fn balance_of(&self, account: Address) -> Result<u64> {
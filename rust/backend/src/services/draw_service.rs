@@ -0,0 +1,173 @@
+use crate::config::env_or;
+use crate::models::{Epoch, ExportScope, RankingScope, WindowDraw, WindowSelection};
+use crate::repository::Repository;
+use crate::services::export_service::EXPORT_PAGE_SIZE;
+use crate::services::hash_service::CanonicalHasher;
+use crate::services::selection_service;
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::{Address, H256},
+    utils::keccak256,
+};
+use std::{collections::BinaryHeap, sync::Arc};
+
+#[derive(Debug, Clone)]
+pub struct DrawConfig {
+    /// Blocks between committing a draw and the block whose hash seeds it
+    pub delay_blocks: u64,
+    /// Blocks on top of the seed block before a window can start with it
+    pub confirmations: u64,
+}
+
+impl DrawConfig {
+    pub fn from_env() -> Self {
+        Self {
+            delay_blocks: env_or("WINDOW_DRAW_DELAY_BLOCKS", 5).max(1),
+            confirmations: env_or("WINDOW_DRAW_CONFIRMATIONS", 3),
+        }
+    }
+}
+
+/// Seeds window selections with the hash of a block mined after the selection was committed,
+/// so the operator cannot pick players whose outcome they already know.
+#[derive(Clone)]
+pub struct DrawService {
+    provider: Arc<Provider<Http>>,
+    config: DrawConfig,
+}
+
+/// Players of a draw and the canonical hash of the snapshot they were drawn from
+#[derive(Debug)]
+pub struct DrawnPlayers {
+    pub addresses: Vec<String>,
+    pub snapshot_hash: String,
+}
+
+impl DrawService {
+    pub fn new(rpc_url: &str, config: DrawConfig) -> Result<Self> {
+        Ok(Self {
+            provider: Arc::new(Provider::<Http>::try_from(rpc_url)?),
+            config,
+        })
+    }
+
+    /// Commit the next window of a leaderboard to a block `delay_blocks` after the head
+    pub async fn commit(
+        &self,
+        repository: &dyn Repository,
+        epoch: &Epoch,
+        selection: &WindowSelection,
+    ) -> Result<WindowDraw> {
+        let head = self.provider.get_block_number().await?.as_u64();
+
+        repository
+            .create_draw(
+                epoch.leaderboard_id,
+                epoch.id,
+                &epoch.snapshot_hash,
+                selection,
+                head + self.config.delay_blocks,
+            )
+            .await
+    }
+
+    /// Hash of the draw's target block, `None` until it has enough confirmations
+    pub async fn seed(&self, draw: &WindowDraw) -> Result<Option<H256>> {
+        let head = self.provider.get_block_number().await?.as_u64();
+        if head < draw.target_block + self.config.confirmations {
+            return Ok(None);
+        }
+
+        let block = self
+            .provider
+            .get_block(draw.target_block)
+            .await?
+            .ok_or_else(|| anyhow!("Block {} not found", draw.target_block))?;
+        Ok(block.hash)
+    }
+}
+
+/// Why a selection cannot be drawn from an epoch, `None` when it can
+pub fn check_draw(epoch: &Epoch, selection: &WindowSelection) -> Option<String> {
+    if epoch.tx_id.is_none() {
+        return Some(format!(
+            "Epoch {} must be anchored before windows are drawn from it",
+            epoch.number
+        ));
+    }
+    if selection_service::strategy_bands(selection).is_none() {
+        return Some("An explicit selection cannot be drawn".to_string());
+    }
+    None
+}
+
+/// Rank of a player in a draw, lowest first
+pub fn draw_key(seed: H256, snapshot_hash: &[u8], address: &str) -> Result<[u8; 32]> {
+    let address = address.parse::<Address>()?;
+    Ok(keccak256(
+        [seed.as_bytes(), snapshot_hash, address.as_bytes()].concat(),
+    ))
+}
+
+/// Players of a draw, derived from the seed and the snapshot alone. Each player is keyed by
+/// `keccak256(seed ‖ snapshot_hash ‖ address)`, bands take their players by lowest key and
+/// the window lists them by key.
+pub async fn draw_players(
+    repository: &dyn Repository,
+    draw: &WindowDraw,
+    seed: H256,
+) -> Result<DrawnPlayers> {
+    let (count, bands) = selection_service::strategy_bands(&draw.selection)
+        .ok_or_else(|| anyhow!("An explicit selection cannot be drawn"))?;
+    let snapshot_hash = hex::decode(&draw.snapshot_hash)?;
+    let scope = RankingScope::Snapshot {
+        epoch_id: draw.epoch_id,
+    };
+    let bands = selection_service::score_bands(repository, scope, bands).await?;
+
+    // The `count` lowest keys of each band
+    let mut candidates: Vec<BinaryHeap<([u8; 32], String)>> = vec![BinaryHeap::new(); bands.len()];
+    let mut hasher = CanonicalHasher::new();
+    let mut after = String::new();
+    loop {
+        let scores = repository
+            .export_scores(
+                &ExportScope::Snapshot {
+                    epoch_id: draw.epoch_id,
+                },
+                &after,
+                EXPORT_PAGE_SIZE,
+            )
+            .await?;
+        let Some(last) = scores.last() else {
+            break;
+        };
+        after = last.address.clone();
+
+        for score in &scores {
+            hasher.update(&score.address, score.score)?;
+            let band = bands
+                .iter()
+                .position(|band| band.contains(&(score.score as i64)))
+                .ok_or_else(|| anyhow!("Score {} is outside the bands", score.score))?;
+            let key = draw_key(seed, &snapshot_hash, &score.address)?;
+            candidates[band].push((key, score.address.clone()));
+            if candidates[band].len() > count as usize {
+                candidates[band].pop();
+            }
+        }
+    }
+
+    let candidates = candidates
+        .into_iter()
+        .map(BinaryHeap::into_sorted_vec)
+        .collect();
+    let mut players = selection_service::allocate(count, candidates);
+    players.sort();
+
+    Ok(DrawnPlayers {
+        addresses: players.into_iter().map(|(_, address)| address).collect(),
+        snapshot_hash: hasher.finalize(),
+    })
+}
@@ -0,0 +1,249 @@
+use crate::models::{AddressScore, ExportScope};
+use crate::repository::DynRepository;
+use crate::services::hash_service::CanonicalHasher;
+use actix_web::web::Bytes;
+use anyhow::{anyhow, Result};
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, TimestampMillisecondArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use futures_util::{stream, Stream, StreamExt};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::format::KeyValue;
+use serde::Serialize;
+use std::{str::FromStr, sync::Arc};
+
+/// Scores read from the repository per query
+pub const EXPORT_PAGE_SIZE: u32 = 5000;
+/// Rows per Parquet row group, bounds what the writer buffers
+const PARQUET_ROW_GROUP_SIZE: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            "parquet" => Ok(Self::Parquet),
+            _ => Err(anyhow!("Unknown format, expected csv, ndjson or parquet")),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Ndjson => "application/x-ndjson",
+            Self::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    address: &'a str,
+    score: u32,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Canonical hash of the exported scores, the last NDJSON line
+#[derive(Serialize)]
+struct ExportSummary<'a> {
+    hash: &'a str,
+    record_count: usize,
+}
+
+enum Encoder {
+    Csv,
+    Ndjson,
+    Parquet(Box<ArrowWriter<Vec<u8>>>),
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Result<Self> {
+        Ok(match format {
+            ExportFormat::Csv => Self::Csv,
+            ExportFormat::Ndjson => Self::Ndjson,
+            ExportFormat::Parquet => {
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(PARQUET_ROW_GROUP_SIZE)
+                    .build();
+                Self::Parquet(Box::new(ArrowWriter::try_new(
+                    Vec::new(),
+                    parquet_schema(),
+                    Some(properties),
+                )?))
+            }
+        })
+    }
+
+    fn header(&self) -> Vec<u8> {
+        match self {
+            Self::Csv => b"address,score,created_at\n".to_vec(),
+            Self::Ndjson | Self::Parquet(_) => Vec::new(),
+        }
+    }
+
+    fn rows(&mut self, scores: &[AddressScore]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self {
+            Self::Csv => {
+                for score in scores {
+                    let created_at = score
+                        .created_at
+                        .map(|created_at| created_at.to_rfc3339())
+                        .unwrap_or_default();
+                    out.extend(
+                        format!("{},{},{}\n", score.address, score.score, created_at).bytes(),
+                    );
+                }
+            }
+            Self::Ndjson => {
+                for score in scores {
+                    serde_json::to_writer(&mut out, &export_row(score))?;
+                    out.push(b'\n');
+                }
+            }
+            Self::Parquet(writer) => {
+                writer.write(&record_batch(scores)?)?;
+                // Row groups the writer completed so far
+                out = std::mem::take(writer.inner_mut());
+            }
+        }
+        Ok(out)
+    }
+
+    fn trailer(self, hash: &str, record_count: usize) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::Csv => format!("# hash={},record_count={}\n", hash, record_count).into_bytes(),
+            Self::Ndjson => {
+                let mut out = serde_json::to_vec(&ExportSummary { hash, record_count })?;
+                out.push(b'\n');
+                out
+            }
+            Self::Parquet(mut writer) => {
+                writer
+                    .append_key_value_metadata(KeyValue::new("hash".to_string(), hash.to_string()));
+                writer.append_key_value_metadata(KeyValue::new(
+                    "record_count".to_string(),
+                    record_count.to_string(),
+                ));
+                writer.into_inner()?
+            }
+        })
+    }
+}
+
+fn export_row(score: &AddressScore) -> ExportRow<'_> {
+    ExportRow {
+        address: &score.address,
+        score: score.score,
+        created_at: score.created_at,
+    }
+}
+
+fn parquet_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("address", DataType::Utf8, false),
+        Field::new("score", DataType::Int64, false),
+        Field::new(
+            "created_at",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            true,
+        ),
+    ]))
+}
+
+fn record_batch(scores: &[AddressScore]) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            scores.iter().map(|score| score.address.as_str()),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            scores.iter().map(|score| score.score as i64),
+        )),
+        Arc::new(
+            TimestampMillisecondArray::from_iter(scores.iter().map(|score| {
+                score
+                    .created_at
+                    .map(|created_at| created_at.timestamp_millis())
+            }))
+            .with_timezone("UTC"),
+        ),
+    ];
+
+    Ok(RecordBatch::try_new(parquet_schema(), columns)?)
+}
+
+struct Export {
+    repository: DynRepository,
+    scope: ExportScope,
+    /// Last exported address, pages continue after it
+    after: String,
+    encoder: Option<Encoder>,
+    hasher: CanonicalHasher,
+}
+
+/// Stream the scores of a scope sorted by address, ending with the canonical hash of the
+/// exported scores: a comment line in CSV, a summary line in NDJSON and footer metadata in
+/// Parquet. The hash of a complete snapshot equals its anchored `snapshot_hash`.
+pub fn export_scores(
+    repository: DynRepository,
+    scope: ExportScope,
+    format: ExportFormat,
+) -> Result<impl Stream<Item = Result<Bytes>> + 'static> {
+    let encoder = Encoder::new(format)?;
+    let header = Bytes::from(encoder.header());
+    let export = Export {
+        repository,
+        scope,
+        after: String::new(),
+        encoder: Some(encoder),
+        hasher: CanonicalHasher::new(),
+    };
+
+    let body = stream::try_unfold(export, |mut export| async move {
+        let Some(encoder) = export.encoder.as_mut() else {
+            return Ok(None);
+        };
+        let scores = export
+            .repository
+            .export_scores(&export.scope, &export.after, EXPORT_PAGE_SIZE)
+            .await?;
+
+        let Some(last) = scores.last() else {
+            let encoder = export.encoder.take().expect("encoder is present");
+            let hasher = std::mem::take(&mut export.hasher);
+            let record_count = hasher.count();
+            let trailer = encoder.trailer(&hasher.finalize(), record_count)?;
+            return Ok(Some((Bytes::from(trailer), export)));
+        };
+        export.after = last.address.clone();
+        for score in &scores {
+            export.hasher.update(&score.address, score.score)?;
+        }
+
+        let rows = encoder.rows(&scores)?;
+        Ok(Some((Bytes::from(rows), export)))
+    });
+
+    Ok(stream::once(async move { Ok(header) }).chain(body))
+}
@@ -1,9 +1,7 @@
+use crate::signer::SignerClient;
 use anyhow::Result;
 use ethers::{
-    middleware::SignerMiddleware,
     prelude::abigen,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
     types::{Address, H256, U256},
 };
 use std::sync::Arc;
@@ -18,27 +16,18 @@ abigen!(
 
 #[derive(Clone)]
 pub struct HashContractService {
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    contract: HashStorage<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    contract: HashStorage<SignerClient>,
 }
 
 impl HashContractService {
-    pub async fn new(rpc_url: &str, private_key: &str, contract_address: &str) -> Result<Self> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
-        let wallet = private_key.parse::<LocalWallet>()?;
-        let chain_id = provider.get_chainid().await?.as_u64();
-        let client = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(chain_id),
-        ));
-
+    pub fn new(client: Arc<SignerClient>, contract_address: &str) -> Result<Self> {
         let address = contract_address
             .parse::<Address>()
             .map_err(|e| anyhow::anyhow!("Failed to parse contract address: {}", e))?;
 
-        let contract = HashStorage::new(address, client.clone());
+        let contract = HashStorage::new(address, client);
 
-        Ok(Self { client, contract })
+        Ok(Self { contract })
     }
 
     pub async fn store_hash(
@@ -46,19 +35,18 @@ impl HashContractService {
         hash: [u8; 32],
         timestamp: i64,
         record_count: usize,
-    ) -> Result<String> {
+    ) -> Result<H256> {
         let hash_bytes = H256::from(hash);
         let timestamp = U256::from(timestamp as u64);
         let record_count = U256::from(record_count);
 
-        let tx = self
+        let tx_hash = self
             .contract
             .store_hash_record(hash_bytes.into(), timestamp, record_count)
             .send()
             .await?
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
+            .tx_hash();
 
-        Ok(format!("{:#x}", tx.transaction_hash))
+        Ok(tx_hash)
     }
 }
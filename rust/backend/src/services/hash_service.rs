@@ -1,7 +1,7 @@
 use crate::models::{AddressScore, HashResponse};
 use anyhow::Result;
 use chrono::Utc;
-use serde_json::{json, Value};
+use serde::Serialize;
 use sha3::{Digest, Keccak256};
 
 pub fn hash_address_data(addresses: Vec<AddressScore>) -> Result<HashResponse> {
@@ -9,33 +9,83 @@ pub fn hash_address_data(addresses: Vec<AddressScore>) -> Result<HashResponse> {
     let mut sorted_addresses = addresses;
     sorted_addresses.sort_by(|a, b| a.address.cmp(&b.address));
 
-    // Create a canonical JSON representation
-    let canonical_data: Vec<Value> = sorted_addresses
-        .iter()
-        .map(|addr| {
-            json!({
-                "address": addr.address.to_lowercase(), // Normalize Ethereum addresses
-                "score": addr.score
-            })
-        })
-        .collect();
-
-    // Serialize to string with sorted keys
-    let serialized = serde_json::to_string(&canonical_data)?;
-
-    // Create Keccak-256 hash
-    let mut hasher = Keccak256::new();
-    hasher.update(serialized.as_bytes());
-    let hash = hasher.finalize();
+    let mut hasher = CanonicalHasher::new();
+    for addr in &sorted_addresses {
+        hasher.update(&addr.address, addr.score)?;
+    }
 
     Ok(HashResponse {
         // serialized_data: serialized,
-        hash: hex::encode(hash),
+        hash: hasher.finalize(),
         timestamp: Utc::now().timestamp(),
         record_count: sorted_addresses.len(),
     })
 }
 
+/// One entry of the canonical JSON, keys in sorted order
+#[derive(Serialize)]
+struct CanonicalEntry<'a> {
+    address: &'a str,
+    score: u32,
+}
+
+/// Keccak-256 of the canonical JSON array `[{"address": ..., "score": ...}, ...]`, fed one
+/// entry at a time. Entries must arrive sorted by address to match `hash_address_data`.
+pub struct CanonicalHasher {
+    hasher: Keccak256,
+    count: usize,
+}
+
+impl CanonicalHasher {
+    pub fn new() -> Self {
+        let mut hasher = Keccak256::new();
+        hasher.update(b"[");
+        Self { hasher, count: 0 }
+    }
+
+    pub fn update(&mut self, address: &str, score: u32) -> Result<()> {
+        if self.count > 0 {
+            self.hasher.update(b",");
+        }
+        // Normalize Ethereum addresses
+        let entry = serde_json::to_vec(&CanonicalEntry {
+            address: &address.to_lowercase(),
+            score,
+        })?;
+        self.hasher.update(&entry);
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn finalize(mut self) -> String {
+        self.hasher.update(b"]");
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl Default for CanonicalHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hash anchored for the frozen scores of a window round, `keccak256(scores_hash ‖ round)`
+/// with the round as a 32-byte big-endian integer like Solidity's `abi.encodePacked`
+pub fn round_hash(scores_hash: &str, round: i64) -> Result<String> {
+    let scores_hash = hex::decode(scores_hash)?;
+    let mut round_bytes = [0u8; 32];
+    round_bytes[24..].copy_from_slice(&(round as u64).to_be_bytes());
+
+    let mut hasher = Keccak256::new();
+    hasher.update(&scores_hash);
+    hasher.update(round_bytes);
+    Ok(hex::encode(hasher.finalize()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +115,36 @@ mod tests {
             "Hashes should be identical for same input"
         );
     }
+
+    #[test]
+    fn test_hash_matches_canonical_json() {
+        let addresses = vec![
+            AddressScore {
+                id: None,
+                address: "0xB\"".to_string(),
+                score: 500,
+                created_at: None,
+            },
+            AddressScore {
+                id: None,
+                address: "0xA".to_string(),
+                score: 100,
+                created_at: None,
+            },
+        ];
+        let canonical = serde_json::to_string(&serde_json::json!([
+            {"address": "0xa", "score": 100},
+            {"address": "0xb\"", "score": 500},
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            hash_address_data(addresses).unwrap().hash,
+            hex::encode(Keccak256::digest(canonical.as_bytes()))
+        );
+        assert_eq!(
+            CanonicalHasher::new().finalize(),
+            hex::encode(Keccak256::digest(b"[]"))
+        );
+    }
 }
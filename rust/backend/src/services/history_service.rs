@@ -0,0 +1,43 @@
+use crate::models::ScoreEvent;
+use crate::repository::Repository;
+use crate::services::score_service::ScoreConfig;
+use anyhow::{anyhow, Result};
+use ethers::types::Address;
+
+/// Sources of score events. Scores stored before their history was recorded start with a
+/// `migration` event.
+pub const EVENT_SUBMISSION: &str = "submission";
+pub const EVENT_IMPORT: &str = "import";
+pub const EVENT_SEED: &str = "seed";
+pub const EVENT_CORRECTION: &str = "correction";
+/// The score moved into the snapshot of a closed epoch
+pub const EVENT_EPOCH: &str = "epoch";
+
+/// Set a player's score by hand, recording why
+pub async fn correct_score(
+    repository: &dyn Repository,
+    config: &ScoreConfig,
+    leaderboard_id: i64,
+    address: &str,
+    score: u32,
+    reason: &str,
+) -> Result<ScoreEvent> {
+    let address = address
+        .parse::<Address>()
+        .map_err(|_| anyhow!("Invalid player address"))?;
+    if score > config.max_score {
+        return Err(anyhow!("Score exceeds {}", config.max_score));
+    }
+    if reason.trim().is_empty() {
+        return Err(anyhow!("A correction needs a reason"));
+    }
+
+    repository
+        .correct_score(
+            leaderboard_id,
+            &format!("{:?}", address),
+            score,
+            reason.trim(),
+        )
+        .await
+}
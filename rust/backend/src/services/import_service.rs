@@ -0,0 +1,180 @@
+use crate::models::{AddressScore, ImportError, ImportReport};
+use crate::repository::Repository;
+use anyhow::Result;
+use ethers::types::Address;
+use serde::Deserialize;
+
+/// Rows handed to the repository at once, each batch commits on its own
+pub const IMPORT_BATCH_SIZE: usize = 1000;
+/// Rejected lines reported back with their reason
+pub const MAX_IMPORT_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// `address,score` lines with an optional header
+    Csv,
+    /// One `{"address": ..., "score": ...}` object per line
+    Ndjson,
+}
+
+impl ImportFormat {
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next()?.trim() {
+            "text/csv" => Some(Self::Csv),
+            "application/x-ndjson" | "application/jsonl" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ImportRow {
+    address: String,
+    score: u32,
+}
+
+/// Imports scores from a body that arrives in chunks, lines may span chunk boundaries.
+/// Players already on the leaderboard keep their score and are counted as duplicates, so
+/// an import that failed half way can be sent again.
+pub struct ScoreImporter<'a> {
+    repository: &'a dyn Repository,
+    leaderboard_id: i64,
+    format: ImportFormat,
+    max_score: u32,
+    buffer: Vec<u8>,
+    line: usize,
+    batch: Vec<AddressScore>,
+    report: ImportReport,
+}
+
+impl<'a> ScoreImporter<'a> {
+    pub fn new(
+        repository: &'a dyn Repository,
+        leaderboard_id: i64,
+        format: ImportFormat,
+        max_score: u32,
+    ) -> Self {
+        Self {
+            repository,
+            leaderboard_id,
+            format,
+            max_score,
+            buffer: Vec::new(),
+            line: 0,
+            batch: Vec::with_capacity(IMPORT_BATCH_SIZE),
+            report: ImportReport::default(),
+        }
+    }
+
+    /// Parse the complete lines of a chunk, storing a batch whenever one fills up
+    pub async fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        self.buffer.extend_from_slice(chunk);
+        let Some(end) = self.buffer.iter().rposition(|b| *b == b'\n') else {
+            return Ok(());
+        };
+        let rest = self.buffer.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.buffer, rest);
+
+        for line in complete[..end].split(|b| *b == b'\n') {
+            self.parse_line(line);
+            if self.batch.len() >= IMPORT_BATCH_SIZE {
+                self.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a last line without a newline and store what is left
+    pub async fn finish(mut self) -> Result<ImportReport> {
+        let last = std::mem::take(&mut self.buffer);
+        if !last.is_empty() {
+            self.parse_line(&last);
+        }
+        self.flush().await?;
+        Ok(self.report)
+    }
+
+    fn parse_line(&mut self, line: &[u8]) {
+        self.line += 1;
+
+        let text = match std::str::from_utf8(line) {
+            // Also drops the \r of CRLF line endings
+            Ok(text) => text.trim(),
+            Err(_) => return self.reject("Invalid UTF-8".to_string()),
+        };
+        if text.is_empty() {
+            return;
+        }
+        if self.format == ImportFormat::Csv
+            && self.line == 1
+            && text.to_lowercase().starts_with("address")
+        {
+            return;
+        }
+
+        match self.parse_row(text) {
+            Ok(row) => self.batch.push(row),
+            Err(reason) => self.reject(reason),
+        }
+    }
+
+    fn parse_row(&self, text: &str) -> Result<AddressScore, String> {
+        let row = match self.format {
+            ImportFormat::Csv => {
+                let mut fields = text.split(',').map(|f| f.trim().trim_matches('"'));
+                let (Some(address), Some(score), None) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err("Expected address,score".to_string());
+                };
+                ImportRow {
+                    address: address.to_string(),
+                    score: score.parse().map_err(|_| "Invalid score".to_string())?,
+                }
+            }
+            ImportFormat::Ndjson => {
+                serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?
+            }
+        };
+
+        let address = row
+            .address
+            .parse::<Address>()
+            .map_err(|_| "Invalid player address".to_string())?;
+        if row.score > self.max_score {
+            return Err(format!("Score exceeds {}", self.max_score));
+        }
+
+        Ok(AddressScore {
+            id: None,
+            address: format!("{:?}", address),
+            score: row.score,
+            created_at: None,
+        })
+    }
+
+    fn reject(&mut self, reason: String) {
+        self.report.rejected += 1;
+        if self.report.errors.len() < MAX_IMPORT_ERRORS {
+            self.report.errors.push(ImportError {
+                line: self.line,
+                reason,
+            });
+        }
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let inserted = self
+            .repository
+            .import_addresses(self.leaderboard_id, &self.batch)
+            .await?;
+
+        self.report.inserted += inserted;
+        self.report.duplicates += self.batch.len() as u64 - inserted;
+        self.batch.clear();
+        Ok(())
+    }
+}
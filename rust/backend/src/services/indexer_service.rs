@@ -0,0 +1,965 @@
+use crate::config::env_or;
+use crate::models::{
+    IndexedBet, IndexedMarket, IndexedWindow, IndexerStatusResponse, SettlementMarket,
+    WindowRequest,
+};
+use crate::repository::{ChainBatch, DynRepository, EventLog, NewWindow, Repository};
+use crate::services::addr_logger_contract_service::{
+    AddrLoggerEvents, BetPlacedFilter, MarketBetPlacedFilter, MarketSettledFilter,
+    PayoutProcessedFilter, WindowClosedFilter, WindowStartedFilter,
+};
+use crate::services::leaderboard_service::{self, DEFAULT_LEADERBOARD_ID};
+use crate::services::live_service::{AddressResult, LiveEvent, LiveFeed, PoolUpdate};
+use crate::services::market_service;
+use crate::services::payout_service;
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::EthLogDecode,
+    providers::{Http, Middleware, Provider},
+    types::{Address, Filter, Log, U256},
+};
+use log::{debug, error, info, warn};
+use std::{collections::BTreeSet, env, sync::Arc, time::Duration};
+
+pub const OUTCOME_PENDING: &str = "pending";
+pub const OUTCOME_WON: &str = "won";
+pub const OUTCOME_LOST: &str = "lost";
+
+#[derive(Debug, Clone)]
+pub struct IndexerConfig {
+    /// First block to index, defaults to the chain head on first start
+    pub start_block: Option<u64>,
+    pub batch_size: u64,
+    pub poll_interval: Duration,
+    /// Blocks to stay behind the head, reorgs deeper than this are rolled back
+    pub confirmations: u64,
+}
+
+impl IndexerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            start_block: env::var("INDEXER_START_BLOCK")
+                .ok()
+                .and_then(|v| v.trim().parse().ok()),
+            batch_size: env_or("INDEXER_BATCH_SIZE", 1000).max(1),
+            poll_interval: Duration::from_secs(env_or("INDEXER_POLL_INTERVAL_SECS", 3)),
+            confirmations: env_or("INDEXER_CONFIRMATIONS", 0),
+        }
+    }
+}
+
+/// Follows the AddressLogger events and mirrors windows, bets and payouts into the
+/// repository. Besides the default contract it follows the contracts of all leaderboards;
+/// contracts registered later are only indexed from the current cursor on. Every replica
+/// reads the indexed state, only the chain writer spawns the indexing loop.
+#[derive(Clone)]
+pub struct EventIndexerService {
+    repository: DynRepository,
+    provider: Arc<Provider<Http>>,
+    /// Contract of leaderboards without one of their own
+    contract: Address,
+    config: IndexerConfig,
+    live: LiveFeed,
+}
+
+impl EventIndexerService {
+    pub fn new(
+        repository: DynRepository,
+        rpc_url: &str,
+        contract_address: &str,
+        config: IndexerConfig,
+        live: LiveFeed,
+    ) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(rpc_url)?;
+        let contract = contract_address
+            .parse::<Address>()
+            .map_err(|e| anyhow!("Failed to parse contract address: {}", e))?;
+
+        Ok(Self {
+            repository,
+            provider: Arc::new(provider),
+            contract,
+            config,
+            live,
+        })
+    }
+
+    /// Spawn the background loop that follows new blocks.
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            info!(
+                "Event indexer started for {:?} (start block {:?})",
+                self.contract, self.config.start_block
+            );
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.sync().await {
+                    error!("indexer: Sync failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn sync(&self) -> Result<()> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await?
+            .as_u64()
+            .saturating_sub(self.config.confirmations);
+
+        let mut cursor = match self.repository.indexer_cursor().await? {
+            Some(cursor) => cursor,
+            None => {
+                let start = self.config.start_block.unwrap_or(head);
+                info!("indexer: Starting from block {}", start);
+                let mut batch = self.repository.begin_chain_batch().await?;
+                batch.set_cursor(start.saturating_sub(1)).await?;
+                batch.commit().await?;
+                start.saturating_sub(1)
+            }
+        };
+
+        if let Some(ancestor) = self.find_reorg(cursor).await? {
+            self.rollback(ancestor).await?;
+            cursor = ancestor;
+        }
+
+        while cursor < head {
+            let to = head.min(cursor + self.config.batch_size);
+            self.index_range(cursor + 1, to).await?;
+            cursor = to;
+        }
+
+        Ok(())
+    }
+
+    async fn contracts(&self) -> Result<Vec<Address>> {
+        let mut contracts = vec![self.contract];
+        for (contract, _) in
+            leaderboard_service::contract_leaderboards(self.repository.as_ref()).await?
+        {
+            if !contracts.contains(&contract) {
+                contracts.push(contract);
+            }
+        }
+
+        Ok(contracts)
+    }
+
+    /// Compare stored block hashes with the chain, newest first, and return the last
+    /// block that is still canonical when the cursor block was reorged away.
+    async fn find_reorg(&self, cursor: u64) -> Result<Option<u64>> {
+        let stored = self.repository.indexed_blocks(cursor).await?;
+
+        for (i, (number, hash)) in stored.iter().enumerate() {
+            let canonical = self
+                .provider
+                .get_block(*number)
+                .await?
+                .and_then(|block| block.hash)
+                .map(|hash| format!("{:#x}", hash));
+
+            if canonical.as_deref() == Some(hash.as_str()) {
+                return Ok((i > 0).then_some(*number));
+            }
+            warn!("indexer: Block {} was reorged", number);
+        }
+
+        // Nothing indexed is canonical anymore, start over
+        match stored.last() {
+            Some((oldest, _)) => Ok(Some(oldest.saturating_sub(1))),
+            None => Ok(None),
+        }
+    }
+
+    async fn rollback(&self, ancestor: u64) -> Result<()> {
+        warn!("indexer: Rolling back to block {}", ancestor);
+        self.repository.rollback_chain(ancestor).await?;
+        self.live.publish(LiveEvent::Reorg {
+            ancestor: ancestor as i64,
+        });
+        Ok(())
+    }
+
+    async fn index_range(&self, from: u64, to: u64) -> Result<()> {
+        let filter = Filter::new()
+            .address(self.contracts().await?)
+            .from_block(from)
+            .to_block(to);
+        let mut logs = self.provider.get_logs(&filter).await?;
+        logs.retain(|log| log.removed != Some(true));
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        debug!("indexer: {} logs in blocks {}..={}", logs.len(), from, to);
+
+        // Remember the hash of every block with events plus the batch end for reorg checks
+        let mut numbers: BTreeSet<u64> = logs
+            .iter()
+            .filter_map(|log| log.block_number.map(|n| n.as_u64()))
+            .collect();
+        numbers.insert(to);
+        let mut blocks = Vec::with_capacity(numbers.len());
+        for number in numbers {
+            let block = self
+                .provider
+                .get_block(number)
+                .await?
+                .ok_or_else(|| anyhow!("Block {} not found", number))?;
+            let hash = block
+                .hash
+                .ok_or_else(|| anyhow!("Block {} is still pending", number))?;
+            blocks.push((number, hash, block.timestamp.as_u64()));
+        }
+
+        let mut batch = self.repository.begin_chain_batch().await?;
+        for (number, hash, timestamp) in blocks {
+            batch
+                .record_block(number, &format!("{:#x}", hash), timestamp)
+                .await?;
+        }
+
+        let mut events = Vec::new();
+        for log in logs {
+            let event = match AddrLoggerEvents::decode_log(&log.clone().into()) {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("indexer: Skipping undecodable log: {}", e);
+                    continue;
+                }
+            };
+            events
+                .extend(apply_event(batch.as_mut(), self.repository.as_ref(), &log, event).await?);
+        }
+
+        // A settlement emits all of its payouts in one transaction, so it lands in one batch
+        let settled = batch.paid_windows(from, to).await?;
+        for window_id in settled {
+            events.extend(settle_window(batch.as_mut(), window_id).await?);
+        }
+
+        batch.set_cursor(to).await?;
+        batch.commit().await?;
+        self.live.publish_all(events);
+
+        Ok(())
+    }
+
+    pub async fn status(&self) -> Result<IndexerStatusResponse> {
+        self.repository.indexer_status().await
+    }
+
+    pub async fn latest_window(&self, leaderboard_id: i64) -> Result<Option<IndexedWindow>> {
+        self.repository.latest_window(leaderboard_id).await
+    }
+
+    pub async fn window(&self, window_id: i64) -> Result<Option<IndexedWindow>> {
+        self.repository.indexed_window(window_id).await
+    }
+
+    /// Window whose players' scores were frozen in `round_id`
+    pub async fn round_window(&self, round_id: i64) -> Result<Option<IndexedWindow>> {
+        self.repository.round_window(round_id).await
+    }
+
+    pub async fn bet_count(&self, window_id: i64) -> Result<i64> {
+        self.repository.bet_count(window_id).await
+    }
+
+    pub async fn bet(&self, window_id: i64, bet_index: i64) -> Result<Option<IndexedBet>> {
+        self.repository.indexed_bet(window_id, bet_index).await
+    }
+
+    /// Markets of a window with their pools, in the contract's order
+    pub async fn markets(&self, window_id: i64) -> Result<Vec<IndexedMarket>> {
+        let mut markets = self.repository.window_markets(window_id).await?;
+        for market in &mut markets {
+            let bets = self
+                .repository
+                .market_stakes(window_id, market.market_index)
+                .await?;
+            market.pools = market_service::pools(&bets, market.outcomes as u64)
+                .iter()
+                .map(U256::to_string)
+                .collect();
+        }
+
+        Ok(markets)
+    }
+
+    /// Markets of a window in the contract's order, as the prover settles them. The window's
+    /// proof must commit exactly these.
+    pub async fn settlement_markets(&self, window_id: i64) -> Result<Vec<SettlementMarket>> {
+        let markets = self.repository.window_markets(window_id).await?;
+
+        Ok(markets
+            .iter()
+            .filter_map(|market| market_service::settlement_market(&market.address, &market.market))
+            .collect())
+    }
+
+    /// Up and down pools (after fees) for one address of a window, as the contract tracks them.
+    pub async fn pool_amounts(&self, window_id: i64, address: &str) -> Result<(U256, U256)> {
+        let bets = self.repository.pool_bets(window_id).await?;
+
+        Ok(payout_service::pools(&bets, address))
+    }
+}
+
+/// Derive each bet's outcome and payout once the window's payouts have been indexed.
+async fn settle_window(batch: &mut dyn ChainBatch, window_id: i64) -> Result<Option<LiveEvent>> {
+    let window = batch.window(window_id).await?;
+    let bets = batch.pool_bets(window_id).await?;
+
+    let payout_rows = batch.window_payouts(window_id).await?;
+    let payouts = payout_rows
+        .iter()
+        .map(|(bettor, amount, _, _)| Ok((bettor.clone(), U256::from_dec_str(amount)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let Some(winners) = payout_service::infer_winners(&window.valid_addresses, &bets, &payouts)
+    else {
+        warn!(
+            "indexer: Payouts of window {} do not match its bets, leaving bets pending",
+            window_id
+        );
+        return Ok(None);
+    };
+
+    for (bet_index, bet) in bets.iter().enumerate() {
+        let address_index = window
+            .valid_addresses
+            .iter()
+            .position(|addr| *addr == bet.selected_address);
+        // One-sided pools are sent to the treasury, so those bets are lost as well
+        let (outcome, payout) = match address_index.and_then(|i| winners[i]) {
+            Some(side) if side == bet.position => {
+                let (up, down) = payout_service::pools(&bets, &bet.selected_address);
+                let (winning_pool, losing_pool) = if side { (up, down) } else { (down, up) };
+                let payout = payout_service::winning_payout(bet.amount, winning_pool, losing_pool);
+                (OUTCOME_WON, Some(payout.to_string()))
+            }
+            _ => (OUTCOME_LOST, None),
+        };
+
+        batch
+            .set_bet_outcome(window_id, bet_index as i64, outcome, payout)
+            .await?;
+    }
+
+    let Some((_, _, block_number, tx_hash)) = payout_rows.last() else {
+        return Ok(None);
+    };
+    batch
+        .set_window_settled(window_id, *block_number, tx_hash)
+        .await?;
+    info!("indexer: Window {} settled", window_id);
+
+    let results = window
+        .valid_addresses
+        .iter()
+        .zip(winners)
+        .map(|(address, winning_position)| AddressResult {
+            address: address.clone(),
+            winning_position,
+        })
+        .collect();
+    Ok(Some(LiveEvent::WindowSettled {
+        window_id,
+        results,
+        block_number: *block_number,
+    }))
+}
+
+/// Store one decoded event and return the live updates it causes. Events attach to the
+/// latest window of the contract that emitted them.
+async fn apply_event(
+    batch: &mut dyn ChainBatch,
+    repository: &dyn Repository,
+    log: &Log,
+    event: AddrLoggerEvents,
+) -> Result<Vec<LiveEvent>> {
+    let block_number = log.block_number.map(|n| n.as_u64() as i64).unwrap_or(0);
+    let tx_hash = log
+        .transaction_hash
+        .map(|hash| format!("{:#x}", hash))
+        .unwrap_or_default();
+    let position = EventLog {
+        block_number,
+        tx_hash: &tx_hash,
+        log_index: log.log_index.map(|i| i.as_u64() as i64).unwrap_or(0),
+    };
+    let contract = format!("{:?}", log.address);
+
+    match event {
+        AddrLoggerEvents::WindowStartedFilter(WindowStartedFilter {
+            operator,
+            valid_addresses,
+            timestamp,
+        }) => {
+            let addresses: Vec<String> = valid_addresses
+                .iter()
+                .map(|addr| format!("{:?}", addr))
+                .collect();
+            // Shared contracts and snapshots are resolved through the request that started it
+            let request = match repository.window_request(&tx_hash).await? {
+                Some(request) => request,
+                None => WindowRequest {
+                    leaderboard_id: leaderboard_service::contract_leaderboards(repository)
+                        .await?
+                        .into_iter()
+                        .find(|(address, _)| *address == log.address)
+                        .map_or(DEFAULT_LEADERBOARD_ID, |(_, id)| id),
+                    epoch_id: None,
+                    selection: None,
+                    round_id: None,
+                },
+            };
+            let leaderboard_id = request.leaderboard_id;
+            let window = NewWindow {
+                operator: format!("{:?}", operator),
+                addresses: &addresses,
+                started_at: timestamp.as_u64() as i64,
+                contract: &contract,
+                request,
+            };
+            let window_id = batch.insert_window(&window, &position).await?;
+            info!(
+                "indexer: Window {} of leaderboard {} started with {} addresses",
+                window_id,
+                leaderboard_id,
+                addresses.len()
+            );
+
+            Ok(vec![LiveEvent::WindowStarted {
+                window_id,
+                leaderboard_id,
+                addresses,
+                block_number,
+            }])
+        }
+        AddrLoggerEvents::WindowClosedFilter(WindowClosedFilter { timestamp, .. }) => {
+            let window_id = batch
+                .close_window(&contract, timestamp.as_u64() as i64, &position)
+                .await?;
+            info!("indexer: Window closed");
+
+            Ok(window_id
+                .map(|window_id| LiveEvent::WindowClosed {
+                    window_id,
+                    block_number,
+                })
+                .into_iter()
+                .collect())
+        }
+        AddrLoggerEvents::BetPlacedFilter(BetPlacedFilter {
+            bettor,
+            selected_address,
+            position: side,
+            amount,
+        }) => {
+            let Some(window_id) = batch.contract_window(&contract).await? else {
+                warn!(
+                    "indexer: Bet in block {} precedes any indexed window",
+                    block_number
+                );
+                return Ok(vec![]);
+            };
+            let bettor = format!("{:?}", bettor);
+            let selected_address = format!("{:?}", selected_address);
+
+            let bet_index = batch
+                .insert_bet(
+                    window_id,
+                    &bettor,
+                    &selected_address,
+                    side,
+                    amount,
+                    &position,
+                )
+                .await?;
+            // Already indexed
+            let Some(bet_index) = bet_index else {
+                return Ok(vec![]);
+            };
+
+            let bets = batch.pool_bets(window_id).await?;
+            let (up, down) = payout_service::pools(&bets, &selected_address);
+            Ok(vec![
+                LiveEvent::BetPlaced {
+                    window_id,
+                    bet_index,
+                    bettor,
+                    selected_address: selected_address.clone(),
+                    position: side,
+                    amount: amount.to_string(),
+                    block_number,
+                },
+                LiveEvent::Pools {
+                    window_id,
+                    pool: PoolUpdate::new(selected_address, up, down),
+                },
+            ])
+        }
+        AddrLoggerEvents::PayoutProcessedFilter(PayoutProcessedFilter {
+            bettor,
+            amount,
+            is_winner,
+        }) => {
+            batch
+                .insert_payout(
+                    &contract,
+                    &format!("{:?}", bettor),
+                    amount,
+                    is_winner,
+                    &position,
+                )
+                .await?;
+
+            Ok(vec![])
+        }
+        AddrLoggerEvents::MarketBetPlacedFilter(MarketBetPlacedFilter {
+            bettor,
+            kind,
+            selected_address,
+            param,
+            outcome,
+            amount,
+        }) => {
+            let Some(window_id) = batch.contract_window(&contract).await? else {
+                warn!(
+                    "indexer: Market bet in block {} precedes any indexed window",
+                    block_number
+                );
+                return Ok(vec![]);
+            };
+            let address = format!("{:?}", selected_address);
+
+            if batch
+                .market_index(window_id, kind, &address, param)
+                .await?
+                .is_none()
+            {
+                let market = market_service::decode(kind, param)?;
+                batch
+                    .insert_market(window_id, kind, param, &address, &market, block_number)
+                    .await?;
+            }
+            let (market_index, _) = batch
+                .market_index(window_id, kind, &address, param)
+                .await?
+                .ok_or_else(|| anyhow!("Market of {} was not recorded", address))?;
+
+            batch
+                .insert_market_bet(
+                    window_id,
+                    market_index,
+                    &format!("{:?}", bettor),
+                    outcome,
+                    amount,
+                    &position,
+                )
+                .await?;
+
+            Ok(vec![])
+        }
+        AddrLoggerEvents::MarketSettledFilter(MarketSettledFilter {
+            kind,
+            selected_address,
+            param,
+            outcome,
+        }) => {
+            let address = format!("{:?}", selected_address);
+            let market = match batch.contract_window(&contract).await? {
+                Some(window_id) => batch
+                    .market_index(window_id, kind, &address, param)
+                    .await?
+                    .map(|market| (window_id, market)),
+                None => None,
+            };
+            // The contract settles markets with bets only
+            let Some((window_id, (market_index, outcomes))) = market else {
+                warn!(
+                    "indexer: Settled market of {} in block {} was never bet on",
+                    address, block_number
+                );
+                return Ok(vec![]);
+            };
+
+            let bets = batch.market_stakes(window_id, market_index).await?;
+            let payouts = market_service::settle(&bets, outcomes as u64, outcome);
+            batch
+                .settle_market(window_id, market_index, outcome, &payouts, &position)
+                .await?;
+            info!(
+                "indexer: Market {} of window {} settled on outcome {}",
+                market_index, window_id, outcome
+            );
+
+            Ok(vec![])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::models::{BetFilterParams, BetMarket};
+    use crate::repository::SqliteRepository;
+    use crate::services::bet_history_service::{self, BetScope};
+    use ethers::types::{H256, U64};
+
+    fn log_at(block: u64, log_index: u64) -> Log {
+        Log {
+            block_number: Some(U64::from(block)),
+            transaction_hash: Some(H256::from_low_u64_be(block * 100 + log_index)),
+            log_index: Some(U256::from(log_index)),
+            ..Default::default()
+        }
+    }
+
+    fn bet(selected: Address, position: bool, amount: u64) -> AddrLoggerEvents {
+        AddrLoggerEvents::BetPlacedFilter(BetPlacedFilter {
+            bettor: Address::repeat_byte(0xbb),
+            selected_address: selected,
+            position,
+            amount: U256::from(amount),
+        })
+    }
+
+    #[tokio::test]
+    async fn indexes_window_bets_and_rolls_back() {
+        let path = env::temp_dir().join(format!("indexer-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = init_db(&format!("sqlite:{}", path.display()))
+            .await
+            .unwrap();
+        let repository: DynRepository = Arc::new(SqliteRepository::new(pool.clone()));
+        let indexer = EventIndexerService::new(
+            repository.clone(),
+            "http://localhost:8545",
+            &format!("{:?}", Address::repeat_byte(0x01)),
+            IndexerConfig::from_env(),
+            LiveFeed::new(),
+        )
+        .unwrap();
+        let (a, b) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xab));
+
+        let started = AddrLoggerEvents::WindowStartedFilter(WindowStartedFilter {
+            operator: Address::repeat_byte(0x02),
+            valid_addresses: vec![a, b],
+            timestamp: U256::from(1_700_000_000u64),
+        });
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        for (log, event) in [
+            (log_at(10, 0), started),
+            (log_at(11, 0), bet(a, true, 90)),
+            (log_at(11, 1), bet(a, false, 45)),
+            (log_at(12, 0), bet(a, true, 9)),
+        ] {
+            apply_event(batch.as_mut(), repository.as_ref(), &log, event)
+                .await
+                .unwrap();
+        }
+        batch.commit().await.unwrap();
+
+        let window = indexer.latest_window(1).await.unwrap().unwrap();
+        assert_eq!(window.id, 1);
+        assert_eq!(
+            window.valid_addresses.0,
+            vec![format!("{:?}", a), format!("{:?}", b)]
+        );
+        assert_eq!(indexer.bet_count(1).await.unwrap(), 3);
+        assert_eq!(indexer.bet(1, 1).await.unwrap().unwrap().amount, "45");
+        assert_eq!(
+            indexer.pool_amounts(1, &format!("{:?}", a)).await.unwrap(),
+            (U256::from(99), U256::from(45))
+        );
+
+        indexer.rollback(11).await.unwrap();
+        assert_eq!(indexer.bet_count(1).await.unwrap(), 2);
+        assert_eq!(repository.indexer_cursor().await.unwrap(), Some(11));
+
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        let payout = AddrLoggerEvents::PayoutProcessedFilter(PayoutProcessedFilter {
+            bettor: Address::repeat_byte(0xbb),
+            amount: U256::from(135),
+            is_winner: true,
+        });
+        apply_event(batch.as_mut(), repository.as_ref(), &log_at(13, 0), payout)
+            .await
+            .unwrap();
+        settle_window(batch.as_mut(), 1).await.unwrap();
+        batch.commit().await.unwrap();
+
+        let won = indexer.bet(1, 0).await.unwrap().unwrap();
+        assert_eq!(
+            (won.outcome.as_str(), won.payout),
+            ("won", Some("135".into()))
+        );
+        assert_eq!(indexer.bet(1, 1).await.unwrap().unwrap().outcome, "lost");
+        assert_eq!(
+            indexer.window(1).await.unwrap().unwrap().settled_block,
+            Some(13)
+        );
+
+        indexer.rollback(12).await.unwrap();
+        assert_eq!(indexer.bet(1, 0).await.unwrap().unwrap().outcome, "pending");
+        assert!(indexer
+            .window(1)
+            .await
+            .unwrap()
+            .unwrap()
+            .settle_tx
+            .is_none());
+
+        indexer.rollback(9).await.unwrap();
+        assert!(indexer.latest_window(1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn indexes_and_settles_markets() {
+        let path = env::temp_dir().join(format!("indexer-markets-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = init_db(&format!("sqlite:{}", path.display()))
+            .await
+            .unwrap();
+        let repository: DynRepository = Arc::new(SqliteRepository::new(pool.clone()));
+        let indexer = EventIndexerService::new(
+            repository.clone(),
+            "http://localhost:8545",
+            &format!("{:?}", Address::repeat_byte(0x01)),
+            IndexerConfig::from_env(),
+            LiveFeed::new(),
+        )
+        .unwrap();
+        let (a, b) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xab));
+        let quartiles = BetMarket::RankBucket { buckets: 4 };
+        let (kind, param) = market_service::encode(&quartiles).unwrap().unwrap();
+        let market_bet = |bettor: u8, outcome: u8, amount: u64| {
+            AddrLoggerEvents::MarketBetPlacedFilter(MarketBetPlacedFilter {
+                bettor: Address::repeat_byte(bettor),
+                kind,
+                selected_address: a,
+                param,
+                outcome,
+                amount: U256::from(amount),
+            })
+        };
+
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        let events = [
+            AddrLoggerEvents::WindowStartedFilter(WindowStartedFilter {
+                operator: Address::repeat_byte(0x02),
+                valid_addresses: vec![a, b],
+                timestamp: U256::from(1_700_000_000u64),
+            }),
+            market_bet(0xb1, 1, 90),
+            market_bet(0xb2, 0, 45),
+            bet(a, true, 10),
+            market_bet(0xb3, 3, 15),
+            AddrLoggerEvents::MarketSettledFilter(MarketSettledFilter {
+                kind,
+                selected_address: a,
+                param,
+                outcome: 1,
+            }),
+        ];
+        for (block, event) in events.into_iter().enumerate() {
+            apply_event(
+                batch.as_mut(),
+                repository.as_ref(),
+                &log_at(10 + block as u64, 0),
+                event,
+            )
+            .await
+            .unwrap();
+        }
+        // `processMarketPayouts` pays out in the transaction that settles the market
+        let payout = AddrLoggerEvents::PayoutProcessedFilter(PayoutProcessedFilter {
+            bettor: Address::repeat_byte(0xb1),
+            amount: U256::from(150),
+            is_winner: true,
+        });
+        let log = Log {
+            transaction_hash: log_at(15, 0).transaction_hash,
+            ..log_at(15, 1)
+        };
+        apply_event(batch.as_mut(), repository.as_ref(), &log, payout)
+            .await
+            .unwrap();
+        batch.commit().await.unwrap();
+
+        let markets = indexer.markets(1).await.unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].market.0, quartiles);
+        assert_eq!(markets[0].pools, vec!["45", "90", "0", "15"]);
+        assert_eq!(markets[0].winning_outcome, Some(1));
+        let (outcome, payout) = sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT outcome, payout FROM market_bets WHERE bet_index = 0",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((outcome.as_str(), payout), ("won", Some("150".into())));
+        // The market's payout is not taken for an up/down payout, so the one-sided bet is lost
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        settle_window(batch.as_mut(), 1).await.unwrap();
+        batch.commit().await.unwrap();
+        assert_eq!(indexer.bet(1, 0).await.unwrap().unwrap().outcome, "lost");
+
+        // Market bets show up in the bet history next to up/down bets
+        let mut filters = BetFilterParams {
+            page: None,
+            per_page: None,
+            window_id: None,
+            selected_address: None,
+            position: None,
+            outcome: None,
+        };
+        let winner = format!("{:?}", Address::repeat_byte(0xb1));
+        let page = bet_history_service::find_bets(
+            repository.as_ref(),
+            BetScope::Player(&winner),
+            &filters,
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.total, 1);
+        let won = &page.bets[0];
+        assert_eq!(won.selected_address, format!("{:?}", a));
+        assert_eq!(
+            (won.position, won.market_index, won.market_outcome),
+            (None, Some(0), Some(1))
+        );
+        assert_eq!(
+            (won.outcome.as_str(), won.payout.as_deref()),
+            ("won", Some("150"))
+        );
+        let stats = bet_history_service::player_stats(repository.as_ref(), &winner)
+            .await
+            .unwrap();
+        assert_eq!((stats.wins, stats.losses), (1, 0));
+        assert_eq!(
+            (stats.volume.as_str(), stats.total_payout.as_str()),
+            ("90", "150")
+        );
+        let window_bets =
+            bet_history_service::find_bets(repository.as_ref(), BetScope::Window(1), &filters)
+                .await
+                .unwrap();
+        assert_eq!(window_bets.total, 4);
+        filters.outcome = Some(OUTCOME_WON.to_string());
+        let won_bets =
+            bet_history_service::find_bets(repository.as_ref(), BetScope::Window(1), &filters)
+                .await
+                .unwrap();
+        assert_eq!(won_bets.total, 1);
+
+        indexer.rollback(14).await.unwrap();
+        let markets = indexer.markets(1).await.unwrap();
+        assert_eq!(markets[0].pools, vec!["45", "90", "0", "15"]);
+        assert!(markets[0].winning_outcome.is_none());
+        indexer.rollback(11).await.unwrap();
+        assert_eq!(
+            indexer.markets(1).await.unwrap()[0].pools,
+            vec!["0", "90", "0", "0"]
+        );
+    }
+
+    // The window the prover and VerificationAndPrize tests settle
+    const JOURNAL_FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../solidity_contracts/verification/test/fixtures/top_half_journal.json"
+    );
+
+    #[tokio::test]
+    async fn hands_the_prover_the_markets_the_contract_settles() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(JOURNAL_FIXTURE).unwrap()).unwrap();
+        let path = env::temp_dir().join(format!("indexer-settlement-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let pool = init_db(&format!("sqlite:{}", path.display()))
+            .await
+            .unwrap();
+        let repository: DynRepository = Arc::new(SqliteRepository::new(pool));
+        let indexer = EventIndexerService::new(
+            repository.clone(),
+            "http://localhost:8545",
+            &format!("{:?}", Address::repeat_byte(0x01)),
+            IndexerConfig::from_env(),
+            LiveFeed::new(),
+        )
+        .unwrap();
+        let addresses: Vec<Address> = serde_json::from_value(fixture["addresses"].clone()).unwrap();
+        let results = fixture["market_results"].as_array().unwrap();
+
+        // A bet on each of the fixture's markets, in their order, then their settlement
+        let mut events = vec![AddrLoggerEvents::WindowStartedFilter(WindowStartedFilter {
+            operator: Address::repeat_byte(0x02),
+            valid_addresses: addresses.clone(),
+            timestamp: U256::from(1_700_000_000u64),
+        })];
+        let market = |result: &serde_json::Value| {
+            (
+                result["kind"].as_u64().unwrap() as u8,
+                result["selectedAddress"].as_str().unwrap().parse().unwrap(),
+                U256::from_dec_str(result["param"].as_str().unwrap()).unwrap(),
+                result["outcome"].as_u64().unwrap() as u8,
+            )
+        };
+        for result in results {
+            let (kind, selected_address, param, outcome) = market(result);
+            events.push(AddrLoggerEvents::MarketBetPlacedFilter(
+                MarketBetPlacedFilter {
+                    bettor: Address::repeat_byte(0xb1),
+                    kind,
+                    selected_address,
+                    param,
+                    outcome,
+                    amount: U256::from(90),
+                },
+            ));
+        }
+        events.push(bet(addresses[0], true, 10));
+        for result in results {
+            let (kind, selected_address, param, outcome) = market(result);
+            events.push(AddrLoggerEvents::MarketSettledFilter(MarketSettledFilter {
+                kind,
+                selected_address,
+                param,
+                outcome,
+            }));
+        }
+        let mut batch = repository.begin_chain_batch().await.unwrap();
+        for (block, event) in events.into_iter().enumerate() {
+            apply_event(
+                batch.as_mut(),
+                repository.as_ref(),
+                &log_at(10 + block as u64, 0),
+                event,
+            )
+            .await
+            .unwrap();
+        }
+        batch.commit().await.unwrap();
+
+        // The prover reads them as the fixture's markets, so the journal commits what the
+        // contract checks against the window
+        let markets = indexer.settlement_markets(1).await.unwrap();
+        assert_eq!(serde_json::to_value(&markets).unwrap(), fixture["markets"]);
+        let settled: Vec<Option<i64>> = indexer
+            .markets(1)
+            .await
+            .unwrap()
+            .iter()
+            .map(|market| market.winning_outcome)
+            .collect();
+        let outcomes: Vec<Option<i64>> = results
+            .iter()
+            .map(|result| result["outcome"].as_i64())
+            .collect();
+        assert_eq!(settled, outcomes);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::models::Leaderboard;
+use crate::repository::Repository;
+use anyhow::{anyhow, Result};
+use ethers::types::Address;
+
+pub const DEFAULT_LEADERBOARD: &str = "default";
+pub const DEFAULT_LEADERBOARD_ID: i64 = 1;
+
+const MAX_SLUG_LEN: usize = 64;
+
+/// Slugs double as the `game_id` of score submissions.
+pub fn validate_slug(slug: &str) -> Result<(), String> {
+    if slug.is_empty() || slug.len() > MAX_SLUG_LEN {
+        return Err(format!("Game id must be 1 to {} characters", MAX_SLUG_LEN));
+    }
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Game id may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+pub async fn create_leaderboard(
+    repository: &dyn Repository,
+    slug: &str,
+    name: &str,
+    contract_address: Option<&str>,
+) -> Result<Leaderboard> {
+    validate_slug(slug).map_err(|e| anyhow!(e))?;
+    let contract_address = contract_address
+        .map(|address| {
+            address
+                .parse::<Address>()
+                .map(|address| format!("{:?}", address))
+                .map_err(|e| anyhow!("Invalid contract address: {}", e))
+        })
+        .transpose()?;
+
+    repository
+        .create_leaderboard(slug, name.trim(), contract_address)
+        .await
+}
+
+/// Leaderboards with a dedicated AddressLogger contract, by contract address
+pub async fn contract_leaderboards(repository: &dyn Repository) -> Result<Vec<(Address, i64)>> {
+    let rows = repository.contract_leaderboards().await?;
+
+    rows.into_iter()
+        .map(|(address, id)| Ok((address.parse::<Address>()?, id)))
+        .collect()
+}
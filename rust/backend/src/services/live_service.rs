@@ -0,0 +1,230 @@
+use crate::models::{IndexedWindow, TrackedTransaction};
+use crate::services::payout_service;
+use actix_web::web::Bytes;
+use ethers::types::U256;
+use futures_util::{stream, Stream, StreamExt};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, time::Duration};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc::UnboundedSender,
+};
+
+/// Events buffered per subscriber before it starts missing updates
+const CHANNEL_CAPACITY: usize = 1024;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Up and down pools of one address with the decimal odds a unit stake on each side pays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolUpdate {
+    pub address: String,
+    pub up_amount: String,
+    pub down_amount: String,
+    pub up_odds: Option<f64>,
+    pub down_odds: Option<f64>,
+}
+
+impl PoolUpdate {
+    pub fn new(address: String, up: U256, down: U256) -> Self {
+        let (up_odds, down_odds) = payout_service::implied_odds(up, down);
+        Self {
+            address,
+            up_amount: up.to_string(),
+            down_amount: down.to_string(),
+            up_odds,
+            down_odds,
+        }
+    }
+}
+
+/// Winning side of an address once a window is settled, `None` when its pool was one-sided
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressResult {
+    pub address: String,
+    pub winning_position: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// Sent once when a client connects
+    Snapshot {
+        window: Option<IndexedWindow>,
+        pools: Vec<PoolUpdate>,
+    },
+    WindowStarted {
+        window_id: i64,
+        leaderboard_id: i64,
+        addresses: Vec<String>,
+        block_number: i64,
+    },
+    WindowClosed {
+        window_id: i64,
+        block_number: i64,
+    },
+    BetPlaced {
+        window_id: i64,
+        bet_index: i64,
+        bettor: String,
+        selected_address: String,
+        position: bool,
+        amount: String,
+        block_number: i64,
+    },
+    Pools {
+        window_id: i64,
+        pool: PoolUpdate,
+    },
+    WindowSettled {
+        window_id: i64,
+        results: Vec<AddressResult>,
+        block_number: i64,
+    },
+    /// Indexed state after this block was discarded, clients should reload
+    Reorg {
+        ancestor: i64,
+    },
+    /// Status change of a transaction submitted by this backend
+    Transaction {
+        transaction: TrackedTransaction,
+    },
+}
+
+impl LiveEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::Snapshot { .. } => "snapshot",
+            LiveEvent::WindowStarted { .. } => "window_started",
+            LiveEvent::WindowClosed { .. } => "window_closed",
+            LiveEvent::BetPlaced { .. } => "bet_placed",
+            LiveEvent::Pools { .. } => "pools",
+            LiveEvent::WindowSettled { .. } => "window_settled",
+            LiveEvent::Reorg { .. } => "reorg",
+            LiveEvent::Transaction { .. } => "transaction",
+        }
+    }
+
+    /// Encode as a Server-Sent Events frame named after the event type.
+    pub fn to_sse(&self) -> Bytes {
+        let data = serde_json::to_string(self).unwrap_or_else(|e| {
+            warn!("live: Failed to serialize {} event: {}", self.name(), e);
+            "{}".to_string()
+        });
+        Bytes::from(format!("event: {}\ndata: {}\n\n", self.name(), data))
+    }
+}
+
+/// Fan-out of indexer and transaction updates to connected clients.
+#[derive(Clone)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveEvent>,
+    /// Hands published events over to the other replicas
+    relay: Option<UnboundedSender<LiveEvent>>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            relay: None,
+        }
+    }
+
+    /// A feed whose events also go to the clients of the other replicas through `relay`
+    pub fn relayed(relay: UnboundedSender<LiveEvent>) -> Self {
+        Self {
+            relay: Some(relay),
+            ..Self::new()
+        }
+    }
+
+    pub fn publish(&self, event: LiveEvent) {
+        if let Some(relay) = &self.relay {
+            let _ = relay.send(event.clone());
+        }
+        self.publish_local(event);
+    }
+
+    /// Publish to the clients of this replica only, for events relayed from another one
+    pub fn publish_local(&self, event: LiveEvent) {
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(event);
+    }
+
+    pub fn publish_all(&self, events: impl IntoIterator<Item = LiveEvent>) {
+        for event in events {
+            self.publish(event);
+        }
+    }
+
+    /// SSE body starting with `snapshot`, followed by live events and keep-alive comments.
+    pub fn sse_stream(
+        &self,
+        snapshot: LiveEvent,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> + 'static {
+        let receiver = self.sender.subscribe();
+        let keep_alive = tokio::time::interval_at(
+            tokio::time::Instant::now() + KEEP_ALIVE_INTERVAL,
+            KEEP_ALIVE_INTERVAL,
+        );
+
+        let first = stream::once(async move { Ok(snapshot.to_sse()) });
+        let rest = stream::unfold(
+            (receiver, keep_alive),
+            |(mut receiver, mut keep_alive)| async move {
+                let frame = tokio::select! {
+                    received = receiver.recv() => match received {
+                        Ok(event) => event.to_sse(),
+                        // The client fell behind and has to reload its state
+                        Err(RecvError::Lagged(missed)) => Bytes::from(format!(
+                            "event: resync\ndata: {{\"missed\":{}}}\n\n",
+                            missed
+                        )),
+                        Err(RecvError::Closed) => return None,
+                    },
+                    _ = keep_alive.tick() => Bytes::from_static(b": keep-alive\n\n"),
+                };
+                Some((Ok(frame), (receiver, keep_alive)))
+            },
+        );
+
+        first.chain(rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn streams_snapshot_then_published_events() {
+        let feed = LiveFeed::new();
+        let stream = feed.sse_stream(LiveEvent::Snapshot {
+            window: None,
+            pools: vec![],
+        });
+        futures_util::pin_mut!(stream);
+
+        feed.publish(LiveEvent::Pools {
+            window_id: 1,
+            pool: PoolUpdate::new("0xaa".to_string(), U256::from(90), U256::from(45)),
+        });
+
+        let snapshot = stream.next().await.unwrap().unwrap();
+        assert!(snapshot.starts_with(b"event: snapshot\ndata: {\"type\":\"snapshot\""));
+
+        let pools = stream.next().await.unwrap().unwrap();
+        let pools = std::str::from_utf8(&pools).unwrap();
+        assert!(pools.starts_with("event: pools\n"));
+        assert!(pools.contains("\"up_odds\":1.5"));
+        assert!(pools.contains("\"down_odds\":3.0"));
+    }
+}
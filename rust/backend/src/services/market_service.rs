@@ -0,0 +1,193 @@
+use crate::models::{BetMarket, SettlementMarket};
+use crate::services::payout_service;
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, U256};
+
+// Market kinds of `placeMarketBet`, `top_half` bets go through `placeBet`
+pub const MARKET_TOP_K: u8 = 1;
+pub const MARKET_RANK_BUCKET: u8 = 2;
+pub const MARKET_HEAD_TO_HEAD: u8 = 3;
+pub const MARKET_OVER_UNDER: u8 = 4;
+
+/// Bucket counts the contract accepts for rank bucket markets
+pub const RANK_BUCKET_COUNTS: [u64; 2] = [4, 10];
+
+/// Contract kind and param of a market, `None` for `top_half`
+pub fn encode(market: &BetMarket) -> Result<Option<(u8, U256)>> {
+    Ok(match market {
+        BetMarket::TopHalf => None,
+        BetMarket::TopK { k } => Some((MARKET_TOP_K, U256::from(*k))),
+        BetMarket::RankBucket { buckets } => {
+            if !RANK_BUCKET_COUNTS.contains(buckets) {
+                return Err(anyhow!("A rank bucket market has 4 or 10 buckets"));
+            }
+            Some((MARKET_RANK_BUCKET, U256::from(*buckets)))
+        }
+        BetMarket::HeadToHead { opponent } => {
+            let opponent = opponent.parse::<Address>()?;
+            Some((
+                MARKET_HEAD_TO_HEAD,
+                U256::from_big_endian(opponent.as_bytes()),
+            ))
+        }
+        BetMarket::OverUnder { threshold } => Some((MARKET_OVER_UNDER, U256::from(*threshold))),
+    })
+}
+
+/// Market of a `MarketBetPlaced` or `MarketSettled` event
+pub fn decode(kind: u8, param: U256) -> Result<BetMarket> {
+    let small = || {
+        (param <= U256::from(u64::MAX))
+            .then(|| param.as_u64())
+            .ok_or_else(|| anyhow!("Market param {} is out of range", param))
+    };
+    Ok(match kind {
+        MARKET_TOP_K => BetMarket::TopK { k: small()? },
+        MARKET_RANK_BUCKET => BetMarket::RankBucket { buckets: small()? },
+        MARKET_HEAD_TO_HEAD => {
+            let mut bytes = [0u8; 32];
+            param.to_big_endian(&mut bytes);
+            BetMarket::HeadToHead {
+                opponent: format!("{:?}", Address::from_slice(&bytes[12..])),
+            }
+        }
+        MARKET_OVER_UNDER => BetMarket::OverUnder {
+            threshold: small()?,
+        },
+        _ => return Err(anyhow!("Unknown market kind {}", kind)),
+    })
+}
+
+/// A market on `address` as the prover settles it, `None` for `top_half`
+pub fn settlement_market(address: &str, market: &BetMarket) -> Option<SettlementMarket> {
+    let address = address.to_string();
+    Some(match market.clone() {
+        BetMarket::TopHalf => return None,
+        BetMarket::TopK { k } => SettlementMarket::TopK { address, k },
+        BetMarket::RankBucket { buckets } => SettlementMarket::RankBucket { address, buckets },
+        BetMarket::HeadToHead { opponent } => SettlementMarket::HeadToHead { address, opponent },
+        BetMarket::OverUnder { threshold } => SettlementMarket::OverUnder { address, threshold },
+    })
+}
+
+/// Number of outcomes of a market, yes/no markets have 0 (no) and 1 (yes)
+pub fn outcomes(market: &BetMarket) -> u64 {
+    match market {
+        BetMarket::RankBucket { buckets } => *buckets,
+        _ => 2,
+    }
+}
+
+/// Outcome a bet takes: `position` on yes/no markets, `bucket` on rank buckets
+pub fn bet_outcome(market: &BetMarket, position: Option<bool>, bucket: Option<u8>) -> Result<u8> {
+    match (market, position, bucket) {
+        (BetMarket::RankBucket { buckets }, None, Some(bucket)) => {
+            if u64::from(bucket) >= *buckets {
+                return Err(anyhow!("Bucket {} of {} does not exist", bucket, buckets));
+            }
+            Ok(bucket)
+        }
+        (BetMarket::RankBucket { .. }, _, _) => Err(anyhow!("A rank bucket bet takes a bucket")),
+        (_, Some(position), None) => Ok(position as u8),
+        _ => Err(anyhow!("A yes/no bet takes a position")),
+    }
+}
+
+/// A bet on a market, in the order it was placed
+#[derive(Debug, Clone)]
+pub struct MarketStake {
+    pub outcome: u8,
+    /// Stake after the fee, as emitted in `MarketBetPlaced`
+    pub amount: U256,
+}
+
+/// Amount bet on each outcome
+pub fn pools(bets: &[MarketStake], outcomes: u64) -> Vec<U256> {
+    let mut pools = vec![U256::zero(); outcomes as usize];
+    for bet in bets {
+        if let Some(pool) = pools.get_mut(bet.outcome as usize) {
+            *pool += bet.amount;
+        }
+    }
+    pools
+}
+
+/// Payout of each bet once `winner` won, `None` for lost bets. Winning bets share the other
+/// outcomes' pools like `processMarketPayouts`, a market nobody won or lost pays nothing back.
+pub fn settle(bets: &[MarketStake], outcomes: u64, winner: u8) -> Vec<Option<U256>> {
+    let pools = pools(bets, outcomes);
+    let total = pools.iter().fold(U256::zero(), |total, pool| total + pool);
+    let winning_pool = pools.get(winner as usize).copied().unwrap_or_default();
+    let losing_pool = total - winning_pool;
+    let one_sided = winning_pool.is_zero() || losing_pool.is_zero();
+
+    bets.iter()
+        .map(|bet| {
+            (bet.outcome == winner && !one_sided)
+                .then(|| payout_service::winning_payout(bet.amount, winning_pool, losing_pool))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stake(outcome: u8, amount: u64) -> MarketStake {
+        MarketStake {
+            outcome,
+            amount: U256::from(amount),
+        }
+    }
+
+    #[test]
+    fn test_markets_round_trip_through_the_contract_encoding() {
+        let opponent = format!("{:?}", Address::repeat_byte(0xab));
+        for market in [
+            BetMarket::TopK { k: 3 },
+            BetMarket::RankBucket { buckets: 10 },
+            BetMarket::HeadToHead { opponent },
+            BetMarket::OverUnder { threshold: 750 },
+        ] {
+            let (kind, param) = encode(&market).unwrap().unwrap();
+            assert_eq!(decode(kind, param).unwrap(), market);
+        }
+        assert!(encode(&BetMarket::TopHalf).unwrap().is_none());
+        assert!(encode(&BetMarket::RankBucket { buckets: 5 }).is_err());
+        assert!(decode(MARKET_TOP_K, U256::MAX).is_err());
+
+        let quartiles = BetMarket::RankBucket { buckets: 4 };
+        assert_eq!(bet_outcome(&quartiles, None, Some(3)).unwrap(), 3);
+        assert!(bet_outcome(&quartiles, None, Some(4)).is_err());
+        assert!(bet_outcome(&quartiles, Some(true), None).is_err());
+        assert_eq!(
+            bet_outcome(&BetMarket::TopK { k: 2 }, Some(true), None).unwrap(),
+            1
+        );
+        assert!(bet_outcome(&BetMarket::TopK { k: 2 }, None, Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_settle_shares_the_losing_buckets() {
+        // Bucket 1 wins 45 + 15 from buckets 0 and 3
+        let bets = vec![stake(1, 90), stake(0, 45), stake(1, 45), stake(3, 15)];
+        assert_eq!(
+            pools(&bets, 4),
+            vec![
+                U256::from(45),
+                U256::from(135),
+                U256::zero(),
+                U256::from(15)
+            ]
+        );
+        // proportion = 90 * 1e6 / 135 = 666666, winnings = 60 * 666666 / 1e6 = 39
+        assert_eq!(
+            settle(&bets, 4, 1),
+            vec![Some(U256::from(129)), None, Some(U256::from(64)), None]
+        );
+
+        // Nobody picked bucket 2, the pools go to the treasury
+        assert_eq!(settle(&bets, 4, 2), vec![None; 4]);
+        assert_eq!(settle(&bets[..1], 2, 1), vec![None]);
+    }
+}
@@ -1,5 +1,19 @@
 pub mod addr_logger_contract_service;
 pub mod address_service;
+pub mod bet_history_service;
 pub mod betting_token_service;
+pub mod draw_service;
+pub mod export_service;
 pub mod hash_contract_service;
 pub mod hash_service;
+pub mod history_service;
+pub mod import_service;
+pub mod indexer_service;
+pub mod leaderboard_service;
+pub mod live_service;
+pub mod market_service;
+pub mod payout_service;
+pub mod ranking_service;
+pub mod score_service;
+pub mod selection_service;
+pub mod tx_tracker_service;
@@ -0,0 +1,225 @@
+use ethers::types::U256;
+
+/// Percentage of every bet sent to the treasury by `place_bet`
+pub const FEE_PERCENTAGE: u64 = 10;
+/// Scale used by `process_payouts` when computing a bet's share of the winning pool
+pub const PROPORTION_SCALE: u64 = 1_000_000;
+
+/// A bet as seen by the payout logic, in the order it was placed
+#[derive(Debug, Clone)]
+pub struct PoolBet {
+    pub bettor: String,
+    pub selected_address: String,
+    pub position: bool,
+    /// Stake after the fee, as emitted in `BetPlaced`
+    pub amount: U256,
+}
+
+/// Fee and net stake of a bet amount, as split by `place_bet`
+pub fn split_fee(amount: U256) -> (U256, U256) {
+    let fee = (amount * U256::from(FEE_PERCENTAGE)) / U256::from(100);
+    (fee, amount - fee)
+}
+
+/// Outcome of a prospective bet against the current pools of an address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BetQuote {
+    pub fee: U256,
+    pub net_stake: U256,
+    pub up_after: U256,
+    pub down_after: U256,
+    /// Share of the winning pool in units of `PROPORTION_SCALE`
+    pub proportion: U256,
+    /// Paid if the position wins and nobody else bets, zero while the other side is empty
+    pub potential_payout: U256,
+}
+
+/// Quote a bet of `amount` (before the fee) as `place_bet` and `process_payouts` would settle it.
+pub fn quote(amount: U256, position: bool, up: U256, down: U256) -> BetQuote {
+    let (fee, net_stake) = split_fee(amount);
+    let (up_after, down_after) = if position {
+        (up + net_stake, down)
+    } else {
+        (up, down + net_stake)
+    };
+    let (winning_pool, losing_pool) = if position {
+        (up_after, down_after)
+    } else {
+        (down_after, up_after)
+    };
+
+    let proportion = if winning_pool.is_zero() {
+        U256::zero()
+    } else {
+        (net_stake * U256::from(PROPORTION_SCALE)) / winning_pool
+    };
+    // One-sided pools are sent to the treasury
+    let potential_payout = if losing_pool.is_zero() {
+        U256::zero()
+    } else {
+        winning_payout(net_stake, winning_pool, losing_pool)
+    };
+
+    BetQuote {
+        fee,
+        net_stake,
+        up_after,
+        down_after,
+        proportion,
+        potential_payout,
+    }
+}
+
+/// Total transferred to a winning bet, mirroring the contract's integer arithmetic
+pub fn winning_payout(stake: U256, winning_pool: U256, losing_pool: U256) -> U256 {
+    if winning_pool.is_zero() {
+        return stake;
+    }
+    let proportion = (stake * U256::from(PROPORTION_SCALE)) / winning_pool;
+    let winnings = (losing_pool * proportion) / U256::from(PROPORTION_SCALE);
+    stake + winnings
+}
+
+/// Decimal odds of each side, the total returned per unit staked if that side wins.
+/// `None` while a side is empty, a one-sided pool pays nothing.
+pub fn implied_odds(up: U256, down: U256) -> (Option<f64>, Option<f64>) {
+    let (up, down) = (to_f64(up), to_f64(down));
+    if up == 0.0 || down == 0.0 {
+        return (None, None);
+    }
+    (Some((up + down) / up), Some((up + down) / down))
+}
+
+/// Lossy conversion for display values such as odds
+pub fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or_default()
+}
+
+/// Up and down pools of one address
+pub fn pools(bets: &[PoolBet], address: &str) -> (U256, U256) {
+    bets.iter()
+        .filter(|bet| bet.selected_address == address)
+        .fold((U256::zero(), U256::zero()), |(up, down), bet| {
+            if bet.position {
+                (up + bet.amount, down)
+            } else {
+                (up, down + bet.amount)
+            }
+        })
+}
+
+/// Recover the winning side of every address from the `PayoutProcessed` events of a settlement.
+///
+/// The contract walks the addresses in window order and pays the winning bets of each one
+/// in the order they were placed, skipping addresses where one side has no bets. Returns
+/// `None` for such one-sided addresses, or `None` overall when the payouts don't fit the bets.
+pub fn infer_winners(
+    addresses: &[String],
+    bets: &[PoolBet],
+    payouts: &[(String, U256)],
+) -> Option<Vec<Option<bool>>> {
+    let mut remaining = payouts;
+    let mut winners = Vec::with_capacity(addresses.len());
+
+    for address in addresses {
+        let (up, down) = pools(bets, address);
+        if up.is_zero() || down.is_zero() {
+            winners.push(None);
+            continue;
+        }
+
+        let expected = |side: bool| -> Vec<(String, U256)> {
+            let (winning_pool, losing_pool) = if side { (up, down) } else { (down, up) };
+            bets.iter()
+                .filter(|bet| bet.selected_address == *address && bet.position == side)
+                .map(|bet| {
+                    (
+                        bet.bettor.clone(),
+                        winning_payout(bet.amount, winning_pool, losing_pool),
+                    )
+                })
+                .collect()
+        };
+
+        let (side, run) = [true, false]
+            .into_iter()
+            .map(|side| (side, expected(side)))
+            .find(|(_, run)| remaining.starts_with(run))?;
+        remaining = &remaining[run.len()..];
+        winners.push(Some(side));
+    }
+
+    remaining.is_empty().then_some(winners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bet(bettor: &str, address: &str, position: bool, amount: u64) -> PoolBet {
+        PoolBet {
+            bettor: bettor.to_string(),
+            selected_address: address.to_string(),
+            position,
+            amount: U256::from(amount),
+        }
+    }
+
+    #[test]
+    fn test_winning_payout_matches_contract_rounding() {
+        // 90 of 135 on the winning side, 45 losing
+        let payout = winning_payout(U256::from(90), U256::from(135), U256::from(45));
+        // proportion = 90 * 1e6 / 135 = 666666, winnings = 45 * 666666 / 1e6 = 29
+        assert_eq!(payout, U256::from(119));
+    }
+
+    #[test]
+    fn test_quote_splits_fee_and_joins_the_pool() {
+        let quote = quote(U256::from(100), true, U256::from(45), U256::from(45));
+        assert_eq!(quote.fee, U256::from(10));
+        assert_eq!(quote.net_stake, U256::from(90));
+        assert_eq!(
+            (quote.up_after, quote.down_after),
+            (U256::from(135), U256::from(45))
+        );
+        // 90 of the 135 up pool, the same bet the settlement test pays 119 for
+        assert_eq!(quote.proportion, U256::from(666_666));
+        assert_eq!(quote.potential_payout, U256::from(119));
+        assert_eq!(
+            quote.potential_payout,
+            winning_payout(quote.net_stake, quote.up_after, quote.down_after)
+        );
+    }
+
+    #[test]
+    fn test_quote_on_one_sided_pool_pays_nothing() {
+        let quote = quote(U256::from(55), false, U256::zero(), U256::from(10));
+        // Fee rounds down like the contract: 55 * 10 / 100 = 5
+        assert_eq!(quote.fee, U256::from(5));
+        assert_eq!(quote.down_after, U256::from(60));
+        assert_eq!(quote.potential_payout, U256::zero());
+    }
+
+    #[test]
+    fn test_infer_winners_from_payout_order() {
+        let addresses = vec!["0xa".to_string(), "0xb".to_string(), "0xc".to_string()];
+        let bets = vec![
+            bet("alice", "0xa", true, 90),
+            bet("bob", "0xa", false, 45),
+            bet("carol", "0xb", true, 10),
+            bet("alice", "0xc", false, 30),
+            bet("bob", "0xc", true, 60),
+        ];
+        // 0xa: up wins, 0xb: one-sided, 0xc: down wins
+        let payouts = vec![
+            ("alice".to_string(), U256::from(135)),
+            ("alice".to_string(), U256::from(90)),
+        ];
+
+        assert_eq!(
+            infer_winners(&addresses, &bets, &payouts),
+            Some(vec![Some(true), None, Some(false)])
+        );
+        assert_eq!(infer_winners(&addresses, &bets, &payouts[..1]), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::models::{PlayerRank, RankedScore, RankingScope, ScoreQuartiles};
+use crate::repository::Repository;
+use anyhow::Result;
+
+/// Most players returned by one ranking query
+pub const MAX_RANKING_LIMIT: u32 = 1000;
+
+/// The highest `limit` scores
+pub async fn top(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    limit: u32,
+) -> Result<Vec<RankedScore>> {
+    ranked_page(repository, scope, 0, limit).await
+}
+
+/// Players at positions `rank - radius` to `rank + radius` in leaderboard order
+pub async fn around(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    rank: u64,
+    radius: u32,
+) -> Result<Vec<RankedScore>> {
+    let offset = rank.saturating_sub(1).saturating_sub(radius as u64);
+    let limit = radius.saturating_mul(2).saturating_add(1);
+
+    ranked_page(repository, scope, offset, limit).await
+}
+
+async fn ranked_page(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    offset: u64,
+    limit: u32,
+) -> Result<Vec<RankedScore>> {
+    let scores = repository.ranked_scores(scope, offset, limit).await?;
+    let Some(first) = scores.first() else {
+        return Ok(Vec::new());
+    };
+
+    // The first player may tie with players on the previous page
+    let counts = repository.count_scores(scope, first.score).await?;
+    let values: Vec<i64> = scores.iter().map(|score| score.score as i64).collect();
+    let ranks = ranking::page_ranks(ranking::rank(counts.higher), offset + 1, &values);
+
+    Ok(scores
+        .into_iter()
+        .zip(ranks)
+        .map(|(score, rank)| RankedScore {
+            rank,
+            address: score.address,
+            score: score.score,
+        })
+        .collect())
+}
+
+/// Rank and percentile of a player, `None` when the player has no score
+pub async fn player_rank(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    address: &str,
+) -> Result<Option<PlayerRank>> {
+    let Some(score) = repository.player_score(scope, address).await? else {
+        return Ok(None);
+    };
+    let counts = repository.count_scores(scope, score).await?;
+
+    Ok(Some(PlayerRank {
+        address: address.to_string(),
+        score,
+        rank: ranking::rank(counts.higher),
+        percentile: ranking::percentile(counts.higher, counts.equal, counts.total),
+        tied: counts.equal,
+        total: counts.total,
+    }))
+}
+
+pub async fn quartiles(repository: &dyn Repository, scope: RankingScope) -> Result<ScoreQuartiles> {
+    let count = repository.count_scores(scope, 0).await?.total;
+
+    Ok(ScoreQuartiles {
+        count,
+        min: quantile(repository, scope, count, 0, 1).await?,
+        q1: quantile(repository, scope, count, 1, 4).await?,
+        median: quantile(repository, scope, count, 1, 2).await?,
+        q3: quantile(repository, scope, count, 3, 4).await?,
+        max: quantile(repository, scope, count, 1, 1).await?,
+    })
+}
+
+/// The `numerator / denominator` quantile of the `count` scores of a scope
+pub async fn quantile(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    count: u64,
+    numerator: u64,
+    denominator: u64,
+) -> Result<Option<u32>> {
+    let Some((lower, upper)) = ranking::quantile_offsets(count, numerator, denominator) else {
+        return Ok(None);
+    };
+    let lower = repository.score_at(scope, lower).await?;
+    let upper = repository.score_at(scope, upper).await?;
+
+    Ok(lower
+        .zip(upper)
+        .map(|(lower, upper)| ranking::midpoint(lower as i64, upper as i64) as u32))
+}
@@ -0,0 +1,157 @@
+use crate::config::env_or;
+use crate::models::{GameServer, ScoreSubmission, ScoreSubmissionResult};
+use crate::repository::{CheckedSubmission, Repository};
+use crate::services::leaderboard_service;
+use anyhow::{anyhow, Result};
+use ethers::{
+    core::k256::ecdsa::VerifyingKey,
+    types::{Address, Signature},
+    utils::public_key_to_address,
+};
+use serde::Serialize;
+use std::{env, str::FromStr};
+
+pub const SUBMISSION_INSERTED: &str = "inserted";
+pub const SUBMISSION_UPDATED: &str = "updated";
+pub const SUBMISSION_DUPLICATE: &str = "duplicate";
+pub const SUBMISSION_STALE: &str = "stale";
+pub const SUBMISSION_REJECTED: &str = "rejected";
+pub const SUBMISSION_REPLAYED: &str = "replayed";
+
+#[derive(Debug, Clone)]
+pub struct ScoreConfig {
+    /// Seconds a submission timestamp may be ahead of the server clock
+    pub max_future_secs: i64,
+    /// Highest score a game server may submit
+    pub max_score: u32,
+    pub max_batch_size: usize,
+    /// Allow seeding random addresses through `POST /addresses/generate`
+    pub enable_seeding: bool,
+    /// Token expected in `X-Admin-Token` to manage game servers, disabled when unset
+    pub admin_token: Option<String>,
+}
+
+impl ScoreConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_future_secs: env_or("SCORE_MAX_FUTURE_SECS", 300),
+            max_score: env_or("SCORE_MAX", u32::MAX),
+            max_batch_size: env_or("SCORE_MAX_BATCH_SIZE", 1000),
+            enable_seeding: env_or("ENABLE_SEEDING", false),
+            admin_token: env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty()),
+        }
+    }
+}
+
+/// Fields a game server signs, serialized in this order as compact JSON.
+#[derive(Serialize)]
+struct CanonicalScore<'a> {
+    address: String,
+    score: u32,
+    game_id: &'a str,
+    timestamp: i64,
+    nonce: u64,
+}
+
+/// The message a game server signs with EIP-191 `personal_sign` for a submission.
+pub fn canonical_message(submission: &ScoreSubmission) -> Result<String> {
+    Ok(serde_json::to_string(&CanonicalScore {
+        address: submission.address.to_lowercase(),
+        score: submission.score,
+        game_id: &submission.game_id,
+        timestamp: submission.timestamp,
+        nonce: submission.nonce,
+    })?)
+}
+
+/// Recover the address that signed a submission.
+pub fn recover_signer(submission: &ScoreSubmission) -> Result<Address> {
+    let signature = Signature::from_str(&submission.signature)
+        .map_err(|e| anyhow!("Invalid signature: {}", e))?;
+    let message = canonical_message(submission)?;
+
+    signature
+        .recover(message.as_str())
+        .map_err(|e| anyhow!("Invalid signature: {}", e))
+}
+
+/// Derive the Ethereum address of a SEC1 (33 or 65 bytes) or raw 64 byte secp256k1 public key.
+pub fn public_key_address(public_key: &str) -> Result<Address> {
+    let mut bytes = hex::decode(public_key.trim_start_matches("0x"))?;
+    if bytes.len() == 64 {
+        bytes.insert(0, 0x04);
+    }
+    let key =
+        VerifyingKey::from_sec1_bytes(&bytes).map_err(|e| anyhow!("Invalid public key: {}", e))?;
+
+    Ok(public_key_to_address(&key))
+}
+
+/// Check a submission and normalize its address, returning the reason it is rejected.
+pub fn validate(
+    submission: &ScoreSubmission,
+    config: &ScoreConfig,
+    now: i64,
+) -> Result<String, String> {
+    let address = submission
+        .address
+        .parse::<Address>()
+        .map_err(|_| "Invalid player address".to_string())?;
+
+    leaderboard_service::validate_slug(&submission.game_id)?;
+
+    if submission.score > config.max_score {
+        return Err(format!("Score exceeds {}", config.max_score));
+    }
+    if submission.timestamp <= 0 {
+        return Err("Invalid timestamp".to_string());
+    }
+    if submission.timestamp > now + config.max_future_secs {
+        return Err("Timestamp is in the future".to_string());
+    }
+    if submission.nonce > i64::MAX as u64 {
+        return Err("Nonce is too large".to_string());
+    }
+
+    Ok(format!("{:?}", address))
+}
+
+pub async fn register_game_server(
+    repository: &dyn Repository,
+    name: &str,
+    public_key: &str,
+) -> Result<GameServer> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Game server name is required"));
+    }
+    let address = public_key_address(public_key)?;
+
+    repository
+        .insert_game_server(
+            name.trim(),
+            &public_key.to_lowercase(),
+            &format!("{:?}", address),
+        )
+        .await
+}
+
+/// Verify and upsert a batch of signed scores. The game id names the leaderboard, where a
+/// player keeps one row replaced only by newer submissions. Each game server's nonces must increase, rejected and replayed
+/// submissions are written to `score_audit`.
+pub async fn submit_scores(
+    repository: &dyn Repository,
+    submissions: &[ScoreSubmission],
+    config: &ScoreConfig,
+) -> Result<Vec<ScoreSubmissionResult>> {
+    let now = chrono::Utc::now().timestamp();
+    let checked: Vec<CheckedSubmission> = submissions
+        .iter()
+        .map(|submission| CheckedSubmission {
+            submission,
+            signer: recover_signer(submission).map_err(|e| e.to_string()),
+            address: validate(submission, config, now),
+        })
+        .collect();
+
+    repository.store_submissions(&checked).await
+}
@@ -0,0 +1,163 @@
+use crate::models::{RankingScope, WindowSelection, WindowSelectionParams};
+use crate::repository::Repository;
+use crate::services::ranking_service;
+use anyhow::{anyhow, Result};
+use ethers::types::Address;
+use rand::{rng, seq::SliceRandom};
+use std::ops::Range;
+
+/// Players of a window when the request leaves the count out
+pub const DEFAULT_WINDOW_COUNT: u32 = 3;
+
+/// Read the selection of a window-start request
+pub fn parse_selection(params: &WindowSelectionParams) -> Result<WindowSelection> {
+    let count = params.count.unwrap_or(DEFAULT_WINDOW_COUNT);
+    let strategy = params.strategy.as_deref().unwrap_or("uniform");
+    if strategy != "explicit" && count == 0 {
+        return Err(anyhow!("A window needs at least one player"));
+    }
+
+    match strategy {
+        "uniform" => Ok(WindowSelection::Uniform { count }),
+        "balanced" => Ok(WindowSelection::Balanced { count }),
+        "stratified" => {
+            let bands = params
+                .bands
+                .ok_or_else(|| anyhow!("A stratified selection needs bands"))?;
+            if bands < 2 || bands > count {
+                return Err(anyhow!("bands must be between 2 and count"));
+            }
+            Ok(WindowSelection::Stratified { count, bands })
+        }
+        "explicit" => {
+            let mut addresses: Vec<String> = Vec::new();
+            for address in params.addresses.as_deref().unwrap_or_default().split(',') {
+                let address = address.trim();
+                if address.is_empty() {
+                    continue;
+                }
+                let address = address
+                    .parse::<Address>()
+                    .map_err(|_| anyhow!("Invalid player address {}", address))?;
+                let address = format!("{:?}", address);
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+            if addresses.is_empty() {
+                return Err(anyhow!("An explicit selection needs addresses"));
+            }
+            Ok(WindowSelection::Explicit { addresses })
+        }
+        _ => Err(anyhow!(
+            "Unknown strategy, expected uniform, balanced, stratified or explicit"
+        )),
+    }
+}
+
+/// Listed players without a score in the scope, an explicit selection may only name ranked
+/// players
+pub async fn unranked_players(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    addresses: &[String],
+) -> Result<Vec<String>> {
+    let mut unranked = Vec::new();
+    for address in addresses {
+        if repository.player_score(scope, address).await?.is_none() {
+            unranked.push(address.clone());
+        }
+    }
+    Ok(unranked)
+}
+
+/// Pick the players of a window. Balanced selections split at the median like the "top half"
+/// bets do, so players at the median count as the upper half.
+pub async fn select(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    selection: &WindowSelection,
+) -> Result<Vec<String>> {
+    let (count, bands) = match selection {
+        WindowSelection::Explicit { addresses } => return Ok(addresses.clone()),
+        selection => strategy_bands(selection).expect("sampled selections have bands"),
+    };
+
+    let mut candidates = Vec::new();
+    for band in score_bands(repository, scope, bands).await? {
+        candidates.push(repository.sample_band(scope, band, count).await?);
+    }
+
+    // The order of a window should not tell which band a player was drawn from
+    let mut players = allocate(count, candidates);
+    players.shuffle(&mut rng());
+    Ok(players)
+}
+
+/// Players and score bands a selection picks from, `None` for an explicit list
+pub fn strategy_bands(selection: &WindowSelection) -> Option<(u32, u32)> {
+    match selection {
+        WindowSelection::Uniform { count } => Some((*count, 1)),
+        WindowSelection::Balanced { count } => Some((*count, 2)),
+        WindowSelection::Stratified { count, bands } => Some((*count, *bands)),
+        WindowSelection::Explicit { .. } => None,
+    }
+}
+
+/// Take an even share of `count` from the candidates of each band, the upper bands taking the
+/// remainder. Bands with too few candidates leave their share to the next candidates of the
+/// others, upper bands first. Candidates are taken in order.
+pub fn allocate<T>(count: u32, candidates: Vec<Vec<T>>) -> Vec<T> {
+    let bands = candidates.len();
+    if bands == 0 {
+        return Vec::new();
+    }
+    let count = count as usize;
+    let shares: Vec<usize> = (0..bands)
+        .map(|i| count / bands + usize::from(i < count % bands))
+        .collect();
+
+    let mut taken: Vec<usize> = candidates
+        .iter()
+        .zip(&shares)
+        .map(|(band, share)| band.len().min(*share))
+        .collect();
+    let mut shortfall = count - taken.iter().sum::<usize>();
+    for (band, taken) in candidates.iter().zip(taken.iter_mut()) {
+        let more = (band.len() - *taken).min(shortfall);
+        *taken += more;
+        shortfall -= more;
+    }
+
+    candidates
+        .into_iter()
+        .zip(taken)
+        .flat_map(|(band, taken)| band.into_iter().take(taken))
+        .collect()
+}
+
+/// `bands` score ranges holding about equal numbers of players, highest first, none when the
+/// scope has no scores. Each starts at a quantile of the scope and ends where the next begins.
+pub async fn score_bands(
+    repository: &dyn Repository,
+    scope: RankingScope,
+    bands: u32,
+) -> Result<Vec<Range<i64>>> {
+    let count = repository.count_scores(scope, 0).await?.total;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut ranges = Vec::with_capacity(bands as usize);
+    let mut end = i64::MAX;
+    for band in (1..bands).rev() {
+        let start = ranking_service::quantile(repository, scope, count, band as u64, bands as u64)
+            .await?
+            .ok_or_else(|| anyhow!("No quantile of a scope with scores"))?
+            as i64;
+        ranges.push(start..end);
+        end = start;
+    }
+    ranges.push(i64::MIN..end);
+    Ok(ranges)
+}
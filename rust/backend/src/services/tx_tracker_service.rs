@@ -0,0 +1,274 @@
+use crate::config::env_or;
+use crate::models::TrackedTransaction;
+use crate::repository::{DynRepository, TransactionReceipt as ReceiptUpdate};
+use crate::services::live_service::{LiveEvent, LiveFeed};
+use anyhow::Result;
+use ethers::{
+    abi::AbiDecode,
+    providers::{Http, Middleware, Provider, RpcError},
+    types::{transaction::eip2718::TypedTransaction, BlockId, TransactionReceipt, H256, U64},
+};
+use log::{debug, error, info, warn};
+use std::{sync::Arc, time::Duration};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_MINED: &str = "mined";
+pub const STATUS_CONFIRMED: &str = "confirmed";
+pub const STATUS_REVERTED: &str = "reverted";
+pub const STATUS_DROPPED: &str = "dropped";
+
+// Selector of the standard `Error(string)` revert payload
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+#[derive(Debug, Clone)]
+pub struct TxTrackerConfig {
+    /// Blocks on top of the inclusion block before a transaction counts as confirmed
+    pub required_confirmations: u64,
+    pub poll_interval: Duration,
+    /// Seconds a transaction may be unknown to the node before it is marked dropped
+    pub drop_timeout_secs: i64,
+}
+
+impl TxTrackerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            required_confirmations: env_or("TX_CONFIRMATIONS", 3),
+            poll_interval: Duration::from_secs(env_or("TX_POLL_INTERVAL_SECS", 3)),
+            drop_timeout_secs: env_or("TX_DROP_TIMEOUT_SECS", 300),
+        }
+    }
+}
+
+/// Persists submitted transactions and follows them until they are final. Every replica
+/// tracks the transactions it sends, only the chain writer spawns the polling loop.
+#[derive(Clone)]
+pub struct TxTrackerService {
+    repository: DynRepository,
+    provider: Arc<Provider<Http>>,
+    config: TxTrackerConfig,
+    live: LiveFeed,
+}
+
+impl TxTrackerService {
+    pub fn new(
+        repository: DynRepository,
+        rpc_url: &str,
+        config: TxTrackerConfig,
+        live: LiveFeed,
+    ) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(rpc_url)?;
+
+        Ok(Self {
+            repository,
+            provider: Arc::new(provider),
+            config,
+            live,
+        })
+    }
+
+    /// Record a freshly submitted transaction as pending.
+    pub async fn track(&self, kind: &str, tx_hash: H256) -> Result<TrackedTransaction> {
+        let tx_hash = format!("{:#x}", tx_hash);
+        debug!("track: Tracking {} transaction {}", kind, tx_hash);
+
+        let tracked = self.repository.track_transaction(kind, &tx_hash).await?;
+        self.live.publish(LiveEvent::Transaction {
+            transaction: tracked.clone(),
+        });
+
+        Ok(tracked)
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<TrackedTransaction>> {
+        self.repository.transaction(id).await
+    }
+
+    /// Spawn the background loop that polls the node for open transactions.
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            info!(
+                "Transaction tracker started (confirmations={}, interval={:?})",
+                self.config.required_confirmations, self.config.poll_interval
+            );
+            let mut interval = tokio::time::interval(self.config.poll_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.poll().await {
+                    error!("tx_tracker: Poll failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn poll(&self) -> Result<()> {
+        let open = self
+            .repository
+            .open_transactions(self.config.required_confirmations)
+            .await?;
+
+        if open.is_empty() {
+            return Ok(());
+        }
+
+        let latest = self.provider.get_block_number().await?.as_u64();
+        for tx in open {
+            if let Err(e) = self.refresh(&tx, latest).await {
+                warn!("tx_tracker: Failed to refresh {}: {}", tx.tx_hash, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn refresh(&self, tx: &TrackedTransaction, latest: u64) -> Result<()> {
+        let hash = tx.tx_hash.parse::<H256>()?;
+
+        let receipt = match self.provider.get_transaction_receipt(hash).await? {
+            Some(receipt) => receipt,
+            None => return self.refresh_unmined(tx, hash).await,
+        };
+
+        let (Some(block_number), Some(block_hash)) = (receipt.block_number, receipt.block_hash)
+        else {
+            return Ok(());
+        };
+        let block_number = block_number.as_u64();
+
+        // The receipt may still point at a block that is no longer canonical
+        let canonical = self
+            .provider
+            .get_block(block_number)
+            .await?
+            .and_then(|block| block.hash);
+        if canonical != Some(block_hash) {
+            return self.mark_reorged(tx).await;
+        }
+
+        let block_hash = format!("{:#x}", block_hash);
+        let reorg_count = match &tx.block_hash {
+            Some(previous) if *previous != block_hash => tx.reorg_count + 1,
+            _ => tx.reorg_count,
+        };
+        let confirmations = latest.saturating_sub(block_number) + 1;
+
+        let (status, revert_reason) = if receipt.status == Some(U64::zero()) {
+            let reason = match &tx.revert_reason {
+                Some(reason) if tx.block_hash.as_deref() == Some(block_hash.as_str()) => {
+                    reason.clone()
+                }
+                _ => self.revert_reason(&receipt).await,
+            };
+            (STATUS_REVERTED, Some(reason))
+        } else if confirmations >= self.config.required_confirmations {
+            (STATUS_CONFIRMED, None)
+        } else {
+            (STATUS_MINED, None)
+        };
+
+        if status != tx.status {
+            info!("tx_tracker: {} {} -> {}", tx.kind, tx.tx_hash, status);
+        }
+
+        self.repository
+            .update_transaction(
+                tx.id,
+                &ReceiptUpdate {
+                    status,
+                    block_number,
+                    block_hash: &block_hash,
+                    confirmations,
+                    gas_used: receipt.gas_used.map(|gas| gas.to_string()),
+                    revert_reason,
+                    reorg_count,
+                },
+            )
+            .await?;
+
+        if status != tx.status || reorg_count != tx.reorg_count {
+            self.publish(tx.id).await?;
+        }
+        Ok(())
+    }
+
+    async fn refresh_unmined(&self, tx: &TrackedTransaction, hash: H256) -> Result<()> {
+        // A transaction that had a receipt and lost it was reorged out
+        if tx.block_hash.is_some() {
+            return self.mark_reorged(tx).await;
+        }
+
+        let age = chrono::Utc::now()
+            .signed_duration_since(tx.created_at)
+            .num_seconds();
+        if age < self.config.drop_timeout_secs
+            || self.provider.get_transaction(hash).await?.is_some()
+        {
+            return Ok(());
+        }
+
+        warn!("tx_tracker: {} {} was dropped", tx.kind, tx.tx_hash);
+        self.repository
+            .set_transaction_status(tx.id, STATUS_DROPPED)
+            .await?;
+
+        self.publish(tx.id).await
+    }
+
+    async fn mark_reorged(&self, tx: &TrackedTransaction) -> Result<()> {
+        warn!(
+            "tx_tracker: {} {} left block {:?} after a reorg",
+            tx.kind, tx.tx_hash, tx.block_hash
+        );
+        self.repository.reset_transaction(tx.id).await?;
+
+        self.publish(tx.id).await
+    }
+
+    async fn publish(&self, id: i64) -> Result<()> {
+        if let Some(transaction) = self.get(id).await? {
+            self.live.publish(LiveEvent::Transaction { transaction });
+        }
+        Ok(())
+    }
+
+    /// Replay a reverted transaction against its parent block to recover the revert data.
+    async fn revert_reason(&self, receipt: &TransactionReceipt) -> String {
+        let tx = match self
+            .provider
+            .get_transaction(receipt.transaction_hash)
+            .await
+        {
+            Ok(Some(tx)) => tx,
+            _ => return "unknown".to_string(),
+        };
+
+        let call: TypedTransaction = (&tx).into();
+        let parent = receipt
+            .block_number
+            .map(|n| BlockId::from(n.as_u64().saturating_sub(1)));
+
+        match self.provider.call(&call, parent).await {
+            Ok(_) => "unknown (replay succeeded)".to_string(),
+            Err(e) => match e.as_error_response() {
+                Some(rpc_error) => match rpc_error.as_revert_data() {
+                    Some(data) if !data.is_empty() => decode_revert_data(&data),
+                    _ => rpc_error.message.clone(),
+                },
+                None => e.to_string(),
+            },
+        }
+    }
+}
+
+/// Solidity contracts revert with `Error(string)`, the Stylus contracts with raw bytes.
+fn decode_revert_data(data: &[u8]) -> String {
+    if data.len() >= 4 && data[..4] == ERROR_STRING_SELECTOR {
+        if let Ok(reason) = String::decode(&data[4..]) {
+            return reason;
+        }
+    }
+
+    match std::str::from_utf8(data) {
+        Ok(reason) if !reason.chars().any(char::is_control) => reason.to_string(),
+        _ => format!("0x{}", hex::encode(data)),
+    }
+}
@@ -0,0 +1,468 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use ethers::{
+    middleware::{NonceManagerMiddleware, SignerMiddleware},
+    providers::{Http, Middleware, Provider, ProviderError},
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, BlockNumber, Bytes, Signature,
+    },
+    utils::rlp::Rlp,
+};
+use log::{info, warn};
+use std::{env, fmt, fs, path::PathBuf, sync::Arc};
+
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Client shared by all contract services. Nonces are assigned locally, so writes sent
+/// before the previous one is mined don't reuse its nonce.
+pub type SignerClient = NonceManagerMiddleware<SignerMiddleware<Provider<Http>, AppSigner>>;
+
+/// Where the backend's transaction signing key comes from, selected with `SIGNER`.
+#[derive(Debug, Clone)]
+pub enum SignerConfig {
+    /// Plaintext `PRIVATE_KEY`, only meant for local development
+    PrivateKey(String),
+    /// Encrypted JSON keystore unlocked with the password stored in a separate file
+    Keystore {
+        path: PathBuf,
+        password_file: PathBuf,
+    },
+    /// BIP-39 mnemonic read from a file, derived at the given path
+    Mnemonic {
+        phrase_file: PathBuf,
+        derivation_path: String,
+    },
+    /// External signer speaking `eth_accounts`, `eth_sign` and `eth_signTransaction`
+    Remote {
+        url: String,
+        address: Option<Address>,
+    },
+}
+
+impl SignerConfig {
+    pub fn from_env() -> Result<Self> {
+        fn required(name: &str) -> Result<String> {
+            env::var(name).map_err(|_| anyhow!("{} not set", name))
+        }
+
+        let kind = env::var("SIGNER").unwrap_or_else(|_| "private_key".to_string());
+        match kind.as_str() {
+            "private_key" => Ok(Self::PrivateKey(required("PRIVATE_KEY")?)),
+            "keystore" => Ok(Self::Keystore {
+                path: required("SIGNER_KEYSTORE_PATH")?.into(),
+                password_file: required("SIGNER_KEYSTORE_PASSWORD_FILE")?.into(),
+            }),
+            "mnemonic" => Ok(Self::Mnemonic {
+                phrase_file: required("SIGNER_MNEMONIC_FILE")?.into(),
+                derivation_path: env::var("SIGNER_DERIVATION_PATH")
+                    .unwrap_or_else(|_| DEFAULT_DERIVATION_PATH.to_string()),
+            }),
+            "remote" => Ok(Self::Remote {
+                url: required("SIGNER_REMOTE_URL")?,
+                address: env::var("SIGNER_REMOTE_ADDRESS")
+                    .ok()
+                    .map(|addr| addr.parse::<Address>())
+                    .transpose()
+                    .map_err(|e| anyhow!("Invalid SIGNER_REMOTE_ADDRESS: {}", e))?,
+            }),
+            other => Err(anyhow!(
+                "Unknown SIGNER '{}', expected private_key, keystore, mnemonic or remote",
+                other
+            )),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::PrivateKey(_) => "private_key",
+            Self::Keystore { .. } => "keystore",
+            Self::Mnemonic { .. } => "mnemonic",
+            Self::Remote { .. } => "remote",
+        }
+    }
+}
+
+/// Connect to the RPC endpoint and build the signing client for the configured backend.
+pub async fn connect(rpc_url: &str, config: &SignerConfig) -> Result<Arc<SignerClient>> {
+    let provider = Provider::<Http>::try_from(rpc_url)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+
+    let signer = AppSigner::from_config(config)
+        .await
+        .with_context(|| format!("Failed to load {} signer", config.kind()))?
+        .with_chain_id(chain_id);
+    info!(
+        "Using {} signer {:?} on chain {}",
+        config.kind(),
+        signer.address(),
+        chain_id
+    );
+
+    let address = signer.address();
+    let client = NonceManagerMiddleware::new(SignerMiddleware::new(provider, signer), address);
+    // Start after the transactions still pending from a previous run
+    client
+        .initialize_nonce(Some(BlockNumber::Pending.into()))
+        .await?;
+
+    Ok(Arc::new(client))
+}
+
+#[derive(Debug, Clone)]
+pub enum AppSigner {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+impl AppSigner {
+    pub async fn from_config(config: &SignerConfig) -> Result<Self> {
+        let signer = match config {
+            SignerConfig::PrivateKey(private_key) => {
+                warn!("Signing with a plaintext PRIVATE_KEY, use a keystore or remote signer in production");
+                Self::Local(private_key.parse::<LocalWallet>()?)
+            }
+            SignerConfig::Keystore {
+                path,
+                password_file,
+            } => {
+                let password = fs::read_to_string(password_file).with_context(|| {
+                    format!("Failed to read password file {}", password_file.display())
+                })?;
+                Self::Local(LocalWallet::decrypt_keystore(path, password.trim_end())?)
+            }
+            SignerConfig::Mnemonic {
+                phrase_file,
+                derivation_path,
+            } => Self::Local(
+                MnemonicBuilder::<English>::default()
+                    .phrase(phrase_file.clone())
+                    .derivation_path(derivation_path)?
+                    .build()?,
+            ),
+            SignerConfig::Remote { url, address } => {
+                Self::Remote(RemoteSigner::connect(url, *address).await?)
+            }
+        };
+
+        Ok(signer)
+    }
+}
+
+#[derive(Debug)]
+pub enum AppSignerError {
+    Wallet(WalletError),
+    Remote(ProviderError),
+    InvalidResponse(String),
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for AppSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wallet(e) => write!(f, "wallet error: {}", e),
+            Self::Remote(e) => write!(f, "remote signer error: {}", e),
+            Self::InvalidResponse(msg) => write!(f, "invalid remote signer response: {}", msg),
+            Self::Unsupported(what) => write!(f, "{} is not supported by this signer", what),
+        }
+    }
+}
+
+impl std::error::Error for AppSignerError {}
+
+#[async_trait]
+impl Signer for AppSigner {
+    type Error = AppSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_message(message)
+                .await
+                .map_err(AppSignerError::Wallet),
+            Self::Remote(remote) => remote.sign_message(message.as_ref()).await,
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_transaction(tx)
+                .await
+                .map_err(AppSignerError::Wallet),
+            Self::Remote(remote) => remote.sign_transaction(tx).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_typed_data(payload)
+                .await
+                .map_err(AppSignerError::Wallet),
+            Self::Remote(_) => Err(AppSignerError::Unsupported("EIP-712 signing")),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::Remote(remote) => remote.address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::Remote(remote) => remote.chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::Remote(remote) => Self::Remote(RemoteSigner {
+                chain_id: chain_id.into(),
+                ..remote
+            }),
+        }
+    }
+}
+
+/// Signer that keeps the key in an external process reachable over HTTP JSON-RPC.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    provider: Provider<Http>,
+    address: Address,
+    chain_id: u64,
+}
+
+impl RemoteSigner {
+    /// Without an explicit address the first account reported by the signer is used.
+    pub async fn connect(url: &str, address: Option<Address>) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(url)?;
+        let accounts: Vec<Address> = provider.request("eth_accounts", ()).await?;
+
+        let address = match address {
+            Some(address) if accounts.contains(&address) => address,
+            Some(address) => {
+                return Err(anyhow!("Remote signer does not manage {:?}", address));
+            }
+            None => *accounts
+                .first()
+                .ok_or_else(|| anyhow!("Remote signer reported no accounts"))?,
+        };
+
+        Ok(Self {
+            provider,
+            address,
+            chain_id: 1,
+        })
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, AppSignerError> {
+        let signature: Bytes = self
+            .provider
+            .request("eth_sign", (self.address, Bytes::from(message.to_vec())))
+            .await
+            .map_err(AppSignerError::Remote)?;
+
+        Signature::try_from(signature.as_ref())
+            .map_err(|e| AppSignerError::InvalidResponse(e.to_string()))
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, AppSignerError> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        let raw: Bytes = self
+            .provider
+            .request("eth_signTransaction", [&tx])
+            .await
+            .map_err(AppSignerError::Remote)?;
+
+        let (signed, signature) = TypedTransaction::decode_signed(&Rlp::new(raw.as_ref()))
+            .map_err(|e| AppSignerError::InvalidResponse(e.to_string()))?;
+        if signed.sighash() != tx.sighash() {
+            return Err(AppSignerError::InvalidResponse(
+                "signed transaction does not match the request".to_string(),
+            ));
+        }
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use ethers::types::{TransactionRequest, H256, U256, U64};
+    use serde_json::{json, Value};
+    use std::{net::TcpListener, sync::Mutex};
+
+    // Hardhat/Anvil default account
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+    const TEST_ADDRESS: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("backend-signer-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Minimal JSON-RPC signer backed by a local wallet
+    async fn stub_rpc(wallet: web::Data<LocalWallet>, body: web::Json<Value>) -> HttpResponse {
+        let params = &body["params"];
+        let result = match body["method"].as_str().unwrap() {
+            "eth_accounts" => json!([wallet.address()]),
+            "eth_sign" => {
+                let message: Bytes = serde_json::from_value(params[1].clone()).unwrap();
+                let signature = wallet.sign_message(message.as_ref()).await.unwrap();
+                json!(Bytes::from(signature.to_vec()))
+            }
+            "eth_signTransaction" => {
+                let tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+                let signature = wallet.sign_transaction_sync(&tx).unwrap();
+                json!(tx.rlp_signed(&signature))
+            }
+            method => panic!("unexpected method {}", method),
+        };
+
+        HttpResponse::Ok().json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+    }
+
+    /// Node that never mines, recording the nonces of the transactions sent to it
+    async fn stub_node(sent: web::Data<Mutex<Vec<U256>>>, body: web::Json<Value>) -> HttpResponse {
+        let result = match body["method"].as_str().unwrap() {
+            "eth_chainId" => json!(U64::from(421614u64)),
+            "eth_getTransactionCount" => json!(U256::from(7)),
+            "eth_sendRawTransaction" => {
+                let raw: Bytes = serde_json::from_value(body["params"][0].clone()).unwrap();
+                let (tx, _) = TypedTransaction::decode_signed(&Rlp::new(raw.as_ref())).unwrap();
+                sent.lock().unwrap().push(*tx.nonce().unwrap());
+                json!(H256::from(ethers::utils::keccak256(raw.as_ref())))
+            }
+            method => panic!("unexpected method {}", method),
+        };
+
+        HttpResponse::Ok().json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+    }
+
+    #[actix_web::test]
+    async fn consecutive_writes_get_their_own_nonces() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let sent = web::Data::new(Mutex::new(Vec::<U256>::new()));
+        let data = sent.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .route("/", web::post().to(stub_node))
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+        let config = SignerConfig::PrivateKey(hex::encode(wallet.signer().to_bytes()));
+        let client = connect(&url, &config).await.unwrap();
+        for _ in 0..2 {
+            let tx = TransactionRequest::new()
+                .to(Address::repeat_byte(0x11))
+                .gas(21_000u64)
+                .gas_price(1u64);
+            client.send_transaction(tx, None).await.unwrap();
+        }
+
+        assert_eq!(*sent.lock().unwrap(), [U256::from(7), U256::from(8)]);
+    }
+
+    #[tokio::test]
+    async fn mnemonic_signer_derives_configured_path() {
+        let dir = temp_dir("mnemonic");
+        let phrase_file = dir.join("phrase");
+        fs::write(&phrase_file, TEST_MNEMONIC).unwrap();
+
+        let signer = AppSigner::from_config(&SignerConfig::Mnemonic {
+            phrase_file,
+            derivation_path: DEFAULT_DERIVATION_PATH.to_string(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(signer.address(), TEST_ADDRESS.parse::<Address>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn keystore_signer_decrypts_with_password_file() {
+        let dir = temp_dir("keystore");
+        let (wallet, name) =
+            LocalWallet::new_keystore(&dir, &mut ethers::core::rand::thread_rng(), "hunter2", None)
+                .unwrap();
+        let password_file = dir.join("password");
+        fs::write(&password_file, "hunter2\n").unwrap();
+
+        let signer = AppSigner::from_config(&SignerConfig::Keystore {
+            path: dir.join(name),
+            password_file,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(signer.address(), wallet.address());
+    }
+
+    #[actix_web::test]
+    async fn remote_signer_signs_through_json_rpc() {
+        let wallet =
+            LocalWallet::new(&mut ethers::core::rand::thread_rng()).with_chain_id(421614u64);
+        let expected = wallet.address();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let data = web::Data::new(wallet);
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .route("/", web::post().to(stub_rpc))
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        let signer = AppSigner::from_config(&SignerConfig::Remote { url, address: None })
+            .await
+            .unwrap()
+            .with_chain_id(421614u64);
+        assert_eq!(signer.address(), expected);
+
+        let signature = signer.sign_message("zkLeaderboard").await.unwrap();
+        signature.verify("zkLeaderboard", expected).unwrap();
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .value(1_000u64)
+            .nonce(0u64)
+            .gas(21_000u64)
+            .gas_price(1u64)
+            .into();
+        let signature = signer.sign_transaction(&tx).await.unwrap();
+        let mut expected_tx = tx.clone();
+        expected_tx.set_from(expected);
+        expected_tx.set_chain_id(421614u64);
+        assert_eq!(signature.recover(expected_tx.sighash()).unwrap(), expected);
+    }
+}
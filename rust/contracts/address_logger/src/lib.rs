@@ -6,19 +6,23 @@ use alloy_sol_types::{
     sol,
     // sol_data::{Address as SOLAddress, FixedBytes as SolFixedBytes, *},
     // SolType,
+    SolValue,
 };
+use sha2::{Digest, Sha256};
 
 use stylus_sdk::{
-    // abi::Bytes,
-    alloy_primitives::{Address, U256},
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256, U8},
     block,
     call::Call,
     contract::address,
-    // crypto::keccak,
-    evm,
-    msg,
+    crypto::keccak,
+    evm, msg,
     prelude::*,
-    storage::{StorageAddress, StorageBool, StorageU256, StorageVec},
+    storage::{
+        StorageAddress, StorageBool, StorageFixedBytes, StorageMap, StorageU256, StorageU8,
+        StorageVec,
+    },
     ArbResult,
 };
 // type ECRECOVERType = (
@@ -29,6 +33,17 @@ use stylus_sdk::{
 // );
 
 const FEE_PERCENTAGE: u64 = 10;
+
+// Markets besides the up/down bets of `place_bet`, settled on the ranks among the window's
+// addresses. Yes/no markets have outcomes 0 (no) and 1 (yes).
+/// Address ranks in the top `param`
+const MARKET_TOP_K: u8 = 1;
+/// Rank bucket of the address with `param` (4 or 10) buckets, outcome 0 is the top bucket
+const MARKET_RANK_BUCKET: u8 = 2;
+/// Address outranks the address in the low 20 bytes of `param`
+const MARKET_HEAD_TO_HEAD: u8 = 3;
+/// Score of the address is over `param`
+const MARKET_OVER_UNDER: u8 = 4;
 // const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
 // const SIGNED_MESSAGE_HEAD: &'static str = "\x19Ethereum Signed Message:\n32";
 
@@ -41,6 +56,10 @@ sol_interface! {
         function allowance(address owner, address spender) external view returns (uint256);
     }
 
+    interface IRiscZeroVerifier {
+        function verify(bytes calldata seal, bytes32 imageId, bytes32 journalDigest) external view;
+    }
+
 }
 
 sol! {
@@ -67,10 +86,44 @@ sol! {
         uint256 amount,
         bool isWinner
     );
+
+    event MarketBetPlaced(
+        address indexed bettor,
+        uint8 kind,
+        address indexed selectedAddress,
+        uint256 param,
+        uint8 outcome,
+        uint256 amount
+    );
+
+    event MarketSettled(
+        uint8 kind,
+        address indexed selectedAddress,
+        uint256 param,
+        uint8 outcome
+    );
     // error EcrecoverCallError();
     // error InvalidSignatureLength();
 }
 
+sol! {
+    // Journal of the prover's top-half guest, ABI-encoded
+    struct MarketResult {
+        uint8 kind;
+        address selectedAddress;
+        uint256 param;
+        uint8 outcome;
+    }
+
+    struct TopHalfJournal {
+        bytes32 roundHash;
+        uint256 windowNumber;
+        address[] addresses;
+        bool[] winners;
+        MarketResult[] markets;
+    }
+}
+
 #[storage]
 #[entrypoint]
 pub struct AddressLogger {
@@ -84,6 +137,15 @@ pub struct AddressLogger {
     address_down_amounts: StorageVec<StorageU256>, // Total DOWN amounts per address (after fees)
     token_address: StorageAddress,
     // nonces: StorageMap<Address, StorageU256>,
+    window_number: StorageU256, // Keys the market maps to the current window
+    markets: StorageVec<Market>, // Markets of the current window, in order of their first bet
+    market_indices: StorageMap<FixedBytes<32>, StorageU256>, // Market key to index + 1
+    market_pools: StorageMap<FixedBytes<32>, StorageU256>, // Pool key to amount (after fees)
+    market_bets: StorageVec<MarketBet>,
+    verifier: StorageAddress, // RISC Zero verifier of `settle_with_proof`
+    image_id: StorageFixedBytes<32>, // Image ID of the prover's top-half guest
+    window_settled: StorageBool, // Up/down payouts of the current window were processed
+    round_hash: StorageFixedBytes<32>, // Anchored hash of the scores the current window settles on
 }
 
 // #[derive(SolidityError)]
@@ -100,6 +162,22 @@ pub struct Bet {
     amount: StorageU256,
 }
 
+#[storage]
+pub struct Market {
+    kind: StorageU8,
+    selected_address: StorageAddress,
+    param: StorageU256,
+    outcomes: StorageU8,
+}
+
+#[storage]
+pub struct MarketBet {
+    bettor: StorageAddress,
+    market: StorageU256,
+    outcome: StorageU8,
+    amount: StorageU256,
+}
+
 #[public]
 impl AddressLogger {
     pub fn init(
@@ -124,7 +202,13 @@ impl AddressLogger {
         Ok(Vec::new())
     }
 
-    pub fn start_betting_window(&mut self, addresses: Vec<Address>) -> ArbResult {
+    /// Start a window on `addresses`, `round_hash` being the anchored hash of the scores it
+    /// settles on, the one `settle_with_proof` requires the journal to commit
+    pub fn start_betting_window(
+        &mut self,
+        addresses: Vec<Address>,
+        round_hash: FixedBytes<32>,
+    ) -> ArbResult {
         // Only operator can start window
         if msg::sender() != self.operator.get() {
             return Err(Vec::from(b"Not authorized"));
@@ -135,6 +219,11 @@ impl AddressLogger {
             return Err(Vec::from(b"Window already active"));
         }
 
+        // Market bets of the previous window are paid out first
+        if !self.markets.is_empty() {
+            return Err(Vec::from(b"Markets not settled"));
+        }
+
         // Clear previous addresses and amounts, store new ones
         while self.valid_addresses.pop().is_some() {}
         while self.address_up_amounts.pop().is_some() {}
@@ -146,8 +235,14 @@ impl AddressLogger {
             self.address_down_amounts.push(U256::ZERO);
         }
 
+        // Markets are keyed by window, the previous window's keys are left behind
+        let window_number = self.window_number.get();
+        self.window_number.set(window_number + U256::from(1));
+
         // Activate window
+        self.round_hash.set(round_hash);
         self.window_active.set(true);
+        self.window_settled.set(false);
 
         // Emit event
         evm::log(WindowStarted {
@@ -331,22 +426,7 @@ impl AddressLogger {
             return Err(Vec::from(b"Invalid address selected"));
         }
 
-        let token = IERC20::new(self.token_address.get());
-
-        let allowance = token.allowance(Call::new_in(self), bettor, address())?;
-        if allowance < amount {
-            return Err(Vec::from(b"Insufficient allowance"));
-        }
-        // Calculate fee and bet amount
-        let fee_amount = (amount * U256::from(FEE_PERCENTAGE)) / U256::from(100);
-        let bet_amount = amount - fee_amount;
-        let treasury_addr = self.treasury.get();
-
-        // Transfer tokens from bettor to contract
-        token.transfer_from(Call::new_in(self), bettor, address(), amount)?;
-
-        // Transfer fee to treasury
-        token.transfer(Call::new_in(self), treasury_addr, fee_amount)?;
+        let bet_amount = self.take_stake(bettor, amount)?;
 
         // Store bet
         let mut new_bet = self.bets.grow();
@@ -392,6 +472,98 @@ impl AddressLogger {
 
         Ok(Vec::new())
     }
+    /// Bet on a market of the current window, opening it with the first bet
+    pub fn place_market_bet(
+        &mut self,
+        bettor: Address,
+        kind: u8,
+        selected_address: Address,
+        param: U256,
+        outcome: u8,
+        amount: U256,
+    ) -> ArbResult {
+        if !self.window_active.get() {
+            return Err(Vec::from(b"No active betting window"));
+        }
+        if !self.is_valid_address(selected_address) {
+            return Err(Vec::from(b"Invalid address selected"));
+        }
+
+        let outcomes = match kind {
+            MARKET_TOP_K => {
+                if param == U256::ZERO || param >= U256::from(self.valid_addresses.len()) {
+                    return Err(Vec::from(b"Invalid top K"));
+                }
+                2
+            }
+            MARKET_RANK_BUCKET => {
+                if param != U256::from(4) && param != U256::from(10) {
+                    return Err(Vec::from(b"Invalid bucket count"));
+                }
+                param.to::<u8>()
+            }
+            MARKET_HEAD_TO_HEAD => {
+                let opponent = opponent_address(param).ok_or(Vec::from(b"Invalid opponent"))?;
+                if opponent == selected_address || !self.is_valid_address(opponent) {
+                    return Err(Vec::from(b"Invalid opponent"));
+                }
+                2
+            }
+            MARKET_OVER_UNDER => 2,
+            _ => return Err(Vec::from(b"Unknown market")),
+        };
+        if outcome >= outcomes {
+            return Err(Vec::from(b"Invalid outcome"));
+        }
+
+        let bet_amount = self.take_stake(bettor, amount)?;
+
+        let market_key = self.market_key(kind, selected_address, param);
+        let mut market = self.market_indices.get(market_key);
+        if market == U256::ZERO {
+            let mut new_market = self.markets.grow();
+            new_market.kind.set(U8::from(kind));
+            new_market.selected_address.set(selected_address);
+            new_market.param.set(param);
+            new_market.outcomes.set(U8::from(outcomes));
+            market = U256::from(self.markets.len());
+            self.market_indices.insert(market_key, market);
+        }
+        let market = market - U256::from(1);
+
+        let pool_key = self.pool_key(market, outcome);
+        let pool = self.market_pools.get(pool_key);
+        self.market_pools.insert(pool_key, pool + bet_amount);
+
+        let mut new_bet = self.market_bets.grow();
+        new_bet.bettor.set(bettor);
+        new_bet.market.set(market);
+        new_bet.outcome.set(U8::from(outcome));
+        new_bet.amount.set(bet_amount);
+
+        evm::log(MarketBetPlaced {
+            bettor,
+            kind,
+            selectedAddress: selected_address,
+            param,
+            outcome,
+            amount: bet_amount,
+        });
+
+        Ok(Vec::new())
+    }
+
+    /// Settle every market of the window with its winning outcome, in market order. Winning
+    /// bets share the other outcomes' pools, markets nobody won or lost go to the treasury.
+    pub fn process_market_payouts(&mut self, outcomes: Vec<u8>) -> Result<(), Vec<u8>> {
+        let sender = msg::sender();
+        if sender != self.operator.get() && sender != self.authorized_contract.get() {
+            return Err(Vec::from(b"Not authorized"));
+        }
+
+        self.pay_out_markets(&outcomes)
+    }
+
     // Helper functions
     pub fn get_window_active(&self) -> bool {
         self.window_active.get()
@@ -422,6 +594,175 @@ impl AddressLogger {
             return Err(Vec::from(b"Not authorized"));
         }
 
+        self.pay_out(&winners)
+    }
+
+    /// Set the RISC Zero verifier and the image ID of the prover's top-half guest that
+    /// `settle_with_proof` accepts
+    pub fn set_verifier(&mut self, verifier: Address, image_id: FixedBytes<32>) -> ArbResult {
+        if msg::sender() != self.operator.get() {
+            return Err(Vec::from(b"Not authorized"));
+        }
+
+        self.verifier.set(verifier);
+        self.image_id.set(image_id);
+        Ok(Vec::new())
+    }
+
+    /// Settle a closed window with a Groth16 receipt of the top-half guest: `seal` is the
+    /// receipt's selector-prefixed seal and `journal` its ABI-encoded journal. The winners and
+    /// market outcomes are taken from the journal, so anyone can settle.
+    pub fn settle_with_proof(&mut self, seal: Bytes, journal: Bytes) -> Result<(), Vec<u8>> {
+        let verifier_address = self.verifier.get();
+        if verifier_address == Address::ZERO {
+            return Err(Vec::from(b"Verifier not set"));
+        }
+        let window = self.window()?;
+
+        // Reverts unless the seal proves the image committed the journal
+        let journal_digest = FixedBytes::<32>::from(<[u8; 32]>::from(Sha256::digest(&*journal)));
+        let image_id = self.image_id.get();
+        let (winners, outcomes) = settlement(&window, &journal, || {
+            let verifier = IRiscZeroVerifier::new(verifier_address);
+            verifier.verify(Call::new_in(self), seal, image_id, journal_digest)?;
+            Ok(())
+        })?;
+
+        self.pay_out(&winners)?;
+        self.pay_out_markets(&outcomes)
+    }
+
+    pub fn is_valid_address(&self, address: Address) -> bool {
+        for i in 0..self.valid_addresses.len() {
+            if let Some(addr) = self.valid_addresses.getter(i) {
+                if addr.get() == address {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn get_operator(&self) -> Address {
+        self.operator.get()
+    }
+
+    pub fn get_treasury(&self) -> Address {
+        self.treasury.get()
+    }
+
+    pub fn get_token(&self) -> Address {
+        self.token_address.get()
+    }
+    pub fn get_up_amount(&self, addr_index: U256) -> Result<U256, Vec<u8>> {
+        let idx = addr_index.as_limbs()[0] as usize;
+        if idx >= self.valid_addresses.len() {
+            return Err(Vec::from(b"Invalid address index"));
+        }
+        Ok(self.address_up_amounts.getter(addr_index).unwrap().get())
+    }
+
+    pub fn get_down_amount(&self, addr_index: U256) -> Result<U256, Vec<u8>> {
+        let idx = addr_index.as_limbs()[0] as usize;
+        if idx >= self.valid_addresses.len() {
+            return Err(Vec::from(b"Invalid address index"));
+        }
+        Ok(self.address_down_amounts.getter(addr_index).unwrap().get())
+    }
+
+    /// Anchored hash of the scores the current window settles on
+    pub fn get_round_hash(&self) -> FixedBytes<32> {
+        self.round_hash.get()
+    }
+
+    /// Number of the current window, counting from 1, which proofs commit to settle it
+    pub fn get_window_number(&self) -> U256 {
+        self.window_number.get()
+    }
+
+    pub fn get_valid_address_count(&self) -> U256 {
+        U256::from(self.valid_addresses.len())
+    }
+
+    /// Address of the current window at an index, the order `process_payouts` takes winners in
+    pub fn get_valid_address(&self, index: U256) -> Result<Address, Vec<u8>> {
+        let address = self
+            .valid_addresses
+            .getter(index)
+            .ok_or(Vec::from(b"Index out of bounds"))?;
+        Ok(address.get())
+    }
+
+    pub fn get_market_count(&self) -> U256 {
+        U256::from(self.markets.len())
+    }
+
+    /// Kind, address, param and number of outcomes of a market
+    pub fn get_market(&self, index: U256) -> Result<(u8, Address, U256, u8), Vec<u8>> {
+        let market = self
+            .markets
+            .getter(index)
+            .ok_or(Vec::from(b"Index out of bounds"))?;
+        Ok((
+            market.kind.get().to::<u8>(),
+            market.selected_address.get(),
+            market.param.get(),
+            market.outcomes.get().to::<u8>(),
+        ))
+    }
+
+    /// Amount (after fees) bet on one outcome of a market
+    pub fn get_market_pool(&self, index: U256, outcome: u8) -> Result<U256, Vec<u8>> {
+        if index >= U256::from(self.markets.len()) {
+            return Err(Vec::from(b"Index out of bounds"));
+        }
+        Ok(self.market_pools.get(self.pool_key(index, outcome)))
+    }
+
+    pub fn get_market_bet_count(&self) -> U256 {
+        U256::from(self.market_bets.len())
+    }
+
+    pub fn get_market_bet(&self, index: U256) -> Result<(Address, U256, u8, U256), Vec<u8>> {
+        let bet = self
+            .market_bets
+            .getter(index)
+            .ok_or(Vec::from(b"Index out of bounds"))?;
+        Ok((
+            bet.bettor.get(),
+            bet.market.get(),
+            bet.outcome.get().to::<u8>(),
+            bet.amount.get(),
+        ))
+    }
+}
+
+impl AddressLogger {
+    /// The current window as `settlement` checks a journal against it
+    fn window(&self) -> Result<Window, Vec<u8>> {
+        let mut markets = Vec::with_capacity(self.markets.len());
+        for i in 0..self.markets.len() {
+            let (kind, selected_address, param, _) = self.get_market(U256::from(i))?;
+            markets.push((kind, selected_address, param));
+        }
+        Ok(Window {
+            active: self.window_active.get(),
+            settled: self.window_settled.get(),
+            round_hash: self.round_hash.get(),
+            number: self.window_number.get(),
+            addresses: (0..self.valid_addresses.len())
+                .filter_map(|i| self.valid_addresses.get(i))
+                .collect(),
+            markets,
+        })
+    }
+
+    /// Pay out the up/down pools of the window's addresses, `winners` in their order
+    fn pay_out(&mut self, winners: &[bool]) -> Result<(), Vec<u8>> {
+        if self.window_settled.get() {
+            return Err(Vec::from(b"Window already settled"));
+        }
+
         // Validate winners array matches addresses
         if winners.len() != self.valid_addresses.len() {
             return Err(Vec::from(b"Invalid winners array length"));
@@ -480,45 +821,309 @@ impl AddressLogger {
         // Clear bets after processing
         let _len = self.bets.len();
         unsafe { self.bets.set_len(0) };
+        self.window_settled.set(true);
 
         Ok(())
     }
 
-    pub fn is_valid_address(&self, address: Address) -> bool {
-        for i in 0..self.valid_addresses.len() {
-            if let Some(addr) = self.valid_addresses.getter(i) {
-                if addr.get() == address {
-                    return true;
+    /// Pay out the window's markets, `outcomes` in market order
+    fn pay_out_markets(&mut self, outcomes: &[u8]) -> Result<(), Vec<u8>> {
+        // Bets can't be added to settled markets
+        if self.window_active.get() {
+            return Err(Vec::from(b"Window still active"));
+        }
+        if outcomes.len() != self.markets.len() {
+            return Err(Vec::from(b"Invalid outcomes array length"));
+        }
+
+        let token = IERC20::new(self.token_address.get());
+        let treasury_addr = self.treasury.get();
+
+        for i in 0..self.markets.len() {
+            let (kind, selected_address, param, market_outcomes) =
+                self.get_market(U256::from(i))?;
+            let winner = outcomes[i];
+            if winner >= market_outcomes {
+                return Err(Vec::from(b"Invalid outcome"));
+            }
+
+            let mut total = U256::ZERO;
+            for outcome in 0..market_outcomes {
+                total += self.market_pools.get(self.pool_key(U256::from(i), outcome));
+            }
+            let winning_pool = self.market_pools.get(self.pool_key(U256::from(i), winner));
+            let losing_pool = total - winning_pool;
+
+            evm::log(MarketSettled {
+                kind,
+                selectedAddress: selected_address,
+                param,
+                outcome: winner,
+            });
+
+            if winning_pool == U256::ZERO || losing_pool == U256::ZERO {
+                if total > U256::ZERO {
+                    token.transfer(Call::new_in(self), treasury_addr, total)?;
                 }
+                continue;
+            }
+
+            let mut payouts = Vec::new();
+            for j in 0..self.market_bets.len() {
+                if let Some(bet) = self.market_bets.getter(j) {
+                    if bet.market.get() == U256::from(i) && bet.outcome.get().to::<u8>() == winner {
+                        let proportion = (bet.amount.get() * U256::from(1000000)) / winning_pool;
+                        let winnings = (losing_pool * proportion) / U256::from(1000000);
+                        payouts.push((bet.bettor.get(), bet.amount.get() + winnings));
+                    }
+                }
+            }
+
+            for (bettor, amount) in payouts {
+                token.transfer(Call::new_in(self), bettor, amount)?;
+                evm::log(PayoutProcessed {
+                    bettor,
+                    amount,
+                    isWinner: true,
+                });
             }
         }
-        false
+
+        unsafe { self.market_bets.set_len(0) };
+        unsafe { self.markets.set_len(0) };
+
+        Ok(())
     }
 
-    pub fn get_operator(&self) -> Address {
-        self.operator.get()
+    /// Transfer a bet from the bettor and its fee to the treasury, returns the amount bet
+    fn take_stake(&mut self, bettor: Address, amount: U256) -> Result<U256, Vec<u8>> {
+        let token = IERC20::new(self.token_address.get());
+
+        let allowance = token.allowance(Call::new_in(self), bettor, address())?;
+        if allowance < amount {
+            return Err(Vec::from(b"Insufficient allowance"));
+        }
+        // Calculate fee and bet amount
+        let fee_amount = (amount * U256::from(FEE_PERCENTAGE)) / U256::from(100);
+        let bet_amount = amount - fee_amount;
+        let treasury_addr = self.treasury.get();
+
+        // Transfer tokens from bettor to contract
+        token.transfer_from(Call::new_in(self), bettor, address(), amount)?;
+
+        // Transfer fee to treasury
+        token.transfer(Call::new_in(self), treasury_addr, fee_amount)?;
+
+        Ok(bet_amount)
     }
 
-    pub fn get_treasury(&self) -> Address {
-        self.treasury.get()
+    fn market_key(&self, kind: u8, selected_address: Address, param: U256) -> FixedBytes<32> {
+        keccak(
+            [
+                &self.window_number.get().to_be_bytes::<32>()[..],
+                &[kind],
+                selected_address.as_slice(),
+                &param.to_be_bytes::<32>(),
+            ]
+            .concat(),
+        )
     }
 
-    pub fn get_token(&self) -> Address {
-        self.token_address.get()
+    fn pool_key(&self, market: U256, outcome: u8) -> FixedBytes<32> {
+        keccak(
+            [
+                &self.window_number.get().to_be_bytes::<32>()[..],
+                &market.to_be_bytes::<32>(),
+                &[outcome],
+            ]
+            .concat(),
+        )
     }
-    pub fn get_up_amount(&self, addr_index: U256) -> Result<U256, Vec<u8>> {
-        let idx = addr_index.as_limbs()[0] as usize;
-        if idx >= self.valid_addresses.len() {
-            return Err(Vec::from(b"Invalid address index"));
+}
+
+/// A window as stored by `start_betting_window` and the market bets placed in it
+struct Window {
+    active: bool,
+    settled: bool,
+    round_hash: FixedBytes<32>,
+    number: U256,
+    addresses: Vec<Address>,
+    /// Kind, address and param of each market, in market order
+    markets: Vec<(u8, Address, U256)>,
+}
+
+/// Winners and market outcomes of a journal settling `window`. `verify` errors unless the
+/// journal was proven. The journal must rank the round anchored when the window started, name
+/// the window, and list its addresses and markets in order, so a proof of another window can't
+/// be replayed on it.
+fn settlement(
+    window: &Window,
+    journal: &[u8],
+    verify: impl FnOnce() -> Result<(), Vec<u8>>,
+) -> Result<(Vec<bool>, Vec<u8>), Vec<u8>> {
+    if window.active {
+        return Err(Vec::from(b"Window still active"));
+    }
+    if window.settled {
+        return Err(Vec::from(b"Window already settled"));
+    }
+    verify()?;
+
+    let journal =
+        TopHalfJournal::abi_decode(journal, true).map_err(|_| Vec::from(b"Invalid journal"))?;
+    if journal.roundHash != window.round_hash {
+        return Err(Vec::from(b"Journal round does not match the window"));
+    }
+    if journal.windowNumber != window.number {
+        return Err(Vec::from(b"Journal window does not match the window"));
+    }
+    if journal.addresses != window.addresses {
+        return Err(Vec::from(b"Journal addresses do not match the window"));
+    }
+    if journal.markets.len() != window.markets.len() {
+        return Err(Vec::from(b"Journal markets do not match the window"));
+    }
+    let mut outcomes = Vec::with_capacity(journal.markets.len());
+    for (result, (kind, selected_address, param)) in journal.markets.iter().zip(&window.markets) {
+        if result.kind != *kind
+            || result.selectedAddress != *selected_address
+            || result.param != *param
+        {
+            return Err(Vec::from(b"Journal markets do not match the window"));
         }
-        Ok(self.address_up_amounts.getter(addr_index).unwrap().get())
+        outcomes.push(result.outcome);
     }
 
-    pub fn get_down_amount(&self, addr_index: U256) -> Result<U256, Vec<u8>> {
-        let idx = addr_index.as_limbs()[0] as usize;
-        if idx >= self.valid_addresses.len() {
-            return Err(Vec::from(b"Invalid address index"));
+    Ok((journal.winners, outcomes))
+}
+
+/// Opponent of a head-to-head market, `None` when `param` is wider than an address
+fn opponent_address(param: U256) -> Option<Address> {
+    let bytes = param.to_be_bytes::<32>();
+    if bytes[..12].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(Address::from_slice(&bytes[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> Window {
+        Window {
+            active: false,
+            settled: false,
+            round_hash: FixedBytes::repeat_byte(0xab),
+            number: U256::from(3),
+            addresses: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            markets: vec![(MARKET_TOP_K, Address::repeat_byte(0x22), U256::from(1))],
         }
-        Ok(self.address_down_amounts.getter(addr_index).unwrap().get())
+    }
+
+    fn journal(window: &Window) -> TopHalfJournal {
+        TopHalfJournal {
+            roundHash: window.round_hash,
+            windowNumber: window.number,
+            addresses: window.addresses.clone(),
+            winners: vec![true, false],
+            markets: vec![MarketResult {
+                kind: MARKET_TOP_K,
+                selectedAddress: Address::repeat_byte(0x22),
+                param: U256::from(1),
+                outcome: 0,
+            }],
+        }
+    }
+
+    fn settle(window: &Window, journal: &TopHalfJournal) -> Result<(Vec<bool>, Vec<u8>), String> {
+        settlement(window, &journal.abi_encode(), || Ok(()))
+            .map_err(|e| String::from_utf8(e).unwrap())
+    }
+
+    #[test]
+    fn settles_a_matching_journal() {
+        let window = window();
+        assert_eq!(
+            settle(&window, &journal(&window)),
+            Ok((vec![true, false], vec![0]))
+        );
+    }
+
+    #[test]
+    fn refuses_a_journal_of_another_round() {
+        let window = window();
+        let mut journal = journal(&window);
+        journal.roundHash = FixedBytes::repeat_byte(0xcd);
+        assert_eq!(
+            settle(&window, &journal),
+            Err("Journal round does not match the window".into())
+        );
+    }
+
+    #[test]
+    fn refuses_a_journal_of_a_past_window() {
+        let window = window();
+        let mut journal = journal(&window);
+        journal.windowNumber = U256::from(2);
+        assert_eq!(
+            settle(&window, &journal),
+            Err("Journal window does not match the window".into())
+        );
+    }
+
+    #[test]
+    fn refuses_addresses_out_of_the_window_order() {
+        let window = window();
+        let mut journal = journal(&window);
+        journal.addresses.reverse();
+        assert_eq!(
+            settle(&window, &journal),
+            Err("Journal addresses do not match the window".into())
+        );
+    }
+
+    #[test]
+    fn refuses_markets_that_are_not_the_window_markets() {
+        let window = window();
+        let mut journal = journal(&window);
+        journal.markets[0].param = U256::from(2);
+        assert_eq!(
+            settle(&window, &journal),
+            Err("Journal markets do not match the window".into())
+        );
+
+        journal.markets.clear();
+        assert_eq!(
+            settle(&window, &journal),
+            Err("Journal markets do not match the window".into())
+        );
+    }
+
+    #[test]
+    fn refuses_a_settled_window_before_verifying() {
+        let mut window = window();
+        window.settled = true;
+        let journal = journal(&window).abi_encode();
+        let result = settlement(&window, &journal, || panic!("Verified a settled window"));
+        assert_eq!(result, Err(Vec::from(b"Window already settled")));
+    }
+
+    #[test]
+    fn refuses_an_active_window() {
+        let mut window = window();
+        window.active = true;
+        assert_eq!(
+            settle(&window, &journal(&window)),
+            Err("Window still active".into())
+        );
+    }
+
+    #[test]
+    fn refuses_a_journal_the_verifier_rejects() {
+        let window = window();
+        let journal = journal(&window).abi_encode();
+        let result = settlement(&window, &journal, || Err(Vec::from(b"Invalid seal")));
+        assert_eq!(result, Err(Vec::from(b"Invalid seal")));
     }
 }
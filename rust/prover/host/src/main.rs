@@ -1,17 +1,25 @@
 use actix_cors::Cors;
 use actix_web::{web, App, HttpResponse, HttpServer, Result};
-use sqlx::SqlitePool;
+use alloy_sol_types::{sol, SolValue};
+use sqlx::AnyPool;
 
 // use anyhow::{Context, Result};
 use ciborium::into_writer;
 use hex::encode;
-use methods::{ZKLEADERBOARD_GUEST_ELF, ZKLEADERBOARD_GUEST_ID};
-use risc0_zkvm::{default_prover, ExecutorEnv};
+use methods::{
+    ZKLEADERBOARD_GUEST_ELF, ZKLEADERBOARD_GUEST_ID, ZKLEADERBOARD_HEAD_TO_HEAD_ELF,
+    ZKLEADERBOARD_HEAD_TO_HEAD_ID, ZKLEADERBOARD_PRIVATE_ELF, ZKLEADERBOARD_PRIVATE_ID,
+    ZKLEADERBOARD_RANK_BUCKET_ELF, ZKLEADERBOARD_RANK_BUCKET_ID,
+};
+use risc0_zkvm::{default_prover, ExecutorEnv, Groth16Receipt, ProverOpts, Receipt, ReceiptClaim};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::{collections::HashMap, env, fs};
 use tokio::sync::{mpsc, Mutex};
 
-// Configuration
+// Configuration, DATABASE_URL (sqlite: or postgres://, as for the backend) overrides DB_PATH
 const DB_PATH: &str = "/Users/nishantpandav/Documents/work/_startups/Blockchain Gods/game/hackathons/zkVerify hackathon 2/zkLeaderboard-app/rust/backend/data/addresses.db";
 const SERVER_ADDR: (&str, u16) = ("127.0.0.1", 8080);
 
@@ -20,24 +28,289 @@ pub struct AddressData {
     pub address: String,
     pub score: i64,
 }
-#[derive(Serialize, Deserialize)]
-struct Output {
+sol! {
+    /// A market and its winning outcome, as committed by the guest
+    struct MarketResult {
+        uint8 kind;
+        address selectedAddress;
+        uint256 param;
+        uint8 outcome;
+    }
+
+    /// The guest's ABI-encoded journal, which VerificationAndPrize decodes to pay out
+    struct TopHalfJournal {
+        bytes32 roundHash;
+        uint256 windowNumber;
+        address[] addresses;
+        bool[] winners;
+        MarketResult[] markets;
+    }
+}
+
+/// A market besides top half/bottom half, as the guest reads it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Market {
+    TopK { address: String, k: u64 },
+    RankBucket { address: String, buckets: u64 },
+    HeadToHead { address: String, opponent: String },
+    OverUnder { address: String, threshold: i64 },
+}
+
+impl Market {
+    /// The market with the winning outcome committed for it
+    fn output(&self, outcome: u8) -> MarketOutput {
+        match self.clone() {
+            Market::TopK { address, k } => MarketOutput::TopK {
+                address,
+                k,
+                in_top_k: outcome == 1,
+            },
+            Market::RankBucket { address, buckets } => MarketOutput::RankBucket {
+                address,
+                buckets,
+                bucket: outcome.into(),
+            },
+            Market::HeadToHead { address, opponent } => MarketOutput::HeadToHead {
+                address,
+                opponent,
+                outranks: outcome == 1,
+            },
+            Market::OverUnder { address, threshold } => MarketOutput::OverUnder {
+                address,
+                threshold,
+                over: outcome == 1,
+            },
+        }
+    }
+}
+
+/// Outcome of a market
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MarketOutput {
+    TopK {
+        address: String,
+        k: u64,
+        in_top_k: bool,
+    },
+    RankBucket {
+        address: String,
+        buckets: u64,
+        bucket: u64,
+    },
+    HeadToHead {
+        address: String,
+        opponent: String,
+        outranks: bool,
+    },
+    OverUnder {
+        address: String,
+        threshold: i64,
+        over: bool,
+    },
+}
+
+/// Guest program a job runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Method {
+    /// Whether each address is in the top half, and the outcome of markets on them
+    #[default]
+    TopHalf,
+    /// Rank and bucket of each address among the full leaderboard
+    RankBucket,
+    /// Which address of each pair has the higher score, run by `/check_pairs`
+    #[serde(skip_deserializing)]
+    HeadToHead,
+    /// Whether each player is in the top half, committed under a salted hash of their address
+    /// and score instead of the address
+    PrivateTopHalf,
+}
+
+impl Method {
+    fn elf(self) -> &'static [u8] {
+        match self {
+            Method::TopHalf => ZKLEADERBOARD_GUEST_ELF,
+            Method::RankBucket => ZKLEADERBOARD_RANK_BUCKET_ELF,
+            Method::HeadToHead => ZKLEADERBOARD_HEAD_TO_HEAD_ELF,
+            Method::PrivateTopHalf => ZKLEADERBOARD_PRIVATE_ELF,
+        }
+    }
+
+    fn image_id(self) -> String {
+        let id = match self {
+            Method::TopHalf => ZKLEADERBOARD_GUEST_ID,
+            Method::RankBucket => ZKLEADERBOARD_RANK_BUCKET_ID,
+            Method::HeadToHead => ZKLEADERBOARD_HEAD_TO_HEAD_ID,
+            Method::PrivateTopHalf => ZKLEADERBOARD_PRIVATE_ID,
+        };
+        let bytes = id
+            .into_iter()
+            .flat_map(|v| v.to_le_bytes().into_iter())
+            .collect::<Vec<_>>();
+        "0x".to_owned() + &hex::encode(bytes)
+    }
+}
+
+/// Bucket counts of the rank bucket method: quartiles and deciles
+const RANK_BUCKET_COUNTS: [u64; 2] = [4, 10];
+
+/// Rank and bucket of an address as committed by the rank bucket guest
+#[derive(Debug, Serialize, Deserialize)]
+struct RankOutput {
+    address: String,
+    rank: u64,
+    bucket: u64,
+}
+
+/// Two addresses to compare, as the head-to-head guest reads them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pair {
     address: String,
+    opponent: String,
+}
+
+/// A pair and the address with the higher score as committed by the head-to-head guest, no
+/// winner when they tie
+#[derive(Debug, Serialize, Deserialize)]
+struct PairOutput {
+    address: String,
+    opponent: String,
+    winner: Option<String>,
+}
+
+/// A score and the salt of its commitment, as the private guest reads it
+#[derive(Debug, Serialize)]
+struct SaltedScore {
+    address: String,
+    score: i64,
+    salt: [u8; 32],
+}
+
+/// Outcome of a player as committed by the private guest
+#[derive(Debug, Deserialize)]
+struct PrivateOutput {
+    identity: [u8; 32],
+    is_top_half: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PrivateResult {
+    identity: String,
     is_top_half: bool,
 }
 
+/// What a player needs to recompute their `identity` in a private job's journal
+#[derive(Debug, Clone, Serialize)]
+struct Opening {
+    address: String,
+    score: i64,
+    salt: String,
+    identity: String,
+}
+
+/// A player asking for their opening, signed with EIP-191 `personal_sign`
+#[derive(Debug, Deserialize)]
+struct OpeningRequest {
+    address: String,
+    signature: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct PositionRequest {
+    /// Guest program to run, `top_half` when omitted
+    #[serde(default)]
+    method: Method,
+    /// Buckets the `rank_bucket` method splits the leaderboard's ranks into, 4 or 10
+    buckets: Option<u64>,
     addresses: Vec<String>,
+    /// Leaderboard the addresses are ranked in, the backend's default one when omitted
+    leaderboard_id: Option<i64>,
+    /// Closed epoch to rank against, the window's `epoch_id`. Live scores when omitted
+    epoch_id: Option<i64>,
+    /// Rank against the live scores as they stood at this unix time in milliseconds, e.g. the
+    /// start of a past betting window, rebuilt from the backend's `score_events`
+    at: Option<i64>,
+    /// Window round whose frozen scores to rank against, refused unless they still match the
+    /// hash anchored when the window opened. Required by `top_half`, whose journal settles the
+    /// window
+    round_id: Option<i64>,
+    /// The contract's number of the window `addresses` settle, committed so a proof only pays
+    /// out that window. Required by `top_half`
+    window_number: Option<u64>,
+    /// Markets to settle on the ranks among `addresses`, in the contract's market order
+    #[serde(default)]
+    markets: Vec<Market>,
+    /// Wrap the receipt in Groth16 and return its `seal`, for `settle_with_proof`
+    #[serde(default)]
+    groth16: bool,
 }
 
-#[derive(Debug, Serialize)]
+/// Pairs to compare, scored in the same scopes as `PositionRequest`
+#[derive(Debug, Deserialize)]
+struct PairsRequest {
+    pairs: Vec<Pair>,
+    leaderboard_id: Option<i64>,
+    epoch_id: Option<i64>,
+    at: Option<i64>,
+    round_id: Option<i64>,
+}
+
+/// A queued proof request
+struct ProofJob {
+    job_id: String,
+    method: Method,
+    buckets: u64,
+    leaderboard_id: i64,
+    epoch_id: Option<i64>,
+    at: Option<i64>,
+    round_id: Option<i64>,
+    window_number: Option<u64>,
+    addresses: Vec<String>,
+    markets: Vec<Market>,
+    pairs: Vec<Pair>,
+    groth16: bool,
+}
+
+const DEFAULT_LEADERBOARD_ID: i64 = 1;
+
+#[derive(Debug, Default, Serialize)]
 struct JobStatus {
     status: String,
     proof: Option<String>,
     journal: Option<String>,
+    /// Selector-prefixed Groth16 seal of a `groth16` job, the `seal` the RISC Zero verifier
+    /// contracts take
+    seal: Option<String>,
+    /// Image ID of the guest the job ran
     image_id: Option<String>,
     results: Option<Vec<AddressResult>>,
+    market_results: Option<Vec<MarketOutput>>,
+    /// Ranks and buckets of the `rank_bucket` method
+    rank_results: Option<Vec<RankOutput>>,
+    buckets: Option<u64>,
+    /// Hash of the full leaderboard the ranks were taken from, as the backend anchors it
+    leaderboard_root: Option<String>,
+    /// Winners of the `/check_pairs` pairs
+    pair_results: Option<Vec<PairOutput>>,
+    /// Outcomes of the `private_top_half` method, by identity
+    private_results: Option<Vec<PrivateResult>>,
+    /// Openings of a private job, only handed to their player
+    #[serde(skip)]
+    openings: Vec<Opening>,
+    /// Why the job was refused, set when `status` is "failed"
+    error: Option<String>,
+}
+
+impl JobStatus {
+    fn failed(error: String) -> Self {
+        Self {
+            status: "failed".into(),
+            error: Some(error),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -47,8 +320,8 @@ struct AddressResult {
 }
 struct AppState {
     jobs: Mutex<HashMap<String, JobStatus>>,
-    tx: mpsc::Sender<(String, Vec<String>)>,
-    db_pool: SqlitePool,
+    tx: mpsc::Sender<ProofJob>,
+    db_pool: AnyPool,
 }
 
 mod handlers {
@@ -58,27 +331,133 @@ mod handlers {
         state: web::Data<AppState>,
         req: web::Json<PositionRequest>,
     ) -> Result<HttpResponse> {
-        let job_id = uuid::Uuid::new_v4().to_string();
+        check_scope(req.epoch_id, req.at, req.round_id)?;
+        let buckets = match (req.method, req.buckets) {
+            (Method::RankBucket, Some(buckets)) if RANK_BUCKET_COUNTS.contains(&buckets) => buckets,
+            (Method::RankBucket, _) => {
+                return Err(actix_web::error::ErrorBadRequest(
+                    "The rank_bucket method takes 4 or 10 buckets",
+                ))
+            }
+            (_, Some(_)) => {
+                return Err(actix_web::error::ErrorBadRequest(
+                    "buckets only applies to the rank_bucket method",
+                ))
+            }
+            (_, None) => 0,
+        };
+        // Payouts are only made on the scores anchored when the window opened
+        if req.method == Method::TopHalf && (req.round_id.is_none() || req.window_number.is_none())
+        {
+            return Err(actix_web::error::ErrorBadRequest(
+                "The top_half method settles a window and needs its round_id and window_number",
+            ));
+        }
+        if req.method != Method::TopHalf && req.window_number.is_some() {
+            return Err(actix_web::error::ErrorBadRequest(
+                "window_number only applies to the top_half method",
+            ));
+        }
+        if req.method != Method::TopHalf && !req.markets.is_empty() {
+            return Err(actix_web::error::ErrorBadRequest(
+                "Markets are settled by the top_half method",
+            ));
+        }
+        print!("Addresses:{:?}", req.addresses);
+        let job_id = queue(
+            &state,
+            ProofJob {
+                job_id: uuid::Uuid::new_v4().to_string(),
+                method: req.method,
+                buckets,
+                leaderboard_id: req.leaderboard_id.unwrap_or(DEFAULT_LEADERBOARD_ID),
+                epoch_id: req.epoch_id,
+                at: req.at,
+                round_id: req.round_id,
+                window_number: req.window_number,
+                addresses: req.addresses.clone(),
+                markets: req.markets.clone(),
+                pairs: Vec::new(),
+                groth16: req.groth16,
+            },
+        )
+        .await?;
+
+        Ok(HttpResponse::Ok().json(job_id))
+    }
+
+    /// Proves which address of each pair has the higher score without revealing the scores
+    pub async fn check_pairs(
+        state: web::Data<AppState>,
+        req: web::Json<PairsRequest>,
+    ) -> Result<HttpResponse> {
+        check_scope(req.epoch_id, req.at, req.round_id)?;
+        if req.pairs.is_empty() {
+            return Err(actix_web::error::ErrorBadRequest("No pairs to compare"));
+        }
+        if req.pairs.iter().any(|pair| pair.address == pair.opponent) {
+            return Err(actix_web::error::ErrorBadRequest(
+                "An address can't be paired with itself",
+            ));
+        }
 
-        let mut jobs = state.jobs.lock().await;
-        jobs.insert(
+        let mut addresses = Vec::new();
+        for pair in &req.pairs {
+            for address in [&pair.address, &pair.opponent] {
+                if !addresses.contains(address) {
+                    addresses.push(address.clone());
+                }
+            }
+        }
+        let job_id = queue(
+            &state,
+            ProofJob {
+                job_id: uuid::Uuid::new_v4().to_string(),
+                method: Method::HeadToHead,
+                buckets: 0,
+                leaderboard_id: req.leaderboard_id.unwrap_or(DEFAULT_LEADERBOARD_ID),
+                epoch_id: req.epoch_id,
+                at: req.at,
+                round_id: req.round_id,
+                window_number: None,
+                addresses,
+                markets: Vec::new(),
+                pairs: req.pairs.clone(),
+                groth16: false,
+            },
+        )
+        .await?;
+
+        Ok(HttpResponse::Ok().json(job_id))
+    }
+
+    fn check_scope(epoch_id: Option<i64>, at: Option<i64>, round_id: Option<i64>) -> Result<()> {
+        let scopes = [epoch_id.is_some(), at.is_some(), round_id.is_some()];
+        if scopes.into_iter().filter(|set| *set).count() > 1 {
+            return Err(actix_web::error::ErrorBadRequest(
+                "epoch_id, at and round_id can't be combined",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Record a pending job and send it to the prover, returning its ID
+    async fn queue(state: &AppState, job: ProofJob) -> Result<String> {
+        let job_id = job.job_id.clone();
+        state.jobs.lock().await.insert(
             job_id.clone(),
             JobStatus {
                 status: "pending".into(),
-                proof: None,
-                journal: None,
-                image_id: None,
-                results: None,
+                ..Default::default()
             },
         );
-        print!("Addresses:{:?}", req.addresses);
         state
             .tx
-            .send((job_id.clone(), req.addresses.clone()))
+            .send(job)
             .await
             .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
 
-        Ok(HttpResponse::Ok().json(job_id))
+        Ok(job_id)
     }
 
     pub async fn get_job_status(
@@ -91,87 +470,347 @@ mod handlers {
             None => HttpResponse::NotFound().finish(),
         }
     }
+
+    /// Hands a player the opening of their identity in a private job, once they sign
+    /// `opening_message(job_id)` with their address
+    pub async fn get_opening(
+        state: web::Data<AppState>,
+        job_id: web::Path<String>,
+        req: web::Json<OpeningRequest>,
+    ) -> Result<HttpResponse> {
+        let job_id = job_id.into_inner();
+        let signer = recover_signer(&opening_message(&job_id), &req.signature)
+            .map_err(|e| actix_web::error::ErrorBadRequest(format!("{:#}", e)))?;
+        if !signer.eq_ignore_ascii_case(&req.address) {
+            return Err(actix_web::error::ErrorUnauthorized(
+                "Signature is not from the address",
+            ));
+        }
+
+        let jobs = state.jobs.lock().await;
+        let opening = jobs
+            .get(&job_id)
+            .and_then(|status| {
+                status
+                    .openings
+                    .iter()
+                    .find(|opening| opening.address.eq_ignore_ascii_case(&signer))
+            })
+            .ok_or_else(|| actix_web::error::ErrorNotFound("No opening for the address"))?;
+
+        Ok(HttpResponse::Ok().json(opening))
+    }
+
+    /// Image ID of each guest, to verify its receipts with
+    pub async fn get_image_ids() -> HttpResponse {
+        HttpResponse::Ok().json(json!({
+            "top_half": Method::TopHalf.image_id(),
+            "rank_bucket": Method::RankBucket.image_id(),
+            "head_to_head": Method::HeadToHead.image_id(),
+            "private_top_half": Method::PrivateTopHalf.image_id(),
+        }))
+    }
 }
 
-async fn generate_proof(
-    addresses: Vec<String>,
-    db_pool: &SqlitePool,
-) -> std::io::Result<JobStatus> {
-    let scores = fetch_scores(db_pool, &addresses)
-        .await
-        .expect("Failed to fetch scores");
+async fn generate_proof(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<JobStatus> {
+    match job.method {
+        Method::TopHalf => prove_top_half(job, db_pool).await,
+        Method::RankBucket => prove_rank_buckets(job, db_pool).await,
+        Method::HeadToHead => prove_head_to_head(job, db_pool).await,
+        Method::PrivateTopHalf => prove_private_top_half(job, db_pool).await,
+    }
+}
+
+/// Scores of the job's addresses in its scope
+async fn fetch_job_scores(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<Vec<AddressData>> {
+    match (job.round_id, job.epoch_id, job.at) {
+        (Some(round_id), _, _) => fetch_round_scores(db_pool, round_id, &job.addresses).await,
+        (None, Some(epoch_id), _) => fetch_snapshot_scores(db_pool, epoch_id, &job.addresses).await,
+        (None, None, Some(at)) => {
+            fetch_scores_at(db_pool, job.leaderboard_id, at, &job.addresses).await
+        }
+        (None, None, None) => fetch_scores(db_pool, job.leaderboard_id, &job.addresses).await,
+    }
+}
 
+/// Winners and markets of a window among every score of its round. The journal commits the
+/// round's hash and the window's number, which the contracts check against the window's
+async fn prove_top_half(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<JobStatus> {
+    let (Some(round_id), Some(window_number)) = (job.round_id, job.window_number) else {
+        anyhow::bail!("Job {} has no round_id or window_number", job.job_id);
+    };
+    let scores = match fetch_round_leaderboard(db_pool, round_id).await {
+        Ok(scores) => scores,
+        Err(e) => {
+            println!("Refusing job {}: {:#}", job.job_id, e);
+            return Ok(JobStatus::failed(format!("{:#}", e)));
+        }
+    };
+
+    // The guest hashes the scores into the round hash, so they go in whole and in order. The
+    // addresses are the window's, in the order the journal's winners are paid out
     let env = ExecutorEnv::builder()
-        .write(&scores)
-        .unwrap()
-        .build()
-        .expect("Failed to build executor environment");
+        .write(&(round_id as u64))?
+        .write(&window_number)?
+        .write(&scores)?
+        .write(&job.addresses)?
+        .write(&job.markets)?
+        .build()?;
 
-    let prover = default_prover();
-    let receipt = prover.prove(env, ZKLEADERBOARD_GUEST_ELF).unwrap().receipt;
+    let receipt = prove(job, env)?;
 
-    let results: Vec<Output> = receipt.journal.decode().unwrap();
-    let address_results: Vec<AddressResult> = results
-        .into_iter()
-        .map(|output| AddressResult {
-            address: output.address,
-            is_top_half: output.is_top_half,
+    let journal = TopHalfJournal::abi_decode(&receipt.journal.bytes, true)?;
+    let address_results: Vec<AddressResult> = job
+        .addresses
+        .iter()
+        .zip(journal.winners)
+        .map(|(address, is_top_half)| AddressResult {
+            address: address.clone(),
+            is_top_half,
         })
         .collect();
+    let market_results = job
+        .markets
+        .iter()
+        .zip(&journal.markets)
+        .map(|(market, result)| market.output(result.outcome))
+        .collect();
     // receipt
     //     .verify(ZKLEADERBOARD_GUEST_ID)
     //     .context("Proof verification failed")?;
 
+    Ok(JobStatus {
+        results: Some(address_results),
+        market_results: Some(market_results),
+        ..completed(job.method, &receipt)?
+    })
+}
+
+/// Ranks and buckets of the job's addresses among every score of the leaderboard, with the
+/// hash of those scores committed as the leaderboard root
+async fn prove_rank_buckets(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<JobStatus> {
+    let leaderboard = match fetch_leaderboard(db_pool, job).await {
+        Ok(leaderboard) => leaderboard,
+        Err(e) => {
+            println!("Refusing job {}: {:#}", job.job_id, e);
+            return Ok(JobStatus::failed(format!("{:#}", e)));
+        }
+    };
+    if let Some(address) = job
+        .addresses
+        .iter()
+        .find(|address| !leaderboard.iter().any(|data| data.address == **address))
+    {
+        return Ok(JobStatus::failed(format!(
+            "{} is not on the leaderboard",
+            address
+        )));
+    }
+
+    let env = ExecutorEnv::builder()
+        .write(&leaderboard)?
+        .write(&job.addresses)?
+        .write(&job.buckets)?
+        .build()?;
+
+    let receipt = prove(job, env)?;
+
+    let (rank_results, buckets, leaderboard_root): (Vec<RankOutput>, u64, [u8; 32]) =
+        receipt.journal.decode()?;
+
+    Ok(JobStatus {
+        rank_results: Some(rank_results),
+        buckets: Some(buckets),
+        leaderboard_root: Some("0x".to_owned() + &hex::encode(leaderboard_root)),
+        ..completed(job.method, &receipt)?
+    })
+}
+
+/// Winner of each pair of the job, the scores stay out of the journal
+async fn prove_head_to_head(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<JobStatus> {
+    let scores = match fetch_job_scores(job, db_pool).await {
+        Ok(scores) => scores,
+        Err(e) => {
+            println!("Refusing job {}: {:#}", job.job_id, e);
+            return Ok(JobStatus::failed(format!("{:#}", e)));
+        }
+    };
+    if let Some(address) = job
+        .addresses
+        .iter()
+        .find(|address| !scores.iter().any(|data| data.address == **address))
+    {
+        return Ok(JobStatus::failed(format!("No score for {}", address)));
+    }
+
+    let env = ExecutorEnv::builder()
+        .write(&scores)?
+        .write(&job.pairs)?
+        .build()?;
+
+    let receipt = prove(job, env)?;
+
+    let pair_results: Vec<PairOutput> = receipt.journal.decode()?;
+
+    Ok(JobStatus {
+        pair_results: Some(pair_results),
+        ..completed(job.method, &receipt)?
+    })
+}
+
+/// Top half outcomes committed under each player's identity, a salted hash of their address and
+/// score. The salts are drawn here and only handed out as openings through `get_opening`.
+async fn prove_private_top_half(job: &ProofJob, db_pool: &AnyPool) -> anyhow::Result<JobStatus> {
+    let scores = match fetch_job_scores(job, db_pool).await {
+        Ok(scores) => scores,
+        Err(e) => {
+            println!("Refusing job {}: {:#}", job.job_id, e);
+            return Ok(JobStatus::failed(format!("{:#}", e)));
+        }
+    };
+    let salted: Vec<SaltedScore> = scores
+        .into_iter()
+        .map(|data| SaltedScore {
+            address: data.address,
+            score: data.score,
+            salt: rand::random(),
+        })
+        .collect();
+
+    let env = ExecutorEnv::builder().write(&salted)?.build()?;
+
+    let receipt = prove(job, env)?;
+
+    let private_results: Vec<PrivateOutput> = receipt.journal.decode()?;
+    let private_results = private_results
+        .into_iter()
+        .map(|output| PrivateResult {
+            identity: "0x".to_owned() + &hex::encode(output.identity),
+            is_top_half: output.is_top_half,
+        })
+        .collect();
+    let openings = salted
+        .into_iter()
+        .map(|data| Opening {
+            identity: "0x".to_owned() + &hex::encode(identity(&data)),
+            salt: "0x".to_owned() + &hex::encode(data.salt),
+            address: data.address,
+            score: data.score,
+        })
+        .collect();
+
+    Ok(JobStatus {
+        private_results: Some(private_results),
+        openings,
+        ..completed(job.method, &receipt)?
+    })
+}
+
+/// `sha256(salt ‖ lowercase address ‖ big-endian score)`, as the private guest commits it
+fn identity(data: &SaltedScore) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data.salt);
+    hasher.update(data.address.to_lowercase().as_bytes());
+    hasher.update(data.score.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// The message a player signs to receive their opening of a private job
+fn opening_message(job_id: &str) -> String {
+    format!("zkLeaderboard opening {}", job_id)
+}
+
+/// Address that signed `message` with EIP-191 `personal_sign`
+fn recover_signer(message: &str, signature: &str) -> anyhow::Result<String> {
+    let bytes = hex::decode(signature.trim_start_matches("0x"))?;
+    if bytes.len() != 65 {
+        anyhow::bail!("Invalid signature length {}", bytes.len());
+    }
+    // v is 27 or 28, or 0 or 1
+    let v = if bytes[64] >= 27 {
+        bytes[64] - 27
+    } else {
+        bytes[64]
+    };
+    let recovery_id = k256::ecdsa::RecoveryId::from_byte(v)
+        .ok_or_else(|| anyhow::anyhow!("Invalid recovery id"))?;
+    let signature = k256::ecdsa::Signature::from_slice(&bytes[..64])?;
+
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    let key = k256::ecdsa::VerifyingKey::recover_from_prehash(
+        &hasher.finalize(),
+        &signature,
+        recovery_id,
+    )?;
+
+    let public_key = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+    Ok(format!("0x{}", hex::encode(&hash[12..])))
+}
+
+/// Prove a job's guest, wrapped in Groth16 for `groth16` jobs
+fn prove(job: &ProofJob, env: ExecutorEnv) -> anyhow::Result<Receipt> {
+    let opts = if job.groth16 {
+        ProverOpts::groth16()
+    } else {
+        ProverOpts::default()
+    };
+    Ok(default_prover()
+        .prove_with_opts(env, job.method.elf(), &opts)?
+        .receipt)
+}
+
+/// Status of a proven job with its receipt, journal and the image ID of its guest
+fn completed(method: Method, receipt: &Receipt) -> anyhow::Result<JobStatus> {
     let mut bin_receipt = Vec::new();
-    into_writer(&receipt, &mut bin_receipt).unwrap();
+    into_writer(receipt, &mut bin_receipt)?;
     let proof = encode(&bin_receipt);
 
-    fs::write("proof.txt", hex::encode(&bin_receipt)).unwrap();
+    fs::write("proof.txt", hex::encode(&bin_receipt))?;
     let receipt_journal_bytes_array = &receipt.journal.bytes.as_slice();
-    let pub_inputs = hex::encode(&receipt_journal_bytes_array);
-
-    let image_id_hex = hex::encode(
-        ZKLEADERBOARD_GUEST_ID
-            .into_iter()
-            .flat_map(|v| v.to_le_bytes().into_iter())
-            .collect::<Vec<_>>(),
-    );
-
-    // let image_id_bytes: Vec<u8> = ZKLEADERBOARD_GUEST_ID
-    //     .iter()
-    //     .flat_map(|v| v.to_le_bytes())
-    //     .collect();
+    let pub_inputs = hex::encode(receipt_journal_bytes_array);
 
     Ok(JobStatus {
         status: "completed".into(),
         proof: Some("0x".to_owned() + &proof),
         journal: Some("0x".to_owned() + &pub_inputs),
-        image_id: Some("0x".to_owned() + &image_id_hex),
-        results: Some(address_results),
+        image_id: Some(method.image_id()),
+        seal: receipt
+            .inner
+            .groth16()
+            .ok()
+            .map(|groth16| "0x".to_owned() + &hex::encode(encode_seal(groth16))),
+        ..Default::default()
     })
 }
 
-async fn fetch_scores(pool: &SqlitePool, addresses: &[String]) -> anyhow::Result<Vec<AddressData>> {
-    let median = sqlx::query_scalar::<_, i64>(
-        r#"
-        SELECT score FROM addresses
-        ORDER BY score
-        LIMIT 1
-        OFFSET (SELECT COUNT(*) FROM addresses) / 2
-        "#,
-    )
-    .fetch_one(pool)
-    .await?;
+/// The seal prefixed with the first 4 bytes of its verifier parameters, which select the
+/// verifier on the RISC Zero verifier router, as risc0-ethereum's `encode_seal`
+fn encode_seal(receipt: &Groth16Receipt<ReceiptClaim>) -> Vec<u8> {
+    [
+        &receipt.verifier_parameters.as_bytes()[..4],
+        &receipt.seal[..],
+    ]
+    .concat()
+}
 
-    print!("Median:{}", median);
+async fn fetch_scores(
+    pool: &AnyPool,
+    leaderboard_id: i64,
+    addresses: &[String],
+) -> anyhow::Result<Vec<AddressData>> {
     // Fetch scores for requested addresses
     let mut address_data = Vec::with_capacity(addresses.len());
     for addr in addresses {
-        if let Ok(score) =
-            sqlx::query_scalar::<_, i64>("SELECT score FROM addresses WHERE address = ?")
-                .bind(addr)
-                .fetch_optional(pool)
-                .await
+        if let Ok(score) = sqlx::query_scalar::<_, i64>(
+            "SELECT score FROM addresses WHERE leaderboard_id = $1 AND address = $2",
+        )
+        .bind(leaderboard_id)
+        .bind(addr)
+        .fetch_optional(pool)
+        .await
         {
             if let Some(score) = score {
                 address_data.push(AddressData {
@@ -185,15 +824,255 @@ async fn fetch_scores(pool: &SqlitePool, addresses: &[String]) -> anyhow::Result
     Ok(address_data)
 }
 
+/// Scores frozen when an epoch was closed, matching the hash anchored on-chain
+async fn fetch_snapshot_scores(
+    pool: &AnyPool,
+    epoch_id: i64,
+    addresses: &[String],
+) -> anyhow::Result<Vec<AddressData>> {
+    let mut address_data = Vec::with_capacity(addresses.len());
+    for addr in addresses {
+        let score = sqlx::query_scalar::<_, i64>(
+            "SELECT score FROM epoch_scores WHERE epoch_id = $1 AND address = $2",
+        )
+        .bind(epoch_id)
+        .bind(addr)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(score) = score {
+            address_data.push(AddressData {
+                address: addr.clone(),
+                score,
+            });
+        }
+    }
+
+    Ok(address_data)
+}
+
+/// Scores as they stood at `at` (unix milliseconds): the last recorded change of each
+/// address, none when its epoch had closed by then
+async fn fetch_scores_at(
+    pool: &AnyPool,
+    leaderboard_id: i64,
+    at: i64,
+    addresses: &[String],
+) -> anyhow::Result<Vec<AddressData>> {
+    let mut address_data = Vec::with_capacity(addresses.len());
+    for addr in addresses {
+        let score = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT score FROM score_events \
+             WHERE leaderboard_id = $1 AND address = $2 AND recorded_at <= $3 \
+             ORDER BY recorded_at DESC, id DESC LIMIT 1",
+        )
+        .bind(leaderboard_id)
+        .bind(addr)
+        .bind(at)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(score) = score.flatten() {
+            address_data.push(AddressData {
+                address: addr.clone(),
+                score,
+            });
+        }
+    }
+
+    Ok(address_data)
+}
+
+/// Scores frozen when a window round started, see `fetch_round_leaderboard`
+async fn fetch_round_scores(
+    pool: &AnyPool,
+    round_id: i64,
+    addresses: &[String],
+) -> anyhow::Result<Vec<AddressData>> {
+    Ok(fetch_round_leaderboard(pool, round_id)
+        .await?
+        .into_iter()
+        .filter(|data| addresses.contains(&data.address))
+        .collect())
+}
+
+/// Every score frozen when a window round started. The round is refused unless its scores
+/// still hash to the round hash and that hash was stored on-chain by its anchor transaction,
+/// which needs `RPC_URL` and `HASH_CONTRACT_ADDRESS`.
+async fn fetch_round_leaderboard(
+    pool: &AnyPool,
+    round_id: i64,
+) -> anyhow::Result<Vec<AddressData>> {
+    let (round_hash, anchor_tx) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT round_hash, anchor_tx FROM window_rounds WHERE id = $1",
+    )
+    .bind(round_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("Round {} not found", round_id))?;
+    let anchor_tx =
+        anchor_tx.ok_or_else(|| anyhow::anyhow!("Round {} was never anchored", round_id))?;
+
+    let scores = sqlx::query_as::<_, (String, i64)>(
+        "SELECT address, score FROM window_round_scores WHERE round_id = $1 ORDER BY address",
+    )
+    .bind(round_id)
+    .fetch_all(pool)
+    .await?;
+    let computed = hash_round(&canonical_hash(&scores)?, round_id);
+    if computed != round_hash {
+        anyhow::bail!(
+            "Scores of round {} hash to {}, not the recorded {}",
+            round_id,
+            computed,
+            round_hash
+        );
+    }
+    let rpc_url = env::var("RPC_URL").map_err(|_| {
+        anyhow::anyhow!(
+            "RPC_URL is not set, the anchor of round {} can't be checked",
+            round_id
+        )
+    })?;
+    check_anchor(&rpc_url, &anchor_tx, &round_hash).await?;
+
+    Ok(scores
+        .into_iter()
+        .map(|(address, score)| AddressData { address, score })
+        .collect())
+}
+
+/// Every score of the job's scope in the order the backend hashes them, by address
+async fn fetch_leaderboard(pool: &AnyPool, job: &ProofJob) -> anyhow::Result<Vec<AddressData>> {
+    let scores =
+        match (job.round_id, job.epoch_id, job.at) {
+            (Some(round_id), _, _) => return fetch_round_leaderboard(pool, round_id).await,
+            (None, Some(epoch_id), _) => {
+                sqlx::query_as::<_, (String, Option<i64>)>(
+                    "SELECT address, score FROM epoch_scores WHERE epoch_id = $1 ORDER BY address",
+                )
+                .bind(epoch_id)
+                .fetch_all(pool)
+                .await?
+            }
+            // The last recorded change of each address, a closed epoch's addresses have none
+            (None, None, Some(at)) => {
+                sqlx::query_as::<_, (String, Option<i64>)>(
+                    "SELECT e.address, e.score FROM score_events e \
+                 WHERE e.leaderboard_id = $1 AND e.id = (\
+                     SELECT id FROM score_events \
+                     WHERE leaderboard_id = $2 AND address = e.address AND recorded_at <= $3 \
+                     ORDER BY recorded_at DESC, id DESC LIMIT 1) \
+                 ORDER BY e.address",
+                )
+                .bind(job.leaderboard_id)
+                .bind(job.leaderboard_id)
+                .bind(at)
+                .fetch_all(pool)
+                .await?
+            }
+            (None, None, None) => sqlx::query_as::<_, (String, Option<i64>)>(
+                "SELECT address, score FROM addresses WHERE leaderboard_id = $1 ORDER BY address",
+            )
+            .bind(job.leaderboard_id)
+            .fetch_all(pool)
+            .await?,
+        };
+
+    Ok(scores
+        .into_iter()
+        .filter_map(|(address, score)| {
+            Some(AddressData {
+                address,
+                score: score?,
+            })
+        })
+        .collect())
+}
+
+/// Keccak-256 of the canonical JSON `[{"address": ..., "score": ...}, ...]` the backend
+/// anchors, entries sorted by address
+fn canonical_hash(scores: &[(String, i64)]) -> anyhow::Result<Vec<u8>> {
+    let entries = scores
+        .iter()
+        .map(|(address, score)| {
+            Ok(json!({
+                "address": address.to_lowercase(),
+                "score": u32::try_from(*score)?,
+            }))
+        })
+        .collect::<anyhow::Result<Vec<Value>>>()?;
+
+    Ok(Keccak256::digest(serde_json::to_vec(&entries)?).to_vec())
+}
+
+/// `keccak256(scores_hash ‖ uint256(round))`, as the backend anchors it
+fn hash_round(scores_hash: &[u8], round_id: i64) -> String {
+    let mut round = [0u8; 32];
+    round[24..].copy_from_slice(&(round_id as u64).to_be_bytes());
+
+    let mut hasher = Keccak256::new();
+    hasher.update(scores_hash);
+    hasher.update(round);
+    hex::encode(hasher.finalize())
+}
+
+/// Checks that `tx_hash` succeeded in storing `round_hash` on `HASH_CONTRACT_ADDRESS`
+async fn check_anchor(rpc_url: &str, tx_hash: &str, round_hash: &str) -> anyhow::Result<()> {
+    let contract = env::var("HASH_CONTRACT_ADDRESS").map_err(|_| {
+        anyhow::anyhow!("HASH_CONTRACT_ADDRESS is not set, anchors can't be checked")
+    })?;
+    let tx = rpc(rpc_url, "eth_getTransactionByHash", json!([tx_hash])).await?;
+    let receipt = rpc(rpc_url, "eth_getTransactionReceipt", json!([tx_hash])).await?;
+    if receipt["status"] != "0x1" {
+        anyhow::bail!("Anchor transaction {} has not succeeded", tx_hash);
+    }
+    let to = tx["to"].as_str().unwrap_or_default();
+    if !to.eq_ignore_ascii_case(&contract) {
+        anyhow::bail!("Anchor transaction {} was sent to {}", tx_hash, to);
+    }
+
+    // storeHashRecord(bytes32 hash, uint256 timestamp, uint256 recordCount)
+    let input = tx["input"].as_str().unwrap_or_default();
+    let stored = input
+        .trim_start_matches("0x")
+        .get(8..72)
+        .unwrap_or_default();
+    if !stored.eq_ignore_ascii_case(round_hash) {
+        anyhow::bail!(
+            "Anchor transaction {} stored {}, not {}",
+            tx_hash,
+            stored,
+            round_hash
+        );
+    }
+
+    Ok(())
+}
+
+async fn rpc(rpc_url: &str, method: &str, params: Value) -> anyhow::Result<Value> {
+    let response: Value = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+        .send()
+        .await?
+        .json()
+        .await?;
+    match &response["result"] {
+        Value::Null => anyhow::bail!("{} returned no result: {}", method, response["error"]),
+        result => Ok(result.clone()),
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // env_logger::init();
 
-    let db_pool = SqlitePool::connect(&format!("sqlite:{}", DB_PATH))
+    sqlx::any::install_default_drivers();
+    let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| format!("sqlite:{}", DB_PATH));
+    let db_pool = AnyPool::connect(&database_url)
         .await
         .expect("Failed to connect to database");
 
-    let (tx, mut rx) = mpsc::channel::<(String, Vec<String>)>(32);
+    let (tx, mut rx) = mpsc::channel::<ProofJob>(32);
     let state = web::Data::new(AppState {
         jobs: Mutex::new(HashMap::new()),
         tx,
@@ -202,15 +1081,16 @@ async fn main() -> std::io::Result<()> {
 
     let state_clone = state.clone();
     tokio::spawn(async move {
-        while let Some((job_id, addresses)) = rx.recv().await {
-            match generate_proof(addresses, &state_clone.db_pool).await {
-                Ok(proof) => {
-                    state_clone.jobs.lock().await.insert(job_id, proof);
-                }
+        while let Some(job) = rx.recv().await {
+            // A job that can't be proven fails, the worker moves on to the next one
+            let status = match generate_proof(&job, &state_clone.db_pool).await {
+                Ok(status) => status,
                 Err(e) => {
-                    println!("Error generating proof: {}", e);
+                    println!("Error generating proof for job {}: {:#}", job.job_id, e);
+                    JobStatus::failed(format!("{:#}", e))
                 }
-            }
+            };
+            state_clone.jobs.lock().await.insert(job.job_id, status);
         }
     });
 
@@ -223,7 +1103,13 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::scope("/check_position").route("/", web::post().to(handlers::check_position)),
             )
-            .service(web::scope("/job").route("/{job_id}", web::get().to(handlers::get_job_status)))
+            .service(
+                web::scope("/job")
+                    .route("/{job_id}", web::get().to(handlers::get_job_status))
+                    .route("/{job_id}/opening", web::post().to(handlers::get_opening)),
+            )
+            .service(web::scope("/check_pairs").route("/", web::post().to(handlers::check_pairs)))
+            .route("/image_ids", web::get().to(handlers::get_image_ids))
     })
     .bind(SERVER_ADDR)?
     .run()
@@ -231,3 +1117,97 @@ async fn main() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use risc0_zkvm::default_executor;
+
+    // The journal the VerificationAndPrize tests pay out from. It is a fixture, not a proof: the
+    // contract tests attest it on a mock zkVerify. Run with UPDATE_JOURNAL_FIXTURE=1 to rewrite
+    // it from the guest
+    const JOURNAL_FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../../solidity_contracts/verification/test/fixtures/top_half_journal.json"
+    );
+
+    #[test]
+    fn test_journal_is_what_the_verification_contract_decodes() {
+        let mut fixture: Value =
+            serde_json::from_str(&fs::read_to_string(JOURNAL_FIXTURE).unwrap()).unwrap();
+        let round_id = fixture["round_id"].as_u64().unwrap();
+        let window_number = fixture["window_number"].as_u64().unwrap();
+        let scores: Vec<AddressData> = serde_json::from_value(fixture["scores"].clone()).unwrap();
+        let addresses: Vec<String> = serde_json::from_value(fixture["addresses"].clone()).unwrap();
+        let markets: Vec<Market> = serde_json::from_value(fixture["markets"].clone()).unwrap();
+
+        let env = ExecutorEnv::builder()
+            .write(&round_id)
+            .unwrap()
+            .write(&window_number)
+            .unwrap()
+            .write(&scores)
+            .unwrap()
+            .write(&addresses)
+            .unwrap()
+            .write(&markets)
+            .unwrap()
+            .build()
+            .unwrap();
+        let session = default_executor()
+            .execute(env, ZKLEADERBOARD_GUEST_ELF)
+            .unwrap();
+        let journal = TopHalfJournal::abi_decode(&session.journal.bytes, true).unwrap();
+
+        // The round hash is the one the backend anchors for these scores
+        let scores: Vec<(String, i64)> = scores
+            .into_iter()
+            .map(|data| (data.address, data.score))
+            .collect();
+        let round_hash = hash_round(&canonical_hash(&scores).unwrap(), round_id as i64);
+        assert_eq!(hex::encode(journal.roundHash), round_hash);
+        assert_eq!(journal.windowNumber.to::<u64>(), window_number);
+        let journal_addresses: Vec<String> = journal
+            .addresses
+            .iter()
+            .map(|address| format!("{:#x}", address))
+            .collect();
+        assert_eq!(journal_addresses, addresses);
+
+        let outputs = json!({
+            "journal": "0x".to_owned() + &hex::encode(&session.journal.bytes),
+            "round_hash": "0x".to_owned() + &round_hash,
+            "winners": journal.winners,
+        });
+        let outcomes: Vec<u8> = journal.markets.iter().map(|m| m.outcome).collect();
+        if env::var("UPDATE_JOURNAL_FIXTURE").is_ok() {
+            for (key, value) in outputs.as_object().unwrap() {
+                fixture[key] = value.clone();
+            }
+            for (result, outcome) in fixture["market_results"]
+                .as_array_mut()
+                .unwrap()
+                .iter_mut()
+                .zip(outcomes)
+            {
+                result["outcome"] = json!(outcome);
+            }
+            let json = serde_json::to_string_pretty(&fixture).unwrap() + "\n";
+            fs::write(JOURNAL_FIXTURE, json).unwrap();
+            return;
+        }
+        for (key, value) in outputs.as_object().unwrap() {
+            assert_eq!(&fixture[key], value, "{} is out of date", key);
+        }
+        let fixture_outcomes: Vec<u64> = fixture["market_results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["outcome"].as_u64().unwrap())
+            .collect();
+        assert_eq!(
+            fixture_outcomes,
+            outcomes.iter().map(|&o| o as u64).collect::<Vec<u64>>()
+        );
+    }
+}
@@ -1,5 +1,8 @@
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::{sol, SolValue};
 use risc0_zkvm::guest::env;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 // use sha2::{Digest, Sha256};
 
 #[derive(Serialize, Deserialize)]
@@ -8,38 +11,167 @@ struct AddressData {
     score: i64,
 }
 
+/// An entry of the canonical JSON the backend hashes
+#[derive(Serialize)]
+struct CanonicalEntry<'a> {
+    address: &'a str,
+    score: u32,
+}
+
+// Market kinds of the AddressLogger contract
+const MARKET_TOP_K: u8 = 1;
+const MARKET_RANK_BUCKET: u8 = 2;
+const MARKET_HEAD_TO_HEAD: u8 = 3;
+const MARKET_OVER_UNDER: u8 = 4;
+
+sol! {
+    /// A market as the contract identifies it and its winning outcome
+    struct MarketResult {
+        uint8 kind;
+        address selectedAddress;
+        uint256 param;
+        uint8 outcome;
+    }
+
+    /// The journal, ABI-encoded so VerificationAndPrize decodes it directly. `roundHash` is the
+    /// hash of the scores ranked, anchored when window `windowNumber` started. `winners` follow
+    /// `addresses`, which are in the window's order.
+    struct TopHalfJournal {
+        bytes32 roundHash;
+        uint256 windowNumber;
+        address[] addresses;
+        bool[] winners;
+        MarketResult[] markets;
+    }
+}
+
+/// A market besides top half/bottom half, ranked among the scores of the round
 #[derive(Serialize, Deserialize)]
-struct Output {
-    address: String,
-    is_top_half: bool,
+#[serde(rename_all = "snake_case")]
+enum Market {
+    TopK { address: String, k: u64 },
+    RankBucket { address: String, buckets: u64 },
+    HeadToHead { address: String, opponent: String },
+    OverUnder { address: String, threshold: i64 },
 }
 
 fn main() {
     env::log("Starting guest program");
+    let round_id: u64 = env::read();
+    let window_number: u64 = env::read();
+    // Every score frozen in the round, in the backend's hashing order, by address
     let scores: Vec<AddressData> = env::read();
+    // The window's addresses in the contract's order
+    let addresses: Vec<String> = env::read();
+    let markets: Vec<Market> = env::read();
 
     let mut sorted_scores: Vec<i64> = scores.iter().map(|d| d.score).collect();
     sorted_scores.sort_unstable();
 
-    let median = match sorted_scores.len() % 2 {
-        1 => sorted_scores[sorted_scores.len() / 2],
-        _ => {
-            (sorted_scores[sorted_scores.len() / 2 - 1] + sorted_scores[sorted_scores.len() / 2])
-                / 2
-        }
-    };
+    // Without scores there are no results to compare
+    let median = ranking::median(&sorted_scores).unwrap_or_default();
 
-    // Generate results
-    let results: Vec<Output> = scores
+    let ranks = ranking::ranks(&scores.iter().map(|d| d.score).collect::<Vec<i64>>());
+    // Rank and score of an address. Addresses without a score rank below every scored one,
+    // so their window can still be settled
+    let unscored_rank = ranking::rank(scores.len() as u64);
+    let player = |address: &str| -> Option<(u64, i64)> {
+        let i = scores
+            .iter()
+            .position(|d| d.address.eq_ignore_ascii_case(address))?;
+        Some((ranks[i], scores[i].score))
+    };
+    let rank = |address: &str| player(address).map_or(unscored_rank, |(rank, _)| rank);
+    let markets: Vec<MarketResult> = markets
         .into_iter()
-        .map(|data| Output {
-            address: data.address,
-            is_top_half: data.score >= median,
+        .map(|market| {
+            let (kind, outcome, param, address) = match market {
+                Market::TopK { address, k } => (
+                    MARKET_TOP_K,
+                    ranking::is_top_k(rank(&address), k) as u64,
+                    U256::from(k),
+                    address,
+                ),
+                Market::RankBucket { address, buckets } => (
+                    MARKET_RANK_BUCKET,
+                    match player(&address) {
+                        Some((rank, _)) => ranking::bucket(rank, scores.len() as u64, buckets)
+                            .expect("Invalid bucket count"),
+                        None => buckets.checked_sub(1).expect("Invalid bucket count"),
+                    },
+                    U256::from(buckets),
+                    address,
+                ),
+                Market::HeadToHead { address, opponent } => (
+                    MARKET_HEAD_TO_HEAD,
+                    ranking::outranks(rank(&address), rank(&opponent)) as u64,
+                    U256::from_be_slice(parse_address(&opponent).as_slice()),
+                    address,
+                ),
+                Market::OverUnder { address, threshold } => (
+                    MARKET_OVER_UNDER,
+                    player(&address).is_some_and(|(_, score)| ranking::is_over(score, threshold))
+                        as u64,
+                    U256::from(u64::try_from(threshold).expect("Negative threshold")),
+                    address,
+                ),
+            };
+            MarketResult {
+                kind,
+                selectedAddress: parse_address(&address),
+                param,
+                outcome: outcome as u8,
+            }
         })
         .collect();
 
+    // Generate results
+    let journal = TopHalfJournal {
+        roundHash: round_hash(&scores, round_id),
+        windowNumber: U256::from(window_number),
+        winners: addresses
+            .iter()
+            .map(|address| {
+                player(address).is_some_and(|(_, score)| ranking::is_top_half(score, median))
+            })
+            .collect(),
+        addresses: addresses
+            .iter()
+            .map(|address| parse_address(address))
+            .collect(),
+        markets,
+    };
+
     // Commit results to journal
-    env::commit(&results);
+    env::commit_slice(&journal.abi_encode());
+}
+
+/// `keccak256(scores_hash ‖ uint256(round))` as the backend anchors it, where `scores_hash` is
+/// the Keccak-256 of the canonical JSON of the scores
+fn round_hash(scores: &[AddressData], round_id: u64) -> B256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"[");
+    for (i, data) in scores.iter().enumerate() {
+        if i > 0 {
+            hasher.update(b",");
+        }
+        let entry = CanonicalEntry {
+            address: &data.address.to_lowercase(),
+            score: u32::try_from(data.score).expect("Score out of range"),
+        };
+        hasher.update(serde_json::to_vec(&entry).unwrap());
+    }
+    hasher.update(b"]");
+    let scores_hash = hasher.finalize();
+
+    let mut hasher = Keccak256::new();
+    hasher.update(scores_hash);
+    hasher.update(U256::from(round_id).to_be_bytes::<32>());
+    B256::from_slice(&hasher.finalize())
+}
+
+fn parse_address(address: &str) -> Address {
+    address.parse().expect("Invalid address")
 }
 
 // Verify score proof (MVP: simple hash verification)
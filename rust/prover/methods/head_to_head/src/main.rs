@@ -0,0 +1,56 @@
+use risc0_zkvm::guest::env;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Serialize, Deserialize)]
+struct AddressData {
+    address: String,
+    score: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Pair {
+    address: String,
+    opponent: String,
+}
+
+/// A pair and the address with the higher score, `None` when they tie
+#[derive(Serialize, Deserialize)]
+struct PairOutput {
+    address: String,
+    opponent: String,
+    winner: Option<String>,
+}
+
+fn main() {
+    env::log("Starting head-to-head guest program");
+    let scores: Vec<AddressData> = env::read();
+    let pairs: Vec<Pair> = env::read();
+
+    // The host only sends pairs of scored addresses
+    let score = |address: &str| -> i64 {
+        scores
+            .iter()
+            .find(|data| data.address == address)
+            .expect("Pair with an address without a score")
+            .score
+    };
+    let results: Vec<PairOutput> = pairs
+        .into_iter()
+        .map(|pair| {
+            let winner = match score(&pair.address).cmp(&score(&pair.opponent)) {
+                Ordering::Greater => Some(pair.address.clone()),
+                Ordering::Less => Some(pair.opponent.clone()),
+                Ordering::Equal => None,
+            };
+            PairOutput {
+                address: pair.address,
+                opponent: pair.opponent,
+                winner,
+            }
+        })
+        .collect();
+
+    // Commit results to journal, the scores stay private
+    env::commit(&results);
+}
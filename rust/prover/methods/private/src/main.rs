@@ -0,0 +1,49 @@
+use risc0_zkvm::guest::env;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A score and the salt of its commitment, known only to the host and the player
+#[derive(Serialize, Deserialize)]
+struct SaltedScore {
+    address: String,
+    score: i64,
+    salt: [u8; 32],
+}
+
+/// Outcome of a player, identified by the commitment to their address and score
+#[derive(Serialize, Deserialize)]
+struct PrivateOutput {
+    identity: [u8; 32],
+    is_top_half: bool,
+}
+
+/// `sha256(salt ‖ lowercase address ‖ big-endian score)`, which the player can open
+fn identity(data: &SaltedScore) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data.salt);
+    hasher.update(data.address.to_lowercase().as_bytes());
+    hasher.update(data.score.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn main() {
+    env::log("Starting private guest program");
+    let scores: Vec<SaltedScore> = env::read();
+
+    let mut sorted_scores: Vec<i64> = scores.iter().map(|d| d.score).collect();
+    sorted_scores.sort_unstable();
+    let median = ranking::median(&sorted_scores).unwrap_or_default();
+
+    let mut results: Vec<PrivateOutput> = scores
+        .iter()
+        .map(|data| PrivateOutput {
+            identity: identity(data),
+            is_top_half: ranking::is_top_half(data.score, median),
+        })
+        .collect();
+    // Sorted by identity, so the order of the request doesn't link identities to addresses
+    results.sort_by_key(|output| output.identity);
+
+    // Commit results to journal, without addresses or scores
+    env::commit(&results);
+}
@@ -0,0 +1,70 @@
+use risc0_zkvm::guest::env;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+#[derive(Serialize, Deserialize)]
+struct AddressData {
+    address: String,
+    score: i64,
+}
+
+/// An entry of the canonical JSON the backend hashes
+#[derive(Serialize)]
+struct CanonicalEntry<'a> {
+    address: &'a str,
+    score: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RankOutput {
+    address: String,
+    rank: u64,
+    bucket: u64,
+}
+
+fn main() {
+    env::log("Starting rank bucket guest program");
+    // The full leaderboard in the backend's hashing order, by address
+    let leaderboard: Vec<AddressData> = env::read();
+    let addresses: Vec<String> = env::read();
+    let buckets: u64 = env::read();
+
+    // Keccak-256 of the canonical JSON the backend anchors for epochs and window rounds, so
+    // the root of a snapshot is its anchored hash
+    let mut hasher = Keccak256::new();
+    hasher.update(b"[");
+    for (i, data) in leaderboard.iter().enumerate() {
+        if i > 0 {
+            hasher.update(b",");
+        }
+        let entry = CanonicalEntry {
+            address: &data.address.to_lowercase(),
+            score: u32::try_from(data.score).expect("Score out of range"),
+        };
+        hasher.update(serde_json::to_vec(&entry).unwrap());
+    }
+    hasher.update(b"]");
+    let leaderboard_root: [u8; 32] = hasher.finalize().into();
+
+    let scores: Vec<i64> = leaderboard.iter().map(|data| data.score).collect();
+    let ranks = ranking::ranks(&scores);
+    let total = leaderboard.len() as u64;
+
+    let results: Vec<RankOutput> = addresses
+        .into_iter()
+        .map(|address| {
+            let i = leaderboard
+                .iter()
+                .position(|data| data.address == address)
+                .expect("Address is not on the leaderboard");
+            RankOutput {
+                bucket: ranking::bucket(ranks[i], total, buckets).expect("Invalid bucket count"),
+                rank: ranks[i],
+                address,
+            }
+        })
+        .collect();
+
+    // Commit results to journal
+    env::commit(&(results, buckets, leaderboard_root));
+}
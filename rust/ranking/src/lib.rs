@@ -0,0 +1,175 @@
+//! Ranking rules shared by the backend and the prover, so a rank or median served by the API
+//! is the one a proof is generated against.
+//!
+//! - Leaderboards are ordered by score, highest first, ties by address
+//! - Ranks are competition ranks ("1224"): tied scores share the rank of the first of them
+//! - Quantiles of an even split are the midpoint of the two middle scores, rounded down
+
+use std::cmp::Ordering;
+
+/// Order of a ranked leaderboard: higher scores first, tied scores by address
+pub fn compare(a: (i64, &str), b: (i64, &str)) -> Ordering {
+    b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1))
+}
+
+/// Rank of a score with `higher` scores above it
+pub fn rank(higher: u64) -> u64 {
+    higher + 1
+}
+
+/// Ranks of consecutive scores in leaderboard order. The first is at 1-based `position` and
+/// has rank `first_rank`, which is lower than `position` when it ties with the page before.
+pub fn page_ranks(first_rank: u64, position: u64, scores: &[i64]) -> Vec<u64> {
+    let mut ranks = Vec::with_capacity(scores.len());
+    for (i, score) in scores.iter().enumerate() {
+        let rank = match i {
+            0 => first_rank,
+            _ if *score == scores[i - 1] => ranks[i - 1],
+            _ => position + i as u64,
+        };
+        ranks.push(rank);
+    }
+    ranks
+}
+
+/// Percentile rank from 0 to 100: the share of players below, plus half of those tied
+/// (counting the player), so tied players share a percentile.
+pub fn percentile(higher: u64, equal: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    let below = total - higher - equal;
+    (below as f64 + equal as f64 / 2.0) * 100.0 / total as f64
+}
+
+/// Offsets into `count` ascending scores whose midpoint is the `numerator / denominator`
+/// quantile, the same offset twice when it falls on a score.
+pub fn quantile_offsets(count: u64, numerator: u64, denominator: u64) -> Option<(u64, u64)> {
+    if count == 0 || denominator == 0 || numerator > denominator {
+        return None;
+    }
+    let scaled = (count - 1) * numerator;
+    Some((scaled / denominator, scaled.div_ceil(denominator)))
+}
+
+/// Midpoint of two scores, rounded down
+pub fn midpoint(lower: i64, upper: i64) -> i64 {
+    lower + (upper - lower).div_euclid(2)
+}
+
+/// The `numerator / denominator` quantile of ascending scores
+pub fn quantile(sorted: &[i64], numerator: u64, denominator: u64) -> Option<i64> {
+    let (lower, upper) = quantile_offsets(sorted.len() as u64, numerator, denominator)?;
+    Some(midpoint(sorted[lower as usize], sorted[upper as usize]))
+}
+
+/// Median of ascending scores
+pub fn median(sorted: &[i64]) -> Option<i64> {
+    quantile(sorted, 1, 2)
+}
+
+/// A score at or above the median is in the top half
+pub fn is_top_half(score: i64, median: i64) -> bool {
+    score >= median
+}
+
+/// Competition ranks of scores, in the order given
+pub fn ranks(scores: &[i64]) -> Vec<u64> {
+    let mut sorted = scores.to_vec();
+    sorted.sort_unstable();
+    scores
+        .iter()
+        .map(|score| {
+            let higher = sorted.len() - sorted.partition_point(|other| other <= score);
+            rank(higher as u64)
+        })
+        .collect()
+}
+
+/// A rank of `k` or better is in the top K, tied players all make it
+pub fn is_top_k(rank: u64, k: u64) -> bool {
+    rank <= k
+}
+
+/// Bucket of a rank among `total` players split into `buckets` equal ranges, 0 holding the
+/// top. Tied players share the bucket of their rank.
+pub fn bucket(rank: u64, total: u64, buckets: u64) -> Option<u64> {
+    if rank == 0 || rank > total || buckets == 0 {
+        return None;
+    }
+    Some((rank - 1) * buckets / total)
+}
+
+/// A player outranks another with a better rank, tied players don't
+pub fn outranks(rank: u64, other: u64) -> bool {
+    rank < other
+}
+
+/// A score over the threshold, scores on it are under
+pub fn is_over(score: i64, threshold: i64) -> bool {
+    score > threshold
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_ties_like_competitions() {
+        let mut players = vec![(300, "0xc"), (500, "0xb"), (300, "0xa"), (100, "0xd")];
+        players.sort_by(|a, b| compare(*a, *b));
+        assert_eq!(
+            players,
+            vec![(500, "0xb"), (300, "0xa"), (300, "0xc"), (100, "0xd")]
+        );
+
+        let scores: Vec<i64> = players.iter().map(|(score, _)| *score).collect();
+        assert_eq!(page_ranks(rank(0), 1, &scores), vec![1, 2, 2, 4]);
+        // A page starting inside a tie keeps the rank of its first player
+        assert_eq!(page_ranks(rank(1), 3, &scores[2..]), vec![2, 4]);
+
+        assert_eq!(percentile(1, 2, 4), 50.0);
+        assert_eq!(percentile(0, 1, 4), 87.5);
+        assert_eq!(percentile(3, 1, 4), 12.5);
+        assert_eq!(percentile(0, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn splits_even_quantiles_at_the_midpoint() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[7]), Some(7));
+        assert_eq!(median(&[1, 2, 3]), Some(2));
+        assert_eq!(median(&[1, 2, 3, 6]), Some(2));
+        assert_eq!(median(&[1, 3, 4, 6]), Some(3));
+
+        let scores = [10, 20, 30, 40, 50, 60, 70, 80];
+        assert_eq!(quantile_offsets(8, 1, 4), Some((1, 2)));
+        assert_eq!(quantile(&scores, 1, 4), Some(25));
+        assert_eq!(quantile(&scores, 3, 4), Some(65));
+        assert_eq!(quantile(&scores, 0, 1), Some(10));
+        assert_eq!(quantile(&scores, 1, 1), Some(80));
+        assert!(is_top_half(45, median(&scores).unwrap()));
+    }
+
+    #[test]
+    fn settles_rank_markets_on_competition_ranks() {
+        let scores = [300, 500, 300, 100, 800, 200, 700, 600, 400, 900];
+        let ranks = ranks(&scores);
+        assert_eq!(ranks, vec![7, 5, 7, 10, 2, 9, 3, 4, 6, 1]);
+
+        assert!(is_top_k(ranks[7], 5));
+        assert!(!is_top_k(ranks[0], 5));
+        // Both players tied for seventh make a top 7
+        assert!(is_top_k(ranks[0], 7) && is_top_k(ranks[2], 7));
+
+        assert_eq!(bucket(1, 10, 4), Some(0));
+        assert_eq!(bucket(ranks[0], 10, 4), Some(2));
+        assert_eq!(bucket(10, 10, 4), Some(3));
+        assert_eq!(bucket(10, 10, 10), Some(9));
+        assert_eq!(bucket(11, 10, 4), None);
+
+        assert!(outranks(ranks[1], ranks[0]));
+        assert!(!outranks(ranks[0], ranks[2]));
+        assert!(is_over(301, 300) && !is_over(300, 300));
+    }
+}
@@ -3,4 +3,20 @@ pragma solidity ^0.8.13;
 
 interface IAddressLogger {
     function processPayouts(bool[] memory winners) external;
+
+    function processMarketPayouts(uint8[] memory outcomes) external;
+
+    function getRoundHash() external view returns (bytes32);
+
+    function getWindowNumber() external view returns (uint256);
+
+    function getValidAddressCount() external view returns (uint256);
+
+    function getValidAddress(uint256 index) external view returns (address);
+
+    function getMarketCount() external view returns (uint256);
+
+    function getMarket(
+        uint256 index
+    ) external view returns (uint8, address, uint256, uint8);
 }
@@ -4,10 +4,33 @@ import "./IZKVerifyAttestation.sol";
 import "./IAddressLogger.sol";
 
 contract VerificationAndPrize {
-    // zkVerify contract on Arbitrum
-    address public constant ZKVERIFY =
-        0x82941a739E74eBFaC72D0d0f8E81B1Dac2f586D5;
+    // zkVerify statement of a RISC Zero proof
+    bytes32 public constant PROVING_SYSTEM_ID =
+        keccak256(abi.encodePacked("risc0"));
+    bytes32 public constant VERSION_HASH =
+        sha256(abi.encodePacked("risc0:v1.2"));
+
+    // zkVerify contract, 0x82941a739E74eBFaC72D0d0f8E81B1Dac2f586D5 on Arbitrum
+    address public immutable zkVerify;
     address public address_logger;
+    // Image ID of the top-half guest whose journals are paid out
+    bytes32 public immutable imageId;
+
+    // The guest's journal, ABI-encoded
+    struct MarketResult {
+        uint8 kind;
+        address selectedAddress;
+        uint256 param;
+        uint8 outcome;
+    }
+
+    struct TopHalfJournal {
+        bytes32 roundHash;
+        uint256 windowNumber;
+        address[] addresses;
+        bool[] winners;
+        MarketResult[] markets;
+    }
 
     event ProofVerified(
         bytes32 indexed leaf,
@@ -21,21 +44,39 @@ contract VerificationAndPrize {
         string reason
     );
 
-    constructor(address _address_logger) {
+    constructor(address _zkVerify, address _address_logger, bytes32 _imageId) {
+        zkVerify = _zkVerify;
         address_logger = _address_logger;
+        imageId = _imageId;
     }
 
+    // Leaf zkVerify attests for a proof of the guest committing `_journal`
+    function statementHash(bytes calldata _journal) public view returns (bytes32) {
+        return
+            keccak256(
+                abi.encodePacked(
+                    PROVING_SYSTEM_ID,
+                    imageId,
+                    VERSION_HASH,
+                    keccak256(_journal)
+                )
+            );
+    }
+
+    // Pays out the winners and market outcomes of a proven journal. The journal must rank the
+    // round anchored when the window started and name the window, and its addresses and markets
+    // must be the window's, in the AddressLogger's order.
     function verifyWinnersAndProcess(
-        bytes32 _leaf,
         uint256 _attestationId,
         bytes32[] calldata _merklePath,
         uint256 _leafCount,
         uint256 _index,
-        bool[] memory winners
+        bytes calldata _journal
     ) public {
-        bool verified = IZkVerifyAttestation(ZKVERIFY).verifyProofAttestation(
+        bytes32 leaf = statementHash(_journal);
+        bool verified = IZkVerifyAttestation(zkVerify).verifyProofAttestation(
             _attestationId,
-            _leaf,
+            leaf,
             _merklePath,
             _leafCount,
             _index
@@ -43,16 +84,71 @@ contract VerificationAndPrize {
 
         if (!verified) {
             emit VerificationFailed(
-                _leaf,
+                leaf,
                 _attestationId,
                 "Proof verification failed"
             );
             revert("Invalid proof");
         }
 
-        emit ProofVerified(_leaf, _attestationId, _index);
+        emit ProofVerified(leaf, _attestationId, _index);
+
+        TopHalfJournal memory journal = abi.decode(_journal, (TopHalfJournal));
+        IAddressLogger logger = IAddressLogger(address_logger);
+        require(
+            journal.roundHash == logger.getRoundHash(),
+            "Journal round does not match the window"
+        );
+        require(
+            journal.windowNumber == logger.getWindowNumber(),
+            "Journal window does not match the window"
+        );
+        checkAddresses(logger, journal.addresses);
+        uint8[] memory outcomes = marketOutcomes(logger, journal.markets);
+
+        logger.processPayouts(journal.winners);
+        emit PayoutsProcessed(journal.winners.length);
+        if (outcomes.length > 0) {
+            logger.processMarketPayouts(outcomes);
+        }
+    }
+
+    function checkAddresses(
+        IAddressLogger logger,
+        address[] memory addresses
+    ) internal view {
+        require(
+            addresses.length == logger.getValidAddressCount(),
+            "Journal addresses do not match the window"
+        );
+        for (uint256 i = 0; i < addresses.length; i++) {
+            require(
+                addresses[i] == logger.getValidAddress(i),
+                "Journal addresses do not match the window"
+            );
+        }
+    }
 
-        IAddressLogger(address_logger).processPayouts(winners);
-        emit PayoutsProcessed(winners.length);
+    // Winning outcome of each of the window's markets
+    function marketOutcomes(
+        IAddressLogger logger,
+        MarketResult[] memory markets
+    ) internal view returns (uint8[] memory outcomes) {
+        require(
+            markets.length == logger.getMarketCount(),
+            "Journal markets do not match the window"
+        );
+        outcomes = new uint8[](markets.length);
+        for (uint256 i = 0; i < markets.length; i++) {
+            (uint8 kind, address selectedAddress, uint256 param, ) = logger
+                .getMarket(i);
+            require(
+                markets[i].kind == kind &&
+                    markets[i].selectedAddress == selectedAddress &&
+                    markets[i].param == param,
+                "Journal markets do not match the window"
+            );
+            outcomes[i] = markets[i].outcome;
+        }
     }
 }
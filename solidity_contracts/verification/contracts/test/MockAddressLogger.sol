@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.13;
+
+// A window of the AddressLogger, recording the payouts it is asked to process
+contract MockAddressLogger {
+    struct Market {
+        uint8 kind;
+        address selectedAddress;
+        uint256 param;
+        uint8 outcomes;
+    }
+
+    bytes32 public roundHash;
+    uint256 public windowNumber;
+    address[] public validAddresses;
+    Market[] public markets;
+    bool[] public winners;
+    uint8[] public marketOutcomes;
+    bool public payoutsProcessed;
+
+    constructor(
+        bytes32 _roundHash,
+        uint256 _windowNumber,
+        address[] memory _validAddresses
+    ) {
+        roundHash = _roundHash;
+        windowNumber = _windowNumber;
+        validAddresses = _validAddresses;
+    }
+
+    function addMarket(
+        uint8 kind,
+        address selectedAddress,
+        uint256 param,
+        uint8 outcomes
+    ) external {
+        markets.push(Market(kind, selectedAddress, param, outcomes));
+    }
+
+    function processPayouts(bool[] memory _winners) external {
+        require(
+            _winners.length == validAddresses.length,
+            "Invalid winners array length"
+        );
+        winners = _winners;
+        payoutsProcessed = true;
+    }
+
+    function processMarketPayouts(uint8[] memory _outcomes) external {
+        require(
+            _outcomes.length == markets.length,
+            "Invalid outcomes array length"
+        );
+        marketOutcomes = _outcomes;
+    }
+
+    function getRoundHash() external view returns (bytes32) {
+        return roundHash;
+    }
+
+    function getWindowNumber() external view returns (uint256) {
+        return windowNumber;
+    }
+
+    function getValidAddressCount() external view returns (uint256) {
+        return validAddresses.length;
+    }
+
+    function getValidAddress(uint256 index) external view returns (address) {
+        return validAddresses[index];
+    }
+
+    function getMarketCount() external view returns (uint256) {
+        return markets.length;
+    }
+
+    function getMarket(
+        uint256 index
+    ) external view returns (uint8, address, uint256, uint8) {
+        Market memory market = markets[index];
+        return (
+            market.kind,
+            market.selectedAddress,
+            market.param,
+            market.outcomes
+        );
+    }
+
+    function getWinners() external view returns (bool[] memory) {
+        return winners;
+    }
+
+    function getMarketOutcomes() external view returns (uint8[] memory) {
+        return marketOutcomes;
+    }
+}
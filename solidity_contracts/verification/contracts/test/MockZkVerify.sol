@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.13;
+
+// Attests the leaves it was given
+contract MockZkVerify {
+    mapping(bytes32 => bool) public leaves;
+
+    function attest(bytes32 _leaf) external {
+        leaves[_leaf] = true;
+    }
+
+    function verifyProofAttestation(
+        uint256,
+        bytes32 _leaf,
+        bytes32[] calldata,
+        uint256,
+        uint256
+    ) external view returns (bool) {
+        return leaves[_leaf];
+    }
+}
@@ -1,8 +1,16 @@
 const { buildModule } = require("@nomicfoundation/hardhat-ignition/modules");
 
 const VerificationAndPrizeModule = buildModule("VerificationAndPrize", (m) => {
+  const zkVerify = m.getParameter(
+    "zkVerify",
+    "0x82941a739E74eBFaC72D0d0f8E81B1Dac2f586D5"
+  );
+  // Image ID of the prover's top-half guest, see GET /image_ids
+  const imageId = m.getParameter("imageId");
   const verificationAndPrize = m.contract("VerificationAndPrize", [
+    zkVerify,
     "0xaedda71dee300fdcae185407aa30b75b254f4caf",
+    imageId,
   ]);
   return { verificationAndPrize };
 });
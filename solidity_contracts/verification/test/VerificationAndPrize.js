@@ -0,0 +1,143 @@
+const { expect } = require("chai");
+const { ethers } = require("hardhat");
+// A fixture, not a proof: the journal the top-half guest commits for these inputs, checked
+// against (and regenerated from) the guest by the prover host's tests. zkVerify is mocked, so the
+// tests attest the journal themselves
+const fixture = require("./fixtures/top_half_journal.json");
+
+const IMAGE_ID = ethers.id("top-half guest");
+const JOURNAL_TYPE = [
+  "tuple(bytes32 roundHash, uint256 windowNumber, address[] addresses, bool[] winners, tuple(uint8 kind, address selectedAddress, uint256 param, uint8 outcome)[] markets)",
+];
+
+describe("VerificationAndPrize", function () {
+  async function deploy({
+    roundHash = fixture.round_hash,
+    windowNumber = fixture.window_number,
+    addresses = fixture.addresses,
+  } = {}) {
+    const zkVerify = await ethers.deployContract("MockZkVerify");
+    const logger = await ethers.deployContract("MockAddressLogger", [
+      roundHash,
+      windowNumber,
+      addresses,
+    ]);
+    for (const market of fixture.market_results) {
+      await logger.addMarket(
+        market.kind,
+        market.selectedAddress,
+        market.param,
+        market.outcomes
+      );
+    }
+    const prize = await ethers.deployContract("VerificationAndPrize", [
+      await zkVerify.getAddress(),
+      await logger.getAddress(),
+      IMAGE_ID,
+    ]);
+    return { zkVerify, logger, prize };
+  }
+
+  async function attest(zkVerify, prize, journal) {
+    const leaf = await prize.statementHash(journal);
+    await zkVerify.attest(leaf);
+    return leaf;
+  }
+
+  it("computes zkVerify's leaf from the journal", async function () {
+    const { prize } = await deploy();
+    const leaf = ethers.solidityPackedKeccak256(
+      ["bytes32", "bytes32", "bytes32", "bytes32"],
+      [
+        ethers.id("risc0"),
+        IMAGE_ID,
+        ethers.sha256(ethers.toUtf8Bytes("risc0:v1.2")),
+        ethers.keccak256(fixture.journal),
+      ]
+    );
+    expect(await prize.statementHash(fixture.journal)).to.equal(leaf);
+  });
+
+  it("pays out the winners and market outcomes decoded from the journal", async function () {
+    const { zkVerify, logger, prize } = await deploy();
+    const leaf = await attest(zkVerify, prize, fixture.journal);
+
+    await expect(prize.verifyWinnersAndProcess(7, [], 1, 0, fixture.journal))
+      .to.emit(prize, "ProofVerified")
+      .withArgs(leaf, 7, 0)
+      .and.to.emit(prize, "PayoutsProcessed")
+      .withArgs(fixture.winners.length);
+
+    expect(await logger.getWinners()).to.deep.equal(fixture.winners);
+    expect(await logger.getMarketOutcomes()).to.deep.equal(
+      fixture.market_results.map((market) => BigInt(market.outcome))
+    );
+  });
+
+  it("refuses winners the proof didn't commit", async function () {
+    const { zkVerify, logger, prize } = await deploy();
+    await attest(zkVerify, prize, fixture.journal);
+
+    const coder = ethers.AbiCoder.defaultAbiCoder();
+    const [journal] = coder.decode(JOURNAL_TYPE, fixture.journal);
+    const winners = journal.winners.map((winner) => !winner);
+    const forged = coder.encode(JOURNAL_TYPE, [
+      [
+        journal.roundHash,
+        journal.windowNumber,
+        journal.addresses,
+        winners,
+        journal.markets,
+      ],
+    ]);
+
+    await expect(
+      prize.verifyWinnersAndProcess(7, [], 1, 0, forged)
+    ).to.be.revertedWith("Invalid proof");
+    expect(await logger.payoutsProcessed()).to.equal(false);
+  });
+
+  it("refuses a journal ranking another round", async function () {
+    const { zkVerify, logger, prize } = await deploy({
+      roundHash: ethers.id("another round"),
+    });
+    await attest(zkVerify, prize, fixture.journal);
+
+    await expect(
+      prize.verifyWinnersAndProcess(7, [], 1, 0, fixture.journal)
+    ).to.be.revertedWith("Journal round does not match the window");
+    expect(await logger.payoutsProcessed()).to.equal(false);
+  });
+
+  it("refuses a journal of a past window", async function () {
+    const { zkVerify, logger, prize } = await deploy({
+      windowNumber: fixture.window_number + 1,
+    });
+    await attest(zkVerify, prize, fixture.journal);
+
+    await expect(
+      prize.verifyWinnersAndProcess(7, [], 1, 0, fixture.journal)
+    ).to.be.revertedWith("Journal window does not match the window");
+    expect(await logger.payoutsProcessed()).to.equal(false);
+  });
+
+  it("refuses a journal of other addresses", async function () {
+    const reordered = [...fixture.addresses].reverse();
+    const { zkVerify, prize } = await deploy({ addresses: reordered });
+    await attest(zkVerify, prize, fixture.journal);
+
+    await expect(
+      prize.verifyWinnersAndProcess(7, [], 1, 0, fixture.journal)
+    ).to.be.revertedWith("Journal addresses do not match the window");
+  });
+
+  it("refuses a journal whose markets aren't the window's", async function () {
+    const { zkVerify, logger, prize } = await deploy();
+    await logger.addMarket(3, fixture.addresses[0], fixture.addresses[1], 2);
+    await attest(zkVerify, prize, fixture.journal);
+
+    await expect(
+      prize.verifyWinnersAndProcess(7, [], 1, 0, fixture.journal)
+    ).to.be.revertedWith("Journal markets do not match the window");
+  });
+});